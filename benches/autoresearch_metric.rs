@@ -27,13 +27,13 @@ fn mixed_workload(c: &mut Criterion) {
         let rt = runtime();
         let mut client = Client::new();
         block_on(&rt, client.start());
-        let _ = block_on(&rt, client.flushdb());
+        let _: Result<(), _> = block_on(&rt, client.flushdb());
 
         // Pre-populate some data for GETs
         block_on(&rt, async {
             for i in 0..100 {
-                client.set(format!("key{}", i), "value").await.unwrap();
-                client
+                let _: () = client.set(format!("key{}", i), "value").await.unwrap();
+                let _: () = client
                     .hset("myhash", format!("field{}", i), "value")
                     .await
                     .unwrap();
@@ -47,7 +47,7 @@ fn mixed_workload(c: &mut Criterion) {
                     match i % 10 {
                         0..=3 => {
                             // SET
-                            client.set(format!("key{}", i), "value").await.unwrap();
+                            let _: () = client.set(format!("key{}", i), "value").await.unwrap();
                         }
                         4..=7 => {
                             // GET
@@ -55,7 +55,7 @@ fn mixed_workload(c: &mut Criterion) {
                         }
                         8 => {
                             // HSET
-                            client
+                            let _: () = client
                                 .hset("myhash", format!("field{}", i), "value")
                                 .await
                                 .unwrap();
@@ -71,13 +71,13 @@ fn mixed_workload(c: &mut Criterion) {
                     }
                 }
             });
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
 
             // Re-populate for next iteration
             block_on(&rt, async {
                 for i in 0..100 {
-                    client.set(format!("key{}", i), "value").await.unwrap();
-                    client
+                    let _: () = client.set(format!("key{}", i), "value").await.unwrap();
+                    let _: () = client
                         .hset("myhash", format!("field{}", i), "value")
                         .await
                         .unwrap();
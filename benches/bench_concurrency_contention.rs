@@ -23,13 +23,13 @@ fn high_contention_same_key(c: &mut Criterion) {
                 let rt = runtime();
                 let storage = StorageEngine::new();
                 block_on(&rt, async {
-                    storage.start_expiration_sweeper().await;
+                    storage.start_expiration_sweeper();
                 });
 
                 // Pre-populate data
                 block_on(&rt, async {
                     let mut client = Client::from_storage(storage.clone());
-                    client.set("contended_key", "initial").await.unwrap();
+                    let _: () = client.set("contended_key", "initial").await.unwrap();
                 });
 
                 b.iter(|| {
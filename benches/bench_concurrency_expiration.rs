@@ -0,0 +1,132 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use not_redis::{Client, RedisData, StorageEngine};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::runtime::Runtime;
+use tokio::task::JoinSet;
+
+fn runtime() -> Runtime {
+    Runtime::new().unwrap()
+}
+
+fn block_on<T>(rt: &Runtime, fut: impl std::future::Future<Output = T>) -> T {
+    rt.block_on(fut)
+}
+
+/// Returns a deadline `secs` from now, in Unix-epoch milliseconds.
+fn deadline_in(secs: u64) -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    now.as_millis() as u64 + secs * 1000
+}
+
+/// Fills the engine with `count` keys that expire an hour from now.
+fn populate_volatile(storage: &StorageEngine, count: usize) {
+    let deadline = deadline_in(3600);
+    for i in 0..count {
+        storage.set(
+            format!("session:{}", i),
+            RedisData::String(b"payload".to_vec()),
+            Some(deadline),
+        );
+    }
+}
+
+/// Overwrites volatile keys, which cancels and reschedules their expiration,
+/// while the index already holds `volatile` other deadlines.
+fn overwrite_volatile_keys(c: &mut Criterion) {
+    let mut group = c.benchmark_group("concurrency/expiration/overwrite_volatile");
+    let num_tasks = 50;
+    let ops_per_task = 100;
+
+    for volatile in [1_000, 100_000] {
+        group.throughput(Throughput::Elements((num_tasks * ops_per_task) as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(volatile),
+            &volatile,
+            |b, &volatile| {
+                let rt = runtime();
+                let storage = StorageEngine::new();
+                block_on(&rt, async {
+                    storage.start_expiration_sweeper();
+                });
+                populate_volatile(&storage, volatile);
+
+                b.iter(|| {
+                    block_on(&rt, async {
+                        let mut set = JoinSet::new();
+                        for task_id in 0..num_tasks {
+                            let storage = storage.clone();
+                            set.spawn(async move {
+                                let deadline = deadline_in(60);
+                                for i in 0..ops_per_task {
+                                    storage.set(
+                                        format!(
+                                            "session:{}",
+                                            (task_id * ops_per_task + i) % volatile
+                                        ),
+                                        RedisData::String(b"refreshed".to_vec()),
+                                        Some(deadline),
+                                    );
+                                }
+                            });
+                        }
+                        while set.join_next().await.is_some() {}
+                    });
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Sets and clears TTLs through the client while the index already holds
+/// `volatile` other deadlines.
+fn expire_and_persist(c: &mut Criterion) {
+    let mut group = c.benchmark_group("concurrency/expiration/expire_persist");
+    let num_tasks = 50;
+    let ops_per_task = 100;
+
+    for volatile in [1_000, 100_000] {
+        group.throughput(Throughput::Elements((num_tasks * ops_per_task * 2) as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(volatile),
+            &volatile,
+            |b, &volatile| {
+                let rt = runtime();
+                let storage = StorageEngine::new();
+                block_on(&rt, async {
+                    storage.start_expiration_sweeper();
+                });
+                populate_volatile(&storage, volatile);
+
+                b.iter(|| {
+                    block_on(&rt, async {
+                        let mut set = JoinSet::new();
+                        for task_id in 0..num_tasks {
+                            let storage = storage.clone();
+                            set.spawn(async move {
+                                let mut client = Client::from_storage(storage);
+                                for i in 0..ops_per_task {
+                                    let key = format!(
+                                        "session:{}",
+                                        (task_id * ops_per_task + i) % volatile
+                                    );
+                                    client.expire(&key, 600).await.unwrap();
+                                    client.persist(&key).await.unwrap();
+                                }
+                            });
+                        }
+                        while set.join_next().await.is_some() {}
+                    });
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = overwrite_volatile_keys, expire_and_persist
+}
+criterion_main!(benches);
@@ -23,7 +23,7 @@ fn concurrent_hash_ops(c: &mut Criterion) {
                 let rt = runtime();
                 let storage = StorageEngine::new();
                 block_on(&rt, async {
-                    storage.start_expiration_sweeper().await;
+                    storage.start_expiration_sweeper();
                 });
 
                 b.iter(|| {
@@ -33,14 +33,14 @@ fn concurrent_hash_ops(c: &mut Criterion) {
                             let storage = storage.clone();
                             set.spawn(async move {
                                 let mut client = Client::from_storage(storage);
-                                client
+                                let _: () = client
                                     .hset("myhash", format!("field{}", i), "value")
                                     .await
                                     .unwrap();
                             });
                         }
                         while set.join_next().await.is_some() {}
-                        let _ = storage.flush();
+                        storage.flush();
                     });
                 });
             },
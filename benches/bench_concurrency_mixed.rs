@@ -23,14 +23,14 @@ fn concurrent_mixed_read_write(c: &mut Criterion) {
                 let rt = runtime();
                 let storage = StorageEngine::new();
                 block_on(&rt, async {
-                    storage.start_expiration_sweeper().await;
+                    storage.start_expiration_sweeper();
                 });
 
                 // Pre-populate data
                 block_on(&rt, async {
                     let mut client = Client::from_storage(storage.clone());
                     for i in 0..num_tasks {
-                        client.set(format!("key{}", i), "value").await.unwrap();
+                        let _: () = client.set(format!("key{}", i), "value").await.unwrap();
                     }
                 });
 
@@ -42,7 +42,8 @@ fn concurrent_mixed_read_write(c: &mut Criterion) {
                             if i % 2 == 0 {
                                 set.spawn(async move {
                                     let mut client = Client::from_storage(storage);
-                                    client.set(format!("key{}", i), "newvalue").await.unwrap();
+                                    let _: () =
+                                        client.set(format!("key{}", i), "newvalue").await.unwrap();
                                 });
                             } else {
                                 set.spawn(async move {
@@ -23,7 +23,7 @@ fn concurrent_string_set_different_keys(c: &mut Criterion) {
                 let rt = runtime();
                 let storage = StorageEngine::new();
                 block_on(&rt, async {
-                    storage.start_expiration_sweeper().await;
+                    storage.start_expiration_sweeper();
                 });
 
                 b.iter(|| {
@@ -33,11 +33,11 @@ fn concurrent_string_set_different_keys(c: &mut Criterion) {
                             let storage = storage.clone();
                             set.spawn(async move {
                                 let mut client = Client::from_storage(storage);
-                                client.set(format!("key{}", i), "value").await.unwrap();
+                                let _: () = client.set(format!("key{}", i), "value").await.unwrap();
                             });
                         }
                         while set.join_next().await.is_some() {}
-                        let _ = storage.flush();
+                        storage.flush();
                     });
                 });
             },
@@ -58,7 +58,7 @@ fn concurrent_string_set_same_key(c: &mut Criterion) {
                 let rt = runtime();
                 let storage = StorageEngine::new();
                 block_on(&rt, async {
-                    storage.start_expiration_sweeper().await;
+                    storage.start_expiration_sweeper();
                 });
 
                 b.iter(|| {
@@ -68,11 +68,11 @@ fn concurrent_string_set_same_key(c: &mut Criterion) {
                             let storage = storage.clone();
                             set.spawn(async move {
                                 let mut client = Client::from_storage(storage);
-                                client.set("same_key", "value").await.unwrap();
+                                let _: () = client.set("same_key", "value").await.unwrap();
                             });
                         }
                         while set.join_next().await.is_some() {}
-                        let _ = storage.flush();
+                        storage.flush();
                     });
                 });
             },
@@ -93,14 +93,14 @@ fn concurrent_string_get_different_keys(c: &mut Criterion) {
                 let rt = runtime();
                 let storage = StorageEngine::new();
                 block_on(&rt, async {
-                    storage.start_expiration_sweeper().await;
+                    storage.start_expiration_sweeper();
                 });
 
                 // Pre-populate data
                 block_on(&rt, async {
                     let mut client = Client::from_storage(storage.clone());
                     for i in 0..num_tasks {
-                        client.set(format!("key{}", i), "value").await.unwrap();
+                        let _: () = client.set(format!("key{}", i), "value").await.unwrap();
                     }
                 });
 
@@ -135,13 +135,13 @@ fn concurrent_string_get_same_key(c: &mut Criterion) {
                 let rt = runtime();
                 let storage = StorageEngine::new();
                 block_on(&rt, async {
-                    storage.start_expiration_sweeper().await;
+                    storage.start_expiration_sweeper();
                 });
 
                 // Pre-populate data
                 block_on(&rt, async {
                     let mut client = Client::from_storage(storage.clone());
-                    client.set("same_key", "value").await.unwrap();
+                    let _: () = client.set("same_key", "value").await.unwrap();
                 });
 
                 b.iter(|| {
@@ -17,13 +17,13 @@ fn hash_set(c: &mut Criterion) {
         let rt = runtime();
         let mut client = Client::new();
         block_on(&rt, client.start());
-        let _ = block_on(&rt, client.flushdb());
+        let _: Result<(), _> = block_on(&rt, client.flushdb());
 
         b.iter(|| {
             block_on(&rt, async {
-                client.hset("myhash", "field", "value").await.unwrap();
+                let _: () = client.hset("myhash", "field", "value").await.unwrap();
             });
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
         });
     });
 
@@ -31,7 +31,7 @@ fn hash_set(c: &mut Criterion) {
         let rt = runtime();
         let mut client = Client::new();
         block_on(&rt, client.start());
-        let _ = block_on(&rt, client.hset("myhash", "field", "value"));
+        let _: Result<(), _> = block_on(&rt, client.hset("myhash", "field", "value"));
 
         b.iter(|| {
             block_on(&rt, async {
@@ -44,13 +44,13 @@ fn hash_set(c: &mut Criterion) {
         let rt = runtime();
         let mut client = Client::new();
         block_on(&rt, client.start());
-        let _ = block_on(&rt, client.flushdb());
+        let _: Result<(), _> = block_on(&rt, client.flushdb());
 
         b.iter(|| {
             block_on(&rt, async {
                 let _: String = client.hget("myhash", "nonexistent").await.unwrap();
             });
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
         });
     });
 
@@ -17,13 +17,13 @@ fn list_ops(c: &mut Criterion) {
         let rt = runtime();
         let mut client = Client::new();
         block_on(&rt, client.start());
-        let _ = block_on(&rt, client.flushdb());
+        let _: Result<(), _> = block_on(&rt, client.flushdb());
 
         b.iter(|| {
             block_on(&rt, async {
-                client.lpush("mylist", "value").await.unwrap();
+                let _: () = client.lpush("mylist", "value").await.unwrap();
             });
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
         });
     });
 
@@ -31,13 +31,13 @@ fn list_ops(c: &mut Criterion) {
         let rt = runtime();
         let mut client = Client::new();
         block_on(&rt, client.start());
-        let _ = block_on(&rt, client.flushdb());
+        let _: Result<(), _> = block_on(&rt, client.flushdb());
 
         b.iter(|| {
             block_on(&rt, async {
-                client.rpush("mylist", "value").await.unwrap();
+                let _: () = client.rpush("mylist", "value").await.unwrap();
             });
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
         });
     });
 
@@ -45,7 +45,7 @@ fn list_ops(c: &mut Criterion) {
         let rt = runtime();
         let mut client = Client::new();
         block_on(&rt, client.start());
-        let _ = block_on(&rt, client.lpush("mylist", "value"));
+        let _: Result<(), _> = block_on(&rt, client.lpush("mylist", "value"));
 
         b.iter(|| {
             block_on(&rt, async {
@@ -17,13 +17,13 @@ fn set_ops(c: &mut Criterion) {
         let rt = runtime();
         let mut client = Client::new();
         block_on(&rt, client.start());
-        let _ = block_on(&rt, client.flushdb());
+        let _: Result<(), _> = block_on(&rt, client.flushdb());
 
         b.iter(|| {
             block_on(&rt, async {
-                client.sadd("myset", "member").await.unwrap();
+                let _: () = client.sadd("myset", "member").await.unwrap();
             });
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
         });
     });
 
@@ -31,7 +31,7 @@ fn set_ops(c: &mut Criterion) {
         let rt = runtime();
         let mut client = Client::new();
         block_on(&rt, client.start());
-        let _ = block_on(&rt, client.sadd("myset", "member"));
+        let _: Result<(), _> = block_on(&rt, client.sadd("myset", "member"));
 
         b.iter(|| {
             block_on(&rt, async {
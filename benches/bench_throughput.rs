@@ -22,15 +22,15 @@ fn batch_writes(c: &mut Criterion) {
                 let rt = runtime();
                 let mut client = Client::new();
                 block_on(&rt, client.start());
-                let _ = block_on(&rt, client.flushdb());
+                let _: Result<(), _> = block_on(&rt, client.flushdb());
 
                 b.iter(move || {
                     block_on(&rt, async {
                         for i in 0..batch_size {
-                            client.set(format!("key{}", i), "value").await.unwrap();
+                            let _: () = client.set(format!("key{}", i), "value").await.unwrap();
                         }
                     });
-                    let _ = block_on(&rt, client.flushdb());
+                    let _: Result<(), _> = block_on(&rt, client.flushdb());
                 });
             },
         );
@@ -53,7 +53,7 @@ fn batch_reads(c: &mut Criterion) {
 
                 // Pre-populate data
                 for i in 0..batch_size {
-                    let _ = block_on(&rt, client.set(format!("key{}", i), "value"));
+                    let _: Result<(), _> = block_on(&rt, client.set(format!("key{}", i), "value"));
                 }
 
                 b.iter(move || {
@@ -76,18 +76,18 @@ fn sequential_operations(c: &mut Criterion) {
         let rt = runtime();
         let mut client = Client::new();
         block_on(&rt, client.start());
-        let _ = block_on(&rt, client.flushdb());
+        let _: Result<(), _> = block_on(&rt, client.flushdb());
 
         b.iter(|| {
             block_on(&rt, async {
                 for i in 0..1000 {
-                    client.set(format!("key{}", i), "value").await.unwrap();
+                    let _: () = client.set(format!("key{}", i), "value").await.unwrap();
                 }
                 for i in 0..1000 {
                     let _: String = client.get(format!("key{}", i)).await.unwrap();
                 }
             });
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
         });
     });
 
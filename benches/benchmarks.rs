@@ -21,13 +21,13 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
 
             b.iter(|| {
                 block_on(&rt, async {
-                    client.set("key", "value").await.unwrap();
+                    let _: () = client.set("key", "value").await.unwrap();
                 });
-                let _ = block_on(&rt, client.flushdb());
+                let _: Result<(), _> = block_on(&rt, client.flushdb());
             });
         });
 
@@ -35,7 +35,7 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.set("key", "value"));
+            let _: Result<(), _> = block_on(&rt, client.set("key", "value"));
 
             b.iter(|| {
                 block_on(&rt, async {
@@ -48,13 +48,13 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
 
             b.iter(|| {
                 block_on(&rt, async {
                     let _: String = client.get("nonexistent").await.unwrap();
                 });
-                let _ = block_on(&rt, client.flushdb());
+                let _: Result<(), _> = block_on(&rt, client.flushdb());
             });
         });
 
@@ -68,13 +68,13 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
 
             b.iter(|| {
                 block_on(&rt, async {
-                    client.hset("myhash", "field", "value").await.unwrap();
+                    let _: () = client.hset("myhash", "field", "value").await.unwrap();
                 });
-                let _ = block_on(&rt, client.flushdb());
+                let _: Result<(), _> = block_on(&rt, client.flushdb());
             });
         });
 
@@ -82,7 +82,7 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.hset("myhash", "field", "value"));
+            let _: Result<(), _> = block_on(&rt, client.hset("myhash", "field", "value"));
 
             b.iter(|| {
                 block_on(&rt, async {
@@ -95,13 +95,13 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
 
             b.iter(|| {
                 block_on(&rt, async {
                     let _: String = client.hget("myhash", "nonexistent").await.unwrap();
                 });
-                let _ = block_on(&rt, client.flushdb());
+                let _: Result<(), _> = block_on(&rt, client.flushdb());
             });
         });
 
@@ -115,13 +115,13 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
 
             b.iter(|| {
                 block_on(&rt, async {
-                    client.lpush("mylist", "value").await.unwrap();
+                    let _: () = client.lpush("mylist", "value").await.unwrap();
                 });
-                let _ = block_on(&rt, client.flushdb());
+                let _: Result<(), _> = block_on(&rt, client.flushdb());
             });
         });
 
@@ -129,13 +129,13 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
 
             b.iter(|| {
                 block_on(&rt, async {
-                    client.rpush("mylist", "value").await.unwrap();
+                    let _: () = client.rpush("mylist", "value").await.unwrap();
                 });
-                let _ = block_on(&rt, client.flushdb());
+                let _: Result<(), _> = block_on(&rt, client.flushdb());
             });
         });
 
@@ -143,7 +143,7 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.lpush("mylist", "value"));
+            let _: Result<(), _> = block_on(&rt, client.lpush("mylist", "value"));
 
             b.iter(|| {
                 block_on(&rt, async {
@@ -162,13 +162,13 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
 
             b.iter(|| {
                 block_on(&rt, async {
-                    client.sadd("myset", "member").await.unwrap();
+                    let _: () = client.sadd("myset", "member").await.unwrap();
                 });
-                let _ = block_on(&rt, client.flushdb());
+                let _: Result<(), _> = block_on(&rt, client.flushdb());
             });
         });
 
@@ -176,7 +176,7 @@ mod single_threaded {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.sadd("myset", "member"));
+            let _: Result<(), _> = block_on(&rt, client.sadd("myset", "member"));
 
             b.iter(|| {
                 block_on(&rt, async {
@@ -204,7 +204,7 @@ mod concurrency {
                     let rt = runtime();
                     let storage = StorageEngine::new();
                     block_on(&rt, async {
-                        storage.start_expiration_sweeper().await;
+                        storage.start_expiration_sweeper();
                     });
 
                     b.iter(|| {
@@ -214,11 +214,12 @@ mod concurrency {
                                 let storage = storage.clone();
                                 set.spawn(async move {
                                     let mut client = Client::from_storage(storage);
-                                    client.set(format!("key{}", i), "value").await.unwrap();
+                                    let _: () =
+                                        client.set(format!("key{}", i), "value").await.unwrap();
                                 });
                             }
                             while set.join_next().await.is_some() {}
-                            let _ = storage.flush();
+                            storage.flush();
                         });
                     });
                 },
@@ -239,7 +240,7 @@ mod concurrency {
                     let rt = runtime();
                     let storage = StorageEngine::new();
                     block_on(&rt, async {
-                        storage.start_expiration_sweeper().await;
+                        storage.start_expiration_sweeper();
                     });
 
                     b.iter(|| {
@@ -249,11 +250,11 @@ mod concurrency {
                                 let storage = storage.clone();
                                 set.spawn(async move {
                                     let mut client = Client::from_storage(storage);
-                                    client.set("same_key", "value").await.unwrap();
+                                    let _: () = client.set("same_key", "value").await.unwrap();
                                 });
                             }
                             while set.join_next().await.is_some() {}
-                            let _ = storage.flush();
+                            storage.flush();
                         });
                     });
                 },
@@ -274,14 +275,14 @@ mod concurrency {
                     let rt = runtime();
                     let storage = StorageEngine::new();
                     block_on(&rt, async {
-                        storage.start_expiration_sweeper().await;
+                        storage.start_expiration_sweeper();
                     });
 
                     // Pre-populate data
                     block_on(&rt, async {
                         let mut client = Client::from_storage(storage.clone());
                         for i in 0..num_tasks {
-                            client.set(format!("key{}", i), "value").await.unwrap();
+                            let _: () = client.set(format!("key{}", i), "value").await.unwrap();
                         }
                     });
 
@@ -316,13 +317,13 @@ mod concurrency {
                     let rt = runtime();
                     let storage = StorageEngine::new();
                     block_on(&rt, async {
-                        storage.start_expiration_sweeper().await;
+                        storage.start_expiration_sweeper();
                     });
 
                     // Pre-populate data
                     block_on(&rt, async {
                         let mut client = Client::from_storage(storage.clone());
-                        client.set("same_key", "value").await.unwrap();
+                        let _: () = client.set("same_key", "value").await.unwrap();
                     });
 
                     b.iter(|| {
@@ -356,7 +357,7 @@ mod concurrency {
                     let rt = runtime();
                     let storage = StorageEngine::new();
                     block_on(&rt, async {
-                        storage.start_expiration_sweeper().await;
+                        storage.start_expiration_sweeper();
                     });
 
                     b.iter(|| {
@@ -366,14 +367,14 @@ mod concurrency {
                                 let storage = storage.clone();
                                 set.spawn(async move {
                                     let mut client = Client::from_storage(storage);
-                                    client
+                                    let _: () = client
                                         .hset("myhash", format!("field{}", i), "value")
                                         .await
                                         .unwrap();
                                 });
                             }
                             while set.join_next().await.is_some() {}
-                            let _ = storage.flush();
+                            storage.flush();
                         });
                     });
                 },
@@ -394,14 +395,14 @@ mod concurrency {
                     let rt = runtime();
                     let storage = StorageEngine::new();
                     block_on(&rt, async {
-                        storage.start_expiration_sweeper().await;
+                        storage.start_expiration_sweeper();
                     });
 
                     // Pre-populate data
                     block_on(&rt, async {
                         let mut client = Client::from_storage(storage.clone());
                         for i in 0..num_tasks {
-                            client.set(format!("key{}", i), "value").await.unwrap();
+                            let _: () = client.set(format!("key{}", i), "value").await.unwrap();
                         }
                     });
 
@@ -413,7 +414,10 @@ mod concurrency {
                                 if i % 2 == 0 {
                                     set.spawn(async move {
                                         let mut client = Client::from_storage(storage);
-                                        client.set(format!("key{}", i), "newvalue").await.unwrap();
+                                        let _: () = client
+                                            .set(format!("key{}", i), "newvalue")
+                                            .await
+                                            .unwrap();
                                     });
                                 } else {
                                     set.spawn(async move {
@@ -444,7 +448,7 @@ mod concurrency {
                     let rt = runtime();
                     let storage = StorageEngine::new();
                     block_on(&rt, async {
-                        storage.start_expiration_sweeper().await;
+                        storage.start_expiration_sweeper();
                     });
 
                     b.iter(|| {
@@ -454,11 +458,14 @@ mod concurrency {
                                 let storage = storage.clone();
                                 set.spawn(async move {
                                     let mut client = Client::from_storage(storage);
-                                    client.lpush("mylist", format!("value{}", i)).await.unwrap();
+                                    let _: () = client
+                                        .lpush("mylist", format!("value{}", i))
+                                        .await
+                                        .unwrap();
                                 });
                             }
                             while set.join_next().await.is_some() {}
-                            let _ = storage.flush();
+                            storage.flush();
                         });
                     });
                 },
@@ -479,13 +486,13 @@ mod concurrency {
                     let rt = runtime();
                     let storage = StorageEngine::new();
                     block_on(&rt, async {
-                        storage.start_expiration_sweeper().await;
+                        storage.start_expiration_sweeper();
                     });
 
                     // Pre-populate data
                     block_on(&rt, async {
                         let mut client = Client::from_storage(storage.clone());
-                        client.set("contended_key", "initial").await.unwrap();
+                        let _: () = client.set("contended_key", "initial").await.unwrap();
                     });
 
                     b.iter(|| {
@@ -523,15 +530,15 @@ mod throughput {
                     let rt = runtime();
                     let mut client = Client::new();
                     block_on(&rt, client.start());
-                    let _ = block_on(&rt, client.flushdb());
+                    let _: Result<(), _> = block_on(&rt, client.flushdb());
 
                     b.iter(move || {
                         block_on(&rt, async {
                             for i in 0..batch_size {
-                                client.set(format!("key{}", i), "value").await.unwrap();
+                                let _: () = client.set(format!("key{}", i), "value").await.unwrap();
                             }
                         });
-                        let _ = block_on(&rt, client.flushdb());
+                        let _: Result<(), _> = block_on(&rt, client.flushdb());
                     });
                 },
             );
@@ -554,7 +561,8 @@ mod throughput {
 
                     // Pre-populate data
                     for i in 0..batch_size {
-                        let _ = block_on(&rt, client.set(format!("key{}", i), "value"));
+                        let _: Result<(), _> =
+                            block_on(&rt, client.set(format!("key{}", i), "value"));
                     }
 
                     b.iter(move || {
@@ -577,18 +585,18 @@ mod throughput {
             let rt = runtime();
             let mut client = Client::new();
             block_on(&rt, client.start());
-            let _ = block_on(&rt, client.flushdb());
+            let _: Result<(), _> = block_on(&rt, client.flushdb());
 
             b.iter(|| {
                 block_on(&rt, async {
                     for i in 0..1000 {
-                        client.set(format!("key{}", i), "value").await.unwrap();
+                        let _: () = client.set(format!("key{}", i), "value").await.unwrap();
                     }
                     for i in 0..1000 {
                         let _: String = client.get(format!("key{}", i)).await.unwrap();
                     }
                 });
-                let _ = block_on(&rt, client.flushdb());
+                let _: Result<(), _> = block_on(&rt, client.flushdb());
             });
         });
 
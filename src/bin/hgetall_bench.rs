@@ -2,16 +2,23 @@
 use not_redis::{Client, StorageEngine};
 use std::time::Instant;
 
-#[tokio::main]
-async fn main() {
+fn main() {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(run());
+}
+
+async fn run() {
     // Setup
     let storage = StorageEngine::new();
-    storage.start_expiration_sweeper().await;
+    storage.start_expiration_sweeper();
 
     // Pre-populate a hash with 100 fields
     let mut client = Client::from_storage(storage.clone());
     for i in 0..100 {
-        client
+        let _: () = client
             .hset("bench_hash", format!("field{}", i), format!("value{}", i))
             .await
             .unwrap();
@@ -47,7 +54,7 @@ async fn main() {
     let hset_iterations = 10_000;
     let hset_start = Instant::now();
     for i in 0..hset_iterations {
-        let _ = client2
+        let _: Result<(), _> = client2
             .hset("bench_hash", format!("field{}", i), "newvalue")
             .await;
     }
@@ -64,7 +71,7 @@ async fn main() {
     let hget_ns = hget_elapsed.as_nanos() / hget_iterations as u128;
 
     // string get benchmark
-    client2.set("string_key", "string_value").await.unwrap();
+    let _: () = client2.set("string_key", "string_value").await.unwrap();
     let string_get_iterations = 10_000;
     let string_get_start = Instant::now();
     for _ in 0..string_get_iterations {
@@ -78,7 +85,7 @@ async fn main() {
     let string_set_start = Instant::now();
     for _ in 0..string_set_iterations {
         let key = format!("key{}", rand::random::<u32>());
-        let _ = client2.set(key, "value").await.unwrap();
+        let _: () = client2.set(key, "value").await.unwrap();
     }
     let string_set_elapsed = string_set_start.elapsed();
     let string_set_ns = string_set_elapsed.as_nanos() / string_set_iterations as u128;
@@ -0,0 +1,100 @@
+//! A synchronous client for code that does not run inside an async runtime.
+//!
+//! [`Client`] runs commands through the [`Commands`](crate::Commands) trait,
+//! which has the same methods as the async
+//! [`not_redis::Client`](crate::Client), but every method returns directly.
+//! Both kinds of client can share one [`StorageEngine`]:
+//!
+//! ```rust
+//! use not_redis::{blocking, Commands, StorageEngine};
+//!
+//! let storage = StorageEngine::new();
+//! let async_client = not_redis::Client::from_storage(storage.clone());
+//! let mut client = blocking::Client::from_storage(storage);
+//! client.start();
+//!
+//! let _: () = client.set("key", "value").unwrap();
+//! let value: String = client.get("key").unwrap();
+//! assert_eq!(value, "value");
+//! # drop(async_client);
+//! ```
+
+use crate::commands::{execute_command, Cmd, ConnectionLike};
+use crate::error::RedisResult;
+use crate::runtime::{SweeperHandle, ThreadExecutor};
+use crate::storage::StorageEngine;
+use crate::types::Value;
+
+/// A synchronous Redis client for executing commands against an in-memory store.
+///
+/// Unlike the async [`Client`](crate::Client), this client needs no runtime:
+/// [`start`](Client::start) runs the expiration sweeper on its own thread.
+/// Its commands come from [`Commands`](crate::Commands).
+///
+/// # Example
+///
+/// ```rust
+/// use not_redis::blocking::Client;
+/// use not_redis::Commands;
+///
+/// let mut client = Client::new();
+/// client.start();
+/// let _: () = client.set("key", "value").unwrap();
+/// let value: String = client.get("key").unwrap();
+/// assert_eq!(value, "value");
+/// ```
+pub struct Client {
+    storage: StorageEngine,
+}
+
+impl Client {
+    /// Creates a new Client with a fresh in-memory storage engine.
+    pub fn new() -> Self {
+        Self {
+            storage: StorageEngine::new(),
+        }
+    }
+
+    /// Creates a new Client with an existing storage engine.
+    ///
+    /// This allows sharing a storage engine between multiple clients.
+    pub fn from_storage(storage: StorageEngine) -> Self {
+        Self { storage }
+    }
+
+    /// Returns a client that sees only the keys starting with `prefix`.
+    ///
+    /// See [`StorageEngine::namespace`]. The new client shares this client's
+    /// storage and starts on its current database.
+    pub fn namespace(&self, prefix: impl AsRef<[u8]>) -> Client {
+        Self {
+            storage: self.storage.namespace(prefix),
+        }
+    }
+
+    /// Starts the background expiration sweeper on a dedicated thread.
+    ///
+    /// Use [`StorageEngine::start_expiration_sweeper_on`] to run it on a
+    /// different [`Executor`](crate::runtime::Executor). Use the returned
+    /// handle to stop the sweeper; it also stops on its own once the client
+    /// and every other user of its storage have been dropped.
+    pub fn start(&self) -> SweeperHandle {
+        self.storage.start_expiration_sweeper_on(&ThreadExecutor)
+    }
+
+    pub(crate) fn storage(&self) -> &StorageEngine {
+        &self.storage
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConnectionLike for Client {
+    fn req_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        execute_command(&mut self.storage, cmd)
+    }
+}
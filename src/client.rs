@@ -1,444 +1,764 @@
+//! The [`Client`] type and the [`Commands`] trait.
+
 use std::time::Duration;
-use crate::storage::StorageEngine;
-use crate::storage::config::{MaxMemoryPolicy, StorageConfig};
-use crate::types::{ToRedisArgs, FromRedisValue, Value};
-use crate::error::{RedisResult, RedisError};
-use crate::commands::{Cmd, SetOptions, IntegerReplyOrNoOp, CopyOptions, execute_command};
 
-/// A trait defining Redis-compatible commands for a client.
-///
-/// This trait provides methods for all common Redis operations including
-/// strings, lists, sets, hashes, sorted sets, and server commands.
+use crate::blocking;
+use crate::clock::Clock;
+use crate::commands::{
+    cmd, BitFieldOps, BitUnit, Cmd, ConnectionLike, CopyOptions, Expiry,
+    HashFieldExpirationOptions, IntegerReplyOrNoOp, LposOptions, ScanOptions, SetOptions,
+};
+use crate::error::RedisResult;
+use crate::runtime::SweeperHandle;
+use crate::storage::{ExpireOption, HasherKind, StorageEngine, StorageEngineBuilder};
+use crate::types::{FromRedisValue, ToRedisArgs, Value};
+
+/// Writes each command once and generates both the [`Commands`] method and
+/// the `async` [`Client`] method from it.
 ///
-/// # Implementors
-///
-/// This trait is implemented by [`Client`] and can be implemented by other
-/// types that want to provide Redis-compatible command interfaces.
-pub trait Commands {
+/// An entry reads like a `Commands` method without its receiver and without
+/// `RedisResult` around the reply type. Its body builds the [`Cmd`] to run.
+macro_rules! implement_commands {
+    (
+        $(
+            $(#[$attr:meta])*
+            fn $name:ident<$($tyarg:ident: $bound:ident),* $(,)?>(
+                $($arg:ident: $argty:ty),* $(,)?
+            ) -> $ret:ty $body:block
+        )*
+    ) => {
+        /// A trait defining Redis-compatible commands for a client.
+        ///
+        /// This trait provides methods for all common Redis operations including
+        /// strings, lists, sets, hashes, sorted sets, streams and server commands.
+        ///
+        /// The methods are synchronous and, like the `redis` crate's `Commands`,
+        /// most are generic over their reply type: annotate the result with the
+        /// type to read the reply as, or `()` to ignore it. The `async` methods
+        /// on [`Client`] have the same signatures.
+        ///
+        /// # Implementors
+        ///
+        /// Every method builds a [`Cmd`] and runs it through
+        /// [`ConnectionLike::req_command`], so the trait is implemented for every
+        /// [`ConnectionLike`] type, including [`Client`] and
+        /// [`blocking::Client`].
+        ///
+        /// # Example
+        ///
+        /// ```rust
+        /// use not_redis::{Client, Commands, RedisResult};
+        ///
+        /// fn visit<C: Commands>(con: &mut C, page: &str) -> RedisResult<i64> {
+        ///     con.sadd("visited", page)
+        /// }
+        ///
+        /// let mut client = Client::new();
+        /// assert_eq!(visit(&mut client, "/home").unwrap(), 1);
+        /// ```
+        pub trait Commands: ConnectionLike + Sized {
+            $(
+                $(#[$attr])*
+                fn $name<$($tyarg: $bound),*>(
+                    &mut self,
+                    $($arg: $argty),*
+                ) -> RedisResult<$ret> {
+                    ($body).query(self)
+                }
+            )*
+        }
+
+        impl Client {
+            $(
+                $(#[$attr])*
+                pub async fn $name<$($tyarg: $bound),*>(
+                    &mut self,
+                    $($arg: $argty),*
+                ) -> RedisResult<$ret> {
+                    Commands::$name(&mut self.inner, $($arg),*)
+                }
+            )*
+        }
+    };
+}
+
+implement_commands! {
     /// Gets the value of a key.
     ///
-    /// Returns `None` if the key doesn't exist or the value is not a string.
-    fn get<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    /// Returns null if the key doesn't exist.
+    fn get<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("GET").arg(key)
+    }
 
     /// Sets the value of a key.
     ///
     /// Returns an OK string on success.
-    fn set<K, V, RV>(&mut self, key: K, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    fn set<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, value: V) -> RV {
+        cmd("SET").arg(key).arg(value)
+    }
 
-    /// Sets the value of a key with additional options.
+    /// Sets a key-value pair with the given [`SetOptions`].
     ///
-    /// Options include expiration time (EX/PX), and conditional flags (NX/XX).
-    fn set_options<K, V, RV>(
-        &mut self,
+    /// Replies with `OK`, or null if the `NX`/`XX` condition failed; read it
+    /// as a `bool` to learn whether the write happened. With
+    /// [`SetOptions::get`], replies with the previous value instead.
+    fn set_options<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
         key: K,
         value: V,
         options: SetOptions,
-    ) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    ) -> RV {
+        let mut cmd = cmd("SET");
+        cmd.arg(key).arg(value);
+        options.write_args(&mut cmd);
+        cmd
+    }
 
-    /// Sets multiple key-value pairs at once.
+    /// Sets the value of a key only if the key does not exist.
     ///
-    /// This operation is atomic - either all keys are set or none are.
-    fn mset<K, V, RV>(&mut self, items: &[(K, V)]) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    /// Returns `1` if the key was set, `0` otherwise.
+    fn set_nx<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, value: V) -> RV {
+        cmd("SETNX").arg(key).arg(value)
+    }
 
-    /// Gets the values of all specified keys.
+    /// Sets a key with a time to live in seconds.
     ///
-    /// Returns a vector of values in the order of the requested keys.
-    fn mget<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    /// Fails if `seconds` is zero.
+    fn set_ex<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        value: V,
+        seconds: u64,
+    ) -> RV {
+        cmd("SETEX").arg(key).arg(seconds).arg(value)
+    }
 
-    /// Deletes one or more keys.
+    /// Sets a key with a time to live in milliseconds.
     ///
-    /// Returns the number of keys that were deleted.
-    fn del<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    /// Fails if `ms` is zero.
+    fn pset_ex<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        value: V,
+        ms: u64,
+    ) -> RV {
+        cmd("PSETEX").arg(key).arg(ms).arg(value)
+    }
 
-    /// Checks if one or more keys exist.
+    /// Sets a key and returns its previous value, or null.
+    fn getset<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, value: V) -> RV {
+        cmd("GETSET").arg(key).arg(value)
+    }
+
+    /// Deletes a key and returns its value, or null if it didn't exist.
+    fn get_del<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("GETDEL").arg(key)
+    }
+
+    /// Gets the value of a key and changes its expiration.
     ///
-    /// Returns the number of keys that exist.
-    fn exists<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    /// [`Expiry::PERSIST`] removes the key's time to live.
+    fn get_ex<K: ToRedisArgs, RV: FromRedisValue>(key: K, expiry: Expiry) -> RV {
+        let mut cmd = cmd("GETEX");
+        cmd.arg(key);
+        expiry.write_args(&mut cmd);
+        cmd
+    }
 
-    /// Appends a value to the end of a string.
+    /// Sets several key-value pairs at once.
+    ///
+    /// No other multi-key command sees some of the keys set and others not.
+    fn mset<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(items: &[(K, V)]) -> RV {
+        let mut cmd = cmd("MSET");
+        for (k, v) in items {
+            cmd.arg(k).arg(v);
+        }
+        cmd
+    }
+
+    /// Sets multiple key-value pairs, unless any of the keys already exists.
+    ///
+    /// Returns `1` if the keys were set, `0` otherwise.
+    fn mset_nx<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(items: &[(K, V)]) -> RV {
+        let mut cmd = cmd("MSETNX");
+        for (k, v) in items {
+            cmd.arg(k).arg(v);
+        }
+        cmd
+    }
+
+    /// Gets the values of several keys.
+    ///
+    /// Returns `None` for keys that are missing or don't hold a string.
+    fn mget<K: ToRedisArgs, RV: FromRedisValue>(keys: K) -> RV {
+        cmd("MGET").arg(keys)
+    }
+
+    /// Deletes one or more keys from the database.
+    ///
+    /// Pass a slice, array or `Vec` to delete several keys at once. Returns
+    /// the number of keys that were deleted.
+    fn del<K: ToRedisArgs, RV: FromRedisValue>(keys: K) -> RV {
+        cmd("DEL").arg(keys)
+    }
+
+    /// Checks if one or more keys exist in the database.
+    ///
+    /// Returns the number of keys that exist; a key given twice is counted
+    /// twice.
+    fn exists<K: ToRedisArgs, RV: FromRedisValue>(keys: K) -> RV {
+        cmd("EXISTS").arg(keys)
+    }
+
+    /// Touches one or more keys.
+    ///
+    /// Keys have no access time here, so this only returns the number of
+    /// keys that exist.
+    fn touch<K: ToRedisArgs, RV: FromRedisValue>(keys: K) -> RV {
+        cmd("TOUCH").arg(keys)
+    }
+
+    /// Appends a value to the string at a key, creating it if missing.
     ///
     /// Returns the length of the string after the append.
-    fn append<K, V, RV>(&mut self, key: K, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    fn append<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, value: V) -> RV {
+        cmd("APPEND").arg(key).arg(value)
+    }
 
-    /// Returns a substring of the string value.
+    /// Returns the part of a string between `start` and `end`, inclusive.
     ///
-    /// # Arguments
-    /// * `key` - The key
-    /// * `from` - Start index (can be negative)
-    /// * `to` - End index (can be negative)
-    fn getrange<K, RV>(&mut self, key: K, from: isize, to: isize) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Overwrites part of a string starting at the specified offset.
-    ///
-    /// Returns the length of the string after modification.
-    fn setrange<K, V, RV>(&mut self, key: K, offset: isize, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Returns the length of a string value.
-    ///
-    /// Returns 0 if the key doesn't exist.
-    fn strlen<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Increments the integer value of a key by the given amount.
-    ///
-    /// Returns the new value after the increment.
-    fn incr<K, V, RV>(&mut self, key: K, delta: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Decrements the integer value of a key by the given amount.
-    ///
-    /// Returns the new value after the decrement.
-    fn decr<K, V, RV>(&mut self, key: K, delta: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    /// Negative offsets count from the end of the string.
+    fn getrange<K: ToRedisArgs, RV: FromRedisValue>(key: K, start: isize, end: isize) -> RV {
+        cmd("GETRANGE").arg(key).arg(start).arg(end)
+    }
+
+    /// Overwrites part of a string starting at `offset`, padding it with
+    /// zero bytes if needed.
+    ///
+    /// Returns the length of the string after the write.
+    fn setrange<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        offset: isize,
+        value: V,
+    ) -> RV {
+        cmd("SETRANGE").arg(key).arg(offset).arg(value)
+    }
+
+    /// Returns the length of the string at a key, or `0` if it is missing.
+    fn strlen<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("STRLEN").arg(key)
+    }
+
+    /// Returns the longest common subsequence of the strings at two keys.
+    fn lcs<K: ToRedisArgs, RV: FromRedisValue>(key1: K, key2: K) -> RV {
+        cmd("LCS").arg(key1).arg(key2)
+    }
+
+    /// Returns the length of the longest common subsequence of the strings
+    /// at two keys.
+    fn lcs_len<K: ToRedisArgs, RV: FromRedisValue>(key1: K, key2: K) -> RV {
+        cmd("LCS").arg(key1).arg(key2).arg("LEN")
+    }
+
+    /// Returns the matching ranges of the longest common subsequence of the
+    /// strings at two keys, as `LCS IDX` does.
+    ///
+    /// Runs shorter than `min_match_len` are left out. With
+    /// `with_match_len`, each match also carries its length.
+    fn lcs_idx<K: ToRedisArgs, RV: FromRedisValue>(
+        key1: K,
+        key2: K,
+        min_match_len: usize,
+        with_match_len: bool,
+    ) -> RV {
+        let mut cmd = cmd("LCS");
+        cmd.arg(key1)
+            .arg(key2)
+            .arg("IDX")
+            .arg("MINMATCHLEN")
+            .arg(min_match_len);
+        if with_match_len {
+            cmd.arg("WITHMATCHLEN");
+        }
+        cmd
+    }
+
+    /// Increments the integer stored at a key by `delta`.
+    ///
+    /// A missing key counts as `0`. Returns the new value.
+    fn incr<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, delta: V) -> RV {
+        cmd("INCRBY").arg(key).arg(delta)
+    }
+
+    /// Increments the number stored at a key by the float `delta`.
+    ///
+    /// A missing key counts as `0`. Returns the new value.
+    fn incr_by_float<K: ToRedisArgs, RV: FromRedisValue>(key: K, delta: f64) -> RV {
+        cmd("INCRBYFLOAT").arg(key).arg(delta)
+    }
+
+    /// Decrements the integer stored at a key by `delta`.
+    ///
+    /// A missing key counts as `0`. Returns the new value.
+    fn decr<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, delta: V) -> RV {
+        cmd("DECRBY").arg(key).arg(delta)
+    }
 
     /// Gets the value of a field in a hash.
     ///
     /// Returns `None` if the field or key doesn't exist.
-    fn hget<K, F, RV>(&mut self, key: K, field: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Gets the values of all specified fields in a hash.
-    fn hmget<K, F, RV>(&mut self, key: K, fields: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue;
+    fn hget<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(key: K, field: F) -> RV {
+        cmd("HGET").arg(key).arg(field)
+    }
+
+    /// Gets the values of several fields in a hash, with `None` for missing
+    /// fields.
+    fn hmget<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(key: K, fields: F) -> RV {
+        cmd("HMGET").arg(key).arg(fields)
+    }
 
     /// Sets the value of a field in a hash.
     ///
     /// Returns 1 if the field is new, 0 if the field was updated.
-    fn hset<K, F, V, RV>(&mut self, key: K, field: F, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    fn hset<K: ToRedisArgs, F: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        field: F,
+        value: V,
+    ) -> RV {
+        cmd("HSET").arg(key).arg(field).arg(value)
+    }
+
+    /// Sets several fields in a hash at once.
+    ///
+    /// Returns the number of fields that were added rather than updated.
+    fn hset_multiple<K: ToRedisArgs, F: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        items: &[(F, V)],
+    ) -> RV {
+        let mut cmd = cmd("HSET");
+        cmd.arg(key);
+        for (field, value) in items {
+            cmd.arg(field).arg(value);
+        }
+        cmd
+    }
+
+    /// Sets a field in a hash only if it does not exist yet.
+    ///
+    /// Returns 1 if the field was set, 0 otherwise.
+    fn hset_nx<K: ToRedisArgs, F: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        field: F,
+        value: V,
+    ) -> RV {
+        cmd("HSETNX").arg(key).arg(field).arg(value)
+    }
 
     /// Deletes one or more fields from a hash.
     ///
     /// Returns the number of fields that were deleted.
-    fn hdel<K, F, RV>(&mut self, key: K, field: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Returns all fields and values in a hash.
-    fn hgetall<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn hdel<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(key: K, fields: F) -> RV {
+        cmd("HDEL").arg(key).arg(fields)
+    }
+
+    /// Gets all fields and values from a hash.
+    ///
+    /// Returns an array of alternating field names and values.
+    fn hgetall<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("HGETALL").arg(key)
+    }
 
     /// Returns all field names in a hash.
-    fn hkeys<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn hkeys<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("HKEYS").arg(key)
+    }
 
     /// Returns all values in a hash.
-    fn hvals<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn hvals<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("HVALS").arg(key)
+    }
 
     /// Returns the number of fields in a hash.
-    fn hlen<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Increments the integer value of a field in a hash by the given amount.
-    fn hincr<K, F, D, RV>(&mut self, key: K, field: F, delta: D) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        D: ToRedisArgs,
-        RV: FromRedisValue;
+    fn hlen<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("HLEN").arg(key)
+    }
+
+    /// Atomically adds `delta` to the integer value of a field, starting
+    /// from `0` if it does not exist.
+    ///
+    /// Returns the new value.
+    fn hincr<K: ToRedisArgs, F: ToRedisArgs, D: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        field: F,
+        delta: D,
+    ) -> RV {
+        cmd("HINCRBY").arg(key).arg(field).arg(delta)
+    }
+
+    /// Atomically adds `delta` to the number stored in a field, starting
+    /// from `0` if it does not exist.
+    ///
+    /// Returns the new value.
+    fn hincr_by_float<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        field: F,
+        delta: f64,
+    ) -> RV {
+        cmd("HINCRBYFLOAT")
+            .arg(key)
+            .arg(field)
+            .arg(delta)
+    }
+
+    /// Returns the length of a field's value, or `0` if it does not exist.
+    fn hstrlen<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(key: K, field: F) -> RV {
+        cmd("HSTRLEN").arg(key).arg(field)
+    }
+
+    /// Returns a random field name from a hash, or `None` if the key does
+    /// not exist.
+    fn hrandfield<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("HRANDFIELD").arg(key)
+    }
+
+    /// Returns up to `count` distinct random field names from a hash, or
+    /// exactly `-count` field names, possibly repeated, if `count` is
+    /// negative.
+    fn hrandfield_count<K: ToRedisArgs, RV: FromRedisValue>(key: K, count: isize) -> RV {
+        cmd("HRANDFIELD").arg(key).arg(count)
+    }
+
+    /// Like [`hrandfield_count`](Self::hrandfield_count), with each field
+    /// followed by its value.
+    fn hrandfield_withvalues<K: ToRedisArgs, RV: FromRedisValue>(key: K, count: isize) -> RV {
+        cmd("HRANDFIELD")
+            .arg(key)
+            .arg(count)
+            .arg("WITHVALUES")
+    }
+
+    /// Sets a time-to-live in seconds on each of `fields` if `option`
+    /// allows it.
+    ///
+    /// Returns, for each field, `-2` if it does not exist, `0` if `option`
+    /// prevented the change, `1` if the timeout was set, or `2` if the
+    /// field was deleted because the TTL was zero.
+    fn hexpire<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        seconds: i64,
+        option: ExpireOption,
+        fields: F,
+    ) -> RV {
+        let mut cmd = cmd("HEXPIRE");
+        cmd.arg(key).arg(seconds);
+        option.write_args(&mut cmd);
+        cmd.fields_arg(fields);
+        cmd
+    }
+
+    /// Like [`hexpire`](Self::hexpire), in milliseconds.
+    fn hpexpire<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        ms: i64,
+        option: ExpireOption,
+        fields: F,
+    ) -> RV {
+        let mut cmd = cmd("HPEXPIRE");
+        cmd.arg(key).arg(ms);
+        option.write_args(&mut cmd);
+        cmd.fields_arg(fields);
+        cmd
+    }
+
+    /// Sets the deadline of each of `fields` as a Unix timestamp in
+    /// seconds, if `option` allows it.
+    ///
+    /// Replies like [`hexpire`](Self::hexpire); a deadline in the past
+    /// deletes the field.
+    fn hexpire_at<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        ts: i64,
+        option: ExpireOption,
+        fields: F,
+    ) -> RV {
+        let mut cmd = cmd("HEXPIREAT");
+        cmd.arg(key).arg(ts);
+        option.write_args(&mut cmd);
+        cmd.fields_arg(fields);
+        cmd
+    }
+
+    /// Like [`hexpire_at`](Self::hexpire_at), in milliseconds.
+    fn hpexpire_at<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        ts: i64,
+        option: ExpireOption,
+        fields: F,
+    ) -> RV {
+        let mut cmd = cmd("HPEXPIREAT");
+        cmd.arg(key).arg(ts);
+        option.write_args(&mut cmd);
+        cmd.fields_arg(fields);
+        cmd
+    }
+
+    /// Returns the remaining time-to-live of each of `fields` in seconds.
+    ///
+    /// A field without a timeout gives `-1` and a missing one `-2`.
+    fn httl<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(key: K, fields: F) -> RV {
+        cmd("HTTL").arg(key).fields_arg(fields)
+    }
+
+    /// Like [`httl`](Self::httl), in milliseconds.
+    fn hpttl<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(key: K, fields: F) -> RV {
+        cmd("HPTTL").arg(key).fields_arg(fields)
+    }
+
+    /// Returns the deadline of each of `fields` as a Unix timestamp in
+    /// seconds.
+    ///
+    /// A field without a timeout gives `-1` and a missing one `-2`.
+    fn hexpire_time<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(key: K, fields: F) -> RV {
+        cmd("HEXPIRETIME").arg(key).fields_arg(fields)
+    }
+
+    /// Like [`hexpire_time`](Self::hexpire_time), in milliseconds.
+    fn hpexpire_time<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(key: K, fields: F) -> RV {
+        cmd("HPEXPIRETIME").arg(key).fields_arg(fields)
+    }
+
+    /// Removes the time-to-live of each of `fields`.
+    ///
+    /// Returns, for each field, `1` if a timeout was removed, `-1` if it had
+    /// none, or `-2` if it does not exist.
+    fn hpersist<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(key: K, fields: F) -> RV {
+        cmd("HPERSIST").arg(key).fields_arg(fields)
+    }
+
+    /// Gets the values of `fields` and sets or removes their time-to-live.
+    ///
+    /// Missing fields give `None`.
+    fn hget_ex<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        fields: F,
+        expiry: Expiry,
+    ) -> RV {
+        let mut cmd = cmd("HGETEX");
+        cmd.arg(key);
+        expiry.write_args(&mut cmd);
+        cmd.fields_arg(fields);
+        cmd
+    }
+
+    /// Sets several fields in a hash, with the condition and expiration in
+    /// `options`.
+    ///
+    /// Returns 1 if the fields were set, 0 if the condition failed.
+    fn hset_ex<K: ToRedisArgs, F: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        options: &HashFieldExpirationOptions,
+        items: &[(F, V)],
+    ) -> RV {
+        let mut cmd = cmd("HSETEX");
+        cmd.arg(key);
+        options.write_args(&mut cmd);
+        cmd.arg("FIELDS").arg(items.len());
+        for (field, value) in items {
+            cmd.arg(field).arg(value);
+        }
+        cmd
+    }
 
     /// Checks if a field exists in a hash.
     ///
     /// Returns 1 if the field exists, 0 otherwise.
-    fn hexists<K, F, RV>(&mut self, key: K, field: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn set<K, V, RV>(&mut self, key: K, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn set_options<K, V, RV>(
-        &mut self,
-        key: K,
-        value: V,
-        options: SetOptions,
-    ) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn mset<K, V, RV>(&mut self, items: &[(K, V)]) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn mget<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn del<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn exists<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn append<K, V, RV>(&mut self, key: K, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn getrange<K, RV>(&mut self, key: K, from: isize, to: isize) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn setrange<K, V, RV>(&mut self, key: K, offset: isize, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn strlen<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn incr<K, V, RV>(&mut self, key: K, delta: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn decr<K, V, RV>(&mut self, key: K, delta: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-RedisValue;
-
-    fn hget<K, F, RV        RV: From>(&mut self, key: K, -> RedisResult field: F)<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn hmget<K, F, RV>(&mut self, key: K, fields: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn hset<K, F, V, RV>(&mut self, key: K, field: F, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn hdel<K, F, RV>(&mut self, key: K, field: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn hgetall<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn hkeys<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn hvals<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn hlen<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn hincr<K, F, D, RV>(&mut self, key: K, field: F, delta: D) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        D: ToRedisArgs,
-        RV: FromRedisValue;
-
-    fn hexists<K, F, RV>(&mut self, key: K, field: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Pushes one or more values to the front (left) of a list.
+    fn hexists<K: ToRedisArgs, F: ToRedisArgs, RV: FromRedisValue>(key: K, field: F) -> RV {
+        cmd("HEXISTS").arg(key).arg(field)
+    }
+
+    /// Pushes one or more values to the front (left) of a list, one at a
+    /// time, so the last value ends up first.
     ///
     /// Returns the length of the list after the push.
-    fn lpush<K, V, RV>(&mut self, key: K, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    fn lpush<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, value: V) -> RV {
+        cmd("LPUSH").arg(key).arg(value)
+    }
 
     /// Pushes one or more values to the back (right) of a list.
     ///
     /// Returns the length of the list after the push.
-    fn rpush<K, V, RV>(&mut self, key: K, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Removes and returns the first (left) element of a list.
-    fn lpop<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Removes and returns the last (right) element of a list.
-    fn rpop<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn rpush<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, value: V) -> RV {
+        cmd("RPUSH").arg(key).arg(value)
+    }
+
+    /// Like [`lpush`](Self::lpush), but only if the list exists.
+    ///
+    /// Returns the length of the list after the push, or `0` if it doesn't
+    /// exist.
+    fn lpush_exists<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, value: V) -> RV {
+        cmd("LPUSHX").arg(key).arg(value)
+    }
+
+    /// Like [`rpush`](Self::rpush), but only if the list exists.
+    ///
+    /// Returns the length of the list after the push, or `0` if it doesn't
+    /// exist.
+    fn rpush_exists<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, value: V) -> RV {
+        cmd("RPUSHX").arg(key).arg(value)
+    }
+
+    /// Removes and returns the first (left) element of a list, or null if
+    /// the list doesn't exist.
+    fn lpop<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("LPOP").arg(key)
+    }
+
+    /// Removes and returns the last (right) element of a list, or null if
+    /// the list doesn't exist.
+    fn rpop<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("RPOP").arg(key)
+    }
+
+    /// Removes and returns up to `count` elements from the front of a list,
+    /// or null if the list doesn't exist.
+    fn lpop_count<K: ToRedisArgs, RV: FromRedisValue>(key: K, count: usize) -> RV {
+        cmd("LPOP").arg(key).arg(count)
+    }
+
+    /// Removes and returns up to `count` elements from the back of a list,
+    /// or null if the list doesn't exist.
+    fn rpop_count<K: ToRedisArgs, RV: FromRedisValue>(key: K, count: usize) -> RV {
+        cmd("RPOP").arg(key).arg(count)
+    }
 
     /// Returns the length of a list.
     ///
-    /// Returns 0 if the key doesn't exist.
-    fn llen<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    /// Returns `0` if the key doesn't exist.
+    fn llen<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("LLEN").arg(key)
+    }
 
-    /// Returns a range of elements from a list.
+    /// Returns the elements of a list from `start` to `stop`, inclusive.
     ///
-    /// # Arguments
-    /// * `key` - The list key
-    /// * `start` - Start index (can be negative)
-    /// * `stop` - Stop index (can be negative)
-    fn lrange<K, RV>(&mut self, key: K, start: isize, stop: isize) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    /// Negative indexes count from the end of the list, so `-1` is the last
+    /// element.
+    fn lrange<K: ToRedisArgs, RV: FromRedisValue>(key: K, start: isize, stop: isize) -> RV {
+        cmd("LRANGE").arg(key).arg(start).arg(stop)
+    }
 
-    /// Returns the element at the specified index in a list.
-    fn lindex<K, RV>(&mut self, key: K, index: isize) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    /// Returns the element at `index` in a list, or null if it is out of
+    /// range. Negative indexes count from the end of the list.
+    fn lindex<K: ToRedisArgs, RV: FromRedisValue>(key: K, index: isize) -> RV {
+        cmd("LINDEX").arg(key).arg(index)
+    }
+
+    /// Replaces the element at `index` in a list.
+    ///
+    /// Fails if the list doesn't exist or `index` is out of range.
+    fn lset<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        index: isize,
+        value: V,
+    ) -> RV {
+        cmd("LSET").arg(key).arg(index).arg(value)
+    }
+
+    /// Inserts `value` before the first occurrence of `pivot` in a list.
+    ///
+    /// Returns the length of the list after the insert, `-1` if `pivot` was
+    /// not found, or `0` if the list doesn't exist.
+    fn linsert_before<K: ToRedisArgs, P: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        pivot: P,
+        value: V,
+    ) -> RV {
+        cmd("LINSERT")
+            .arg(key)
+            .arg("BEFORE")
+            .arg(pivot)
+            .arg(value)
+    }
+
+    /// Like [`linsert_before`](Self::linsert_before), inserting after
+    /// `pivot`.
+    fn linsert_after<K: ToRedisArgs, P: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        pivot: P,
+        value: V,
+    ) -> RV {
+        cmd("LINSERT")
+            .arg(key)
+            .arg("AFTER")
+            .arg(pivot)
+            .arg(value)
+    }
+
+    /// Removes occurrences of `value` from a list: up to `count` from the
+    /// front if `count` is positive, up to `-count` from the back if it is
+    /// negative, or all of them if it is `0`.
+    ///
+    /// Returns the number of elements removed.
+    fn lrem<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        count: isize,
+        value: V,
+    ) -> RV {
+        cmd("LREM").arg(key).arg(count).arg(value)
+    }
+
+    /// Trims a list to the elements from `start` to `stop`, inclusive,
+    /// deleting it if none are left. Negative indexes count from the end of
+    /// the list.
+    fn ltrim<K: ToRedisArgs, RV: FromRedisValue>(key: K, start: isize, stop: isize) -> RV {
+        cmd("LTRIM").arg(key).arg(start).arg(stop)
+    }
+
+    /// Returns the index of `value` in a list, or null if it is not found.
+    ///
+    /// With [`LposOptions::count`], returns an array of indexes instead.
+    fn lpos<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        value: V,
+        options: LposOptions,
+    ) -> RV {
+        let mut cmd = cmd("LPOS");
+        cmd.arg(key).arg(value);
+        options.write_args(&mut cmd);
+        cmd
+    }
 
     /// Adds one or more members to a set.
     ///
-    /// Returns the number of members that were added.
-    fn sadd<K, V, RV>(&mut self, key: K, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    /// Returns the number of members that were added to the set.
+    fn sadd<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, member: V) -> RV {
+        cmd("SADD").arg(key).arg(member)
+    }
 
     /// Removes one or more members from a set.
     ///
     /// Returns the number of members that were removed.
-    fn srem<K, V, RV>(&mut self, key: K, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    fn srem<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, member: V) -> RV {
+        cmd("SREM").arg(key).arg(member)
+    }
 
     /// Returns all members of a set.
-    fn smembers<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn smembers<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("SMEMBERS").arg(key)
+    }
 
     /// Checks if a member exists in a set.
     ///
     /// Returns 1 if the member exists, 0 otherwise.
-    fn sismember<K, V, RV>(&mut self, key: K, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    fn sismember<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, member: V) -> RV {
+        cmd("SISMEMBER").arg(key).arg(member)
+    }
 
     /// Returns the number of members in a set.
-    fn scard<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn scard<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("SCARD").arg(key)
+    }
 
     /// Removes and returns one or more random members from a set.
-    fn spop<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn spop<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("SPOP").arg(key)
+    }
 
     /// Adds one or more members to a sorted set, or updates its score.
     ///
@@ -448,21 +768,20 @@ RedisValue;
     /// * `member` - The member to add
     ///
     /// Returns the number of members added.
-    fn zadd<K, S, V, RV>(&mut self, key: K, score: S, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        S: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    fn zadd<K: ToRedisArgs, S: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        score: S,
+        member: V,
+    ) -> RV {
+        cmd("ZADD").arg(key).arg(score).arg(member)
+    }
 
     /// Removes one or more members from a sorted set.
     ///
     /// Returns the number of members removed.
-    fn zrem<K, V, RV>(&mut self, key: K, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    fn zrem<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, member: V) -> RV {
+        cmd("ZREM").arg(key).arg(member)
+    }
 
     /// Returns a range of members in a sorted set by index (score order).
     ///
@@ -470,10 +789,9 @@ RedisValue;
     /// * `key` - The sorted set key
     /// * `start` - Start index (can be negative)
     /// * `stop` - Stop index (can be negative)
-    fn zrange<K, RV>(&mut self, key: K, start: isize, stop: isize) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn zrange<K: ToRedisArgs, RV: FromRedisValue>(key: K, start: isize, stop: isize) -> RV {
+        cmd("ZRANGE").arg(key).arg(start).arg(stop)
+    }
 
     /// Returns a range of members in a sorted set by score.
     ///
@@ -481,148 +799,241 @@ RedisValue;
     /// * `key` - The sorted set key
     /// * `min` - Minimum score (inclusive by default)
     /// * `max` - Maximum score (inclusive by default)
-    fn zrangebyscore<K, RV>(&mut self, key: K, min: &str, max: &str) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn zrangebyscore<K: ToRedisArgs, RV: FromRedisValue>(key: K, min: &str, max: &str) -> RV {
+        cmd("ZRANGEBYSCORE").arg(key).arg(min).arg(max)
+    }
 
     /// Returns the number of members in a sorted set.
-    fn zcard<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn zcard<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("ZCARD").arg(key)
+    }
 
     /// Returns the score of a member in a sorted set.
-    fn zscore<K, V, RV>(&mut self, key: K, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue;
+    fn zscore<K: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(key: K, member: V) -> RV {
+        cmd("ZSCORE").arg(key).arg(member)
+    }
 
     /// Returns the number of members in a sorted set within a score range.
-    fn zcount<K, RV>(&mut self, key: K, min: &str, max: &str) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn zcount<K: ToRedisArgs, RV: FromRedisValue>(key: K, min: &str, max: &str) -> RV {
+        cmd("ZCOUNT").arg(key).arg(min).arg(max)
+    }
 
     /// Sets a key's time-to-live in seconds.
     ///
     /// Returns `true` if the timeout was set, `false` if the key doesn't exist.
-    fn expire<K>(&mut self, key: K, seconds: i64) -> RedisResult<bool>
-    where
-        K: ToRedisArgs;
+    fn expire<K: ToRedisArgs>(key: K, seconds: i64) -> bool {
+        cmd("EXPIRE").arg(key).arg(seconds)
+    }
+
+    /// Sets a key's time-to-live in seconds if `option` allows it.
+    ///
+    /// A TTL of zero or less deletes the key. Returns `true` if the timeout
+    /// was set, `false` if the key doesn't exist or `option` prevented it.
+    fn expire_options<K: ToRedisArgs>(key: K, seconds: i64, option: ExpireOption) -> bool {
+        let mut cmd = cmd("EXPIRE");
+        cmd.arg(key).arg(seconds);
+        option.write_args(&mut cmd);
+        cmd
+    }
 
     /// Sets the expiration on a key by timestamp (Unix timestamp in seconds).
     ///
-    /// Returns `true` if the timeout was set, `false` if the key doesn't exist.
-    fn expire_at<K>(&mut self, key: K, ts: i64) -> RedisResult<bool>
-    where
-        K: ToRedisArgs;
+    /// A timestamp in the past deletes the key. Returns `true` if the
+    /// timeout was set, `false` if the key doesn't exist.
+    fn expire_at<K: ToRedisArgs>(key: K, ts: i64) -> bool {
+        cmd("EXPIREAT").arg(key).arg(ts)
+    }
 
     /// Sets a key's time-to-live in milliseconds.
     ///
-    /// Returns `true` if the timeout was set, `false` if the key doesn't exist.
-    fn pexpire<K>(&mut self, key: K, ms: i64) -> RedisResult<bool>
-    where
-        K: ToRedisArgs;
+    /// A TTL of zero or less deletes the key. Returns `true` if the timeout
+    /// was set, `false` if the key doesn't exist.
+    fn pexpire<K: ToRedisArgs>(key: K, ms: i64) -> bool {
+        cmd("PEXPIRE").arg(key).arg(ms)
+    }
+
+    /// Sets a key's time-to-live in milliseconds if `option` allows it.
+    ///
+    /// A TTL of zero or less deletes the key. Returns `true` if the timeout
+    /// was set, `false` if the key doesn't exist or `option` prevented it.
+    fn pexpire_options<K: ToRedisArgs>(key: K, ms: i64, option: ExpireOption) -> bool {
+        let mut cmd = cmd("PEXPIRE");
+        cmd.arg(key).arg(ms);
+        option.write_args(&mut cmd);
+        cmd
+    }
 
     /// Sets the expiration on a key by timestamp (Unix timestamp in milliseconds).
     ///
-    /// Returns `true` if the timeout was set, `false` if the key doesn't exist.
-    fn pexpire_at<K>(&mut self, key: K, ts: i64) -> RedisResult<bool>
-    where
-        K: ToRedisArgs;
+    /// A timestamp in the past deletes the key. Returns `true` if the
+    /// timeout was set, `false` if the key doesn't exist.
+    fn pexpire_at<K: ToRedisArgs>(key: K, ts: i64) -> bool {
+        cmd("PEXPIREAT").arg(key).arg(ts)
+    }
 
-    /// Returns the time-to-live of a key in seconds.
+    /// Gets the time-to-live of a key.
     ///
-    /// Returns -1 if the key exists but has no expiration.
-    /// Returns -2 if the key doesn't exist.
-    fn ttl<K>(&mut self, key: K) -> RedisResult<IntegerReplyOrNoOp>
-    where
-        K: ToRedisArgs;
+    /// Returns:
+    /// - `-1` if the key exists but has no expiration
+    /// - `-2` if the key does not exist
+    /// - A non-negative value representing seconds until expiration
+    fn ttl<K: ToRedisArgs>(key: K) -> IntegerReplyOrNoOp {
+        cmd("TTL").arg(key)
+    }
 
     /// Returns the time-to-live of a key in milliseconds.
     ///
     /// Returns -1 if the key exists but has no expiration.
     /// Returns -2 if the key doesn't exist.
-    fn pttl<K>(&mut self, key: K) -> RedisResult<IntegerReplyOrNoOp>
-    where
-        K: ToRedisArgs;
+    fn pttl<K: ToRedisArgs>(key: K) -> IntegerReplyOrNoOp {
+        cmd("PTTL").arg(key)
+    }
 
     /// Removes the expiration from a key.
     ///
-    /// Returns `true` if the expiration was removed, `false` if the key doesn't exist.
-    fn persist<K>(&mut self, key: K) -> RedisResult<bool>
-    where
-        K: ToRedisArgs;
+    /// Returns `true` if an expiration was removed, `false` if the key does not
+    /// exist or has none.
+    fn persist<K: ToRedisArgs>(key: K) -> bool {
+        cmd("PERSIST").arg(key)
+    }
 
     /// Returns the expiration timestamp of a key in seconds.
     ///
     /// Returns -1 if the key exists but has no expiration.
     /// Returns -2 if the key doesn't exist.
-    fn expire_time<K>(&mut self, key: K) -> RedisResult<IntegerReplyOrNoOp>
-    where
-        K: ToRedisArgs;
-
-    /// Sets or clears the bit at the specified offset in a string value.
-    ///
-    /// Returns the original bit value at the specified offset.
-    fn setbit<K>(&mut self, key: K, offset: usize, value: bool) -> RedisResult<bool>
-    where
-        K: ToRedisArgs;
-
-    /// Returns the bit value at the specified offset in a string value.
-    fn getbit<K>(&mut self, key: K, offset: usize) -> RedisResult<bool>
-    where
-        K: ToRedisArgs;
-
-    /// Returns the number of set bits in a string value.
-    ///
-    /// This is also known as "popcount".
-    fn bitcount<K>(&mut self, key: K) -> RedisResult<usize>
-    where
-        K: ToRedisArgs;
-
-    /// Returns the number of set bits in a specified range of a string.
-    fn bitcount_range<K>(&mut self, key: K, start: usize, end: usize) -> RedisResult<usize>
-    where
-        K: ToRedisArgs;
-
-    /// Performs a bitwise AND operation between multiple keys.
-    ///
-    /// Stores the result in the destination key.
-    fn bit_and<D, S, RV>(&mut self, dstkey: D, srckeys: S) -> RedisResult<RV>
-    where
-        D: ToRedisArgs,
-        S: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Performs a bitwise OR operation between multiple keys.
-    ///
-    /// Stores the result in the destination key.
-    fn bit_or<D, S, RV>(&mut self, dstkey: D, srckeys: S) -> RedisResult<RV>
-    where
-        D: ToRedisArgs,
-        S: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Performs a bitwise XOR operation between multiple keys.
-    ///
-    /// Stores the result in the destination key.
-    fn bit_xor<D, S, RV>(&mut self, dstkey: D, srckeys: S) -> RedisResult<RV>
-    where
-        D: ToRedisArgs,
-        S: ToRedisArgs,
-        RV: FromRedisValue;
-
-    /// Performs a bitwise NOT operation on a key.
-    ///
-    /// Stores the result in the destination key.
-    fn bit_not<D, S, RV>(&mut self, dstkey: D, srckey: S) -> RedisResult<RV>
-    where
-        D: ToRedisArgs,
-        S: ToRedisArgs,
-        RV: FromRedisValue;
+    fn expire_time<K: ToRedisArgs>(key: K) -> IntegerReplyOrNoOp {
+        cmd("EXPIRETIME").arg(key)
+    }
+
+    /// Returns the expiration timestamp of a key in milliseconds.
+    ///
+    /// Returns -1 if the key exists but has no expiration.
+    /// Returns -2 if the key doesn't exist.
+    fn pexpire_time<K: ToRedisArgs>(key: K) -> IntegerReplyOrNoOp {
+        cmd("PEXPIRETIME").arg(key)
+    }
+
+    /// Sets or clears the bit at `offset` in the string at a key.
+    ///
+    /// Returns the bit's previous value.
+    fn setbit<K: ToRedisArgs>(key: K, offset: usize, value: bool) -> bool {
+        cmd("SETBIT")
+            .arg(key)
+            .arg(offset)
+            .arg(if value { 1i64 } else { 0i64 })
+    }
+
+    /// Returns the bit at `offset` in the string at a key.
+    fn getbit<K: ToRedisArgs>(key: K, offset: usize) -> bool {
+        cmd("GETBIT").arg(key).arg(offset)
+    }
+
+    /// Returns the number of set bits in the string at a key.
+    fn bitcount<K: ToRedisArgs>(key: K) -> usize {
+        cmd("BITCOUNT").arg(key)
+    }
+
+    /// Returns the number of set bits between the bytes `start` and `end`,
+    /// inclusive.
+    ///
+    /// Negative offsets count from the end of the string.
+    fn bitcount_range<K: ToRedisArgs>(key: K, start: isize, end: isize) -> usize {
+        cmd("BITCOUNT").arg(key).arg(start).arg(end)
+    }
+
+    /// Like [`bitcount_range`](Self::bitcount_range), with `start` and `end`
+    /// counted in `unit`s.
+    fn bitcount_range_unit<K: ToRedisArgs>(
+        key: K,
+        start: isize,
+        end: isize,
+        unit: BitUnit,
+    ) -> usize {
+        let mut cmd = cmd("BITCOUNT");
+        cmd.arg(key).arg(start).arg(end);
+        unit.write_args(&mut cmd);
+        cmd
+    }
+
+    /// Returns the position of the first bit set to `bit` in the string at a
+    /// key, or `-1`.
+    ///
+    /// Looking for a `0` past the end of the string finds the first bit after
+    /// it, as the string counts as padded with zeros.
+    fn bitpos<K: ToRedisArgs>(key: K, bit: bool) -> i64 {
+        cmd("BITPOS").arg(key).arg(bit as i64)
+    }
+
+    /// Like [`bitpos`](Self::bitpos), searching only from `start` to `end`,
+    /// inclusive, counted in `unit`s.
+    ///
+    /// With an `end`, a missing `0` bit gives `-1` rather than the position
+    /// past the range.
+    fn bitpos_range<K: ToRedisArgs>(
+        key: K,
+        bit: bool,
+        start: isize,
+        end: Option<isize>,
+        unit: BitUnit,
+    ) -> i64 {
+        let mut cmd = cmd("BITPOS");
+        cmd.arg(key).arg(bit as i64).arg(start);
+        if let Some(end) = end {
+            cmd.arg(end);
+            unit.write_args(&mut cmd);
+        } else if unit == BitUnit::Bit {
+            // The unit can only follow an end, and -1 reaches the last bit.
+            cmd.arg(-1).arg("BIT");
+        }
+        cmd
+    }
+
+    /// Runs the `BITFIELD` subcommands in `ops` on the string at a key.
+    ///
+    /// Returns one entry per `get`, `set` and `incr_by`; `None` for a write
+    /// that failed under [`BitFieldOverflow::Fail`](crate::BitFieldOverflow).
+    fn bitfield<K: ToRedisArgs>(key: K, ops: &BitFieldOps) -> Vec<Option<i64>> {
+        let mut cmd = cmd("BITFIELD");
+        cmd.arg(key);
+        ops.write_args(&mut cmd);
+        cmd
+    }
+
+    /// Like [`bitfield`](Self::bitfield), but only accepts `get`s.
+    fn bitfield_ro<K: ToRedisArgs>(key: K, ops: &BitFieldOps) -> Vec<Option<i64>> {
+        let mut cmd = cmd("BITFIELD_RO");
+        cmd.arg(key);
+        ops.write_args(&mut cmd);
+        cmd
+    }
+
+    /// Stores the bitwise AND of the strings at `srckeys` in `dstkey`.
+    ///
+    /// Returns the length of the result.
+    fn bit_and<D: ToRedisArgs, S: ToRedisArgs, RV: FromRedisValue>(dstkey: D, srckeys: S) -> RV {
+        cmd("BITOP").arg("AND").arg(dstkey).arg(srckeys)
+    }
+
+    /// Stores the bitwise OR of the strings at `srckeys` in `dstkey`.
+    ///
+    /// Returns the length of the result.
+    fn bit_or<D: ToRedisArgs, S: ToRedisArgs, RV: FromRedisValue>(dstkey: D, srckeys: S) -> RV {
+        cmd("BITOP").arg("OR").arg(dstkey).arg(srckeys)
+    }
+
+    /// Stores the bitwise XOR of the strings at `srckeys` in `dstkey`.
+    ///
+    /// Returns the length of the result.
+    fn bit_xor<D: ToRedisArgs, S: ToRedisArgs, RV: FromRedisValue>(dstkey: D, srckeys: S) -> RV {
+        cmd("BITOP").arg("XOR").arg(dstkey).arg(srckeys)
+    }
+
+    /// Stores the bitwise NOT of the string at `srckey` in `dstkey`.
+    ///
+    /// Returns the length of the result.
+    fn bit_not<D: ToRedisArgs, S: ToRedisArgs, RV: FromRedisValue>(dstkey: D, srckey: S) -> RV {
+        cmd("BITOP").arg("NOT").arg(dstkey).arg(srckey)
+    }
 
     /// Returns all keys matching the given pattern.
     ///
@@ -630,114 +1041,213 @@ RedisValue;
     /// - `*` matches any number of characters
     /// - `?` matches exactly one character
     /// - `[abc]` matches any character in the brackets
-    fn keys<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn keys<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("KEYS").arg(key)
+    }
+
+    /// Returns the next batch of keys from `cursor`, and the cursor to pass
+    /// next time, or `0` once every key has been returned.
+    ///
+    /// Start with a cursor of `0`. Read the reply as `(u64, Vec<String>)`.
+    fn scan<RV: FromRedisValue>(cursor: u64, options: ScanOptions) -> RV {
+        let mut cmd = cmd("SCAN");
+        cmd.arg(cursor);
+        options.write_args(&mut cmd);
+        cmd
+    }
 
     /// Returns the data type of a key's value.
-    fn key_type<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn key_type<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("TYPE").arg(key)
+    }
 
     /// Renames a key to a new name.
     ///
     /// If the new key already exists, it will be overwritten.
-    fn rename<K, N, RV>(&mut self, key: K, new_key: N) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        N: ToRedisArgs,
-        RV: FromRedisValue;
+    fn rename<K: ToRedisArgs, N: ToRedisArgs, RV: FromRedisValue>(key: K, new_key: N) -> RV {
+        cmd("RENAME").arg(key).arg(new_key)
+    }
 
     /// Renames a key to a new name, only if the new key does not exist.
     ///
     /// Returns 1 if the key was renamed, 0 if the new key already exists.
-    fn rename_nx<K, N, RV>(&mut self, key: K, new_key: N) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        N: ToRedisArgs,
-        RV: FromRedisValue;
+    fn rename_nx<K: ToRedisArgs, N: ToRedisArgs, RV: FromRedisValue>(key: K, new_key: N) -> RV {
+        cmd("RENAMENX").arg(key).arg(new_key)
+    }
 
-    /// Deletes one or more keys in a non-blocking manner.
-    ///
-    /// Unlike DEL, the key is unlinked in the background.
-    fn unlink<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    /// Deletes one or more keys, like [`del`](Self::del).
+    fn unlink<K: ToRedisArgs, RV: FromRedisValue>(keys: K) -> RV {
+        cmd("UNLINK").arg(keys)
+    }
 
     /// Copies a key from source to destination.
     ///
     /// Can optionally copy to a different database.
-    fn copy<KSrc, KDst, Db, RV>(
-        &mut self,
+    fn copy<KSrc: ToRedisArgs, KDst: ToRedisArgs, Db: ToRedisArgs, RV: FromRedisValue>(
         source: KSrc,
         destination: KDst,
         options: CopyOptions<Db>,
-    ) -> RedisResult<RV>
-    where
-        KSrc: ToRedisArgs,
-        KDst: ToRedisArgs,
-        Db: ToRedisArgs,
-        RV: FromRedisValue;
+    ) -> RV {
+        let mut cmd = cmd("COPY");
+        cmd.arg(source).arg(destination);
+        options.write_args(&mut cmd);
+        cmd
+    }
 
     /// Pings the server.
     ///
     /// Returns "PONG".
-    fn ping<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue;
+    fn ping<RV: FromRedisValue>() -> RV {
+        cmd("PING")
+    }
 
-    /// Echoes the given message back.
+    /// Echoes the given message.
     ///
     /// Returns the message that was passed in.
-    fn echo<K, RV>(&mut self, msg: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue;
+    fn echo<K: ToRedisArgs, RV: FromRedisValue>(msg: K) -> RV {
+        cmd("ECHO").arg(msg)
+    }
+
+    /// Moves a key to another database.
+    ///
+    /// Returns 1 if the key was moved, 0 if it does not exist or the target
+    /// database already has it.
+    fn move_key<K: ToRedisArgs, RV: FromRedisValue>(key: K, db: i64) -> RV {
+        cmd("MOVE").arg(key).arg(db)
+    }
+
+    /// Switches this client to the database at `db`.
+    ///
+    /// Other clients sharing the storage keep their own selection. Fails if
+    /// `db` is out of range.
+    fn select<RV: FromRedisValue>(db: i64) -> RV {
+        cmd("SELECT").arg(db)
+    }
+
+    /// Exchanges the contents of two databases, for every client.
+    fn swapdb<RV: FromRedisValue>(a: i64, b: i64) -> RV {
+        cmd("SWAPDB").arg(a).arg(b)
+    }
 
     /// Removes all keys from the current database.
     ///
     /// Returns "OK".
-    fn flushdb<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue;
+    fn flushdb<RV: FromRedisValue>() -> RV {
+        cmd("FLUSHDB")
+    }
 
-    /// Removes all keys from all databases.
+    /// Removes all keys from every database.
     ///
     /// Returns "OK".
-    fn flushall<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue;
+    fn flushall<RV: FromRedisValue>() -> RV {
+        cmd("FLUSHALL")
+    }
 
     /// Returns the number of keys in the current database.
-    fn dbsize<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue;
+    fn dbsize<RV: FromRedisValue>() -> RV {
+        cmd("DBSIZE")
+    }
 
     /// Returns the UNIX timestamp of the last successful save.
-    fn lastsave<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue;
+    fn lastsave<RV: FromRedisValue>() -> RV {
+        cmd("LASTSAVE")
+    }
 
     /// Returns the current server time.
     ///
     /// Returns a two-element array: [seconds, microseconds].
-    fn time<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue;
+    fn time<RV: FromRedisValue>() -> RV {
+        cmd("TIME")
+    }
+
+    /// Adds an entry to a stream, creating the stream if needed.
+    ///
+    /// `entry_id` has the form `"timestamp-sequence"`; with `None`, one is
+    /// generated from the current time. Returns the ID of the new entry.
+    fn xadd<K: ToRedisArgs, F: ToRedisArgs, V: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        entry_id: Option<&str>,
+        values: Vec<(F, V)>,
+    ) -> RV {
+        let mut cmd = cmd("XADD");
+        cmd.arg(key).arg(entry_id.unwrap_or("*"));
+        for (field, value) in values {
+            cmd.arg(field).arg(value);
+        }
+        cmd
+    }
+
+    /// Returns the number of entries in a stream.
+    fn xlen<K: ToRedisArgs, RV: FromRedisValue>(key: K) -> RV {
+        cmd("XLEN").arg(key)
+    }
+
+    /// Trims a stream to at most `maxlen` entries, dropping the oldest.
+    ///
+    /// With `approximate`, trims as `MAXLEN ~` does, to 10% below `maxlen`.
+    /// Returns the number of entries removed.
+    fn xtrim<K: ToRedisArgs, RV: FromRedisValue>(key: K, maxlen: usize, approximate: bool) -> RV {
+        cmd("XTRIM")
+            .arg(key)
+            .arg("MAXLEN")
+            .arg(if approximate { "~" } else { "=" })
+            .arg(maxlen)
+    }
+
+    /// Deletes entries from a stream.
+    ///
+    /// Returns the number of entries deleted.
+    fn xdel<K: ToRedisArgs, RV: FromRedisValue>(key: K, entry_ids: Vec<&str>) -> RV {
+        cmd("XDEL").arg(key).arg(entry_ids)
+    }
+
+    /// Returns the entries of a stream with IDs from `start` to `end`,
+    /// inclusive, oldest first.
+    ///
+    /// `"-"` and `"+"` stand for the lowest and highest IDs. With a `count`,
+    /// returns at most that many entries.
+    fn xrange<K: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        start: &str,
+        end: &str,
+        count: Option<usize>,
+    ) -> RV {
+        let mut cmd = cmd("XRANGE");
+        cmd.arg(key).arg(start).arg(end);
+        if let Some(count) = count {
+            cmd.arg("COUNT").arg(count);
+        }
+        cmd
+    }
+
+    /// Like [`xrange`](Self::xrange), newest first, so the range goes from
+    /// `end` down to `start`.
+    fn xrevrange<K: ToRedisArgs, RV: FromRedisValue>(
+        key: K,
+        end: &str,
+        start: &str,
+        count: Option<usize>,
+    ) -> RV {
+        let mut cmd = cmd("XREVRANGE");
+        cmd.arg(key).arg(end).arg(start);
+        if let Some(count) = count {
+            cmd.arg("COUNT").arg(count);
+        }
+        cmd
+    }
 }
 
+impl<T: ConnectionLike> Commands for T {}
+
 /// A Redis client for executing commands against an in-memory store.
 ///
-/// The client provides methods for all common Redis operations including
-/// strings, lists, sets, hashes, sorted sets, and server commands.
+/// The client has an `async` method for every method of [`Commands`], with
+/// the same signature, and also implements the trait itself. For code
+/// without an async runtime, see [`blocking::Client`].
 ///
 /// # Example
 ///
 /// ```rust,no_run
-/// use not_redis::Commands;
 /// use not_redis::Client;
 ///
 /// #[tokio::main]
@@ -745,954 +1255,173 @@ RedisValue;
 ///     let mut client = Client::new();
 ///     client.start().await;
 ///     
-///     client.set("key", "value").await?;
+///     let _: () = client.set("key", "value").await?;
 ///     let value: String = client.get("key").await?;
 ///     
 ///     Ok(())
 /// }
 /// ```
 pub struct Client {
-    storage: StorageEngine,
+    inner: blocking::Client,
 }
 
 impl Client {
     /// Creates a new Client with a fresh in-memory storage engine.
     pub fn new() -> Self {
-        let storage = StorageEngine::new(100);
-        Self { storage }
+        Self {
+            inner: blocking::Client::new(),
+        }
+    }
+
+    /// Returns a builder for a client whose storage engine has non-default
+    /// settings, such as more shards.
+    ///
+    /// ```rust
+    /// use not_redis::Client;
+    ///
+    /// let client = Client::builder().shards(64).initial_capacity(100_000).build();
+    /// ```
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
     }
 
     /// Creates a new Client with an existing storage engine.
     ///
     /// This allows sharing a storage engine between multiple clients.
-    pub fn with_storage(storage: StorageEngine) -> Self {
-        Self { storage }
+    pub fn from_storage(storage: StorageEngine) -> Self {
+        Self {
+            inner: blocking::Client::from_storage(storage),
+        }
+    }
+
+    /// Returns a client that sees only the keys starting with `prefix`.
+    ///
+    /// Keys are prefixed on the way in and stripped from `KEYS` and `SCAN`
+    /// output, so subsystems sharing one storage engine cannot see or clobber
+    /// each other's keys. `SCAN` only walks the namespace, and `DBSIZE` and
+    /// `FLUSHDB` count and clear only its keys. The new client shares this
+    /// client's storage and starts on its current database.
+    ///
+    /// ```rust
+    /// # async fn run() -> not_redis::RedisResult<()> {
+    /// use not_redis::Client;
+    ///
+    /// let client = Client::new();
+    /// let mut billing = client.namespace("billing:");
+    /// let _: () = billing.set("invoice:1", "paid").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn namespace(&self, prefix: impl AsRef<[u8]>) -> Client {
+        Self {
+            inner: self.inner.namespace(prefix),
+        }
     }
 
     /// Starts the client, initializing the background expiration sweeper.
     ///
     /// This must be called before using the client to ensure expired keys
     /// are properly cleaned up.
-    pub async fn start(&self) {
-        self.storage.start_expiration_sweeper().await;
+    ///
+    /// The sweeper runs on the [`DefaultExecutor`](crate::runtime::DefaultExecutor),
+    /// which is the current Tokio runtime when the `tokio` feature is enabled.
+    /// Use the returned handle to stop it; it also stops on its own once the
+    /// client and every other user of its storage have been dropped.
+    pub async fn start(&self) -> SweeperHandle {
+        self.inner.storage().start_expiration_sweeper()
     }
+}
 
-    pub fn builder() -> ClientBuilder {
-        ClientBuilder::new()
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub async fn set_maxmemory(&self, maxmemory: usize) {
-        self.storage.set_maxmemory(maxmemory).await;
+impl ConnectionLike for Client {
+    fn req_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        self.inner.req_command(cmd)
     }
+}
 
-    pub async fn set_maxmemory_policy(&self, policy: MaxMemoryPolicy) {
-        self.storage.set_maxmemory_policy(policy).await;
-    }
+/// Builds a [`Client`] with its own, configured storage engine.
+///
+/// Created by [`Client::builder`]. Each method sets the matching option on
+/// [`StorageEngineBuilder`]; see there for details and defaults.
+#[derive(Debug, Clone, Default)]
+pub struct ClientBuilder {
+    storage: StorageEngineBuilder,
+}
 
-    pub async fn get_maxmemory(&self) -> Option<usize> {
-        self.storage.get_maxmemory().await
+impl ClientBuilder {
+    /// Sets the number of databases.
+    pub fn databases(mut self, count: usize) -> Self {
+        self.storage = self.storage.databases(count);
+        self
     }
 
-    pub async fn get_maxmemory_policy(&self) -> MaxMemoryPolicy {
-        self.storage.get_maxmemory_policy().await
+    /// Sets the number of shards each database's keyspace is split into.
+    pub fn shards(mut self, shards: usize) -> Self {
+        self.storage = self.storage.shards(shards);
+        self
     }
 
-    pub fn current_memory_usage(&self) -> usize {
-        self.storage.current_memory_usage()
+    /// Reserves room for `capacity` keys up front.
+    pub fn initial_capacity(mut self, capacity: usize) -> Self {
+        self.storage = self.storage.initial_capacity(capacity);
+        self
     }
-}
-
-pub struct ClientBuilder {
-    config: StorageConfig,
-}
 
-impl ClientBuilder {
-    pub fn new() -> Self {
-        Self {
-            config: StorageConfig::new(),
-        }
+    /// Sets how often the background sweeper runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn sweep_interval(mut self, interval: Duration) -> Self {
+        self.storage = self.storage.sweep_interval(interval);
+        self
     }
 
-    pub fn maxmemory(mut self, maxmemory: usize) -> Self {
-        self.config.maxmemory = Some(maxmemory);
+    /// Sets how hard the background sweeper works, from 1 to 10.
+    pub fn active_expire_effort(mut self, effort: u8) -> Self {
+        self.storage = self.storage.active_expire_effort(effort);
         self
     }
 
-    pub fn maxmemory_policy(mut self, policy: MaxMemoryPolicy) -> Self {
-        self.config.maxmemory_policy = policy;
+    /// Sets how many fields to reserve when a write creates a hash.
+    pub fn hash_capacity(mut self, capacity: usize) -> Self {
+        self.storage = self.storage.hash_capacity(capacity);
         self
     }
 
-    pub fn build(self) -> Client {
-        let storage = StorageEngine::with_config(self.config);
-        Client { storage }
+    /// Sets how many members to reserve when a write creates a set.
+    pub fn set_capacity(mut self, capacity: usize) -> Self {
+        self.storage = self.storage.set_capacity(capacity);
+        self
     }
-}
 
-impl Default for ClientBuilder {
-    fn default() -> Self {
-        Self::new()
+    /// Sets how many elements to reserve when a write creates a list.
+    pub fn list_capacity(mut self, capacity: usize) -> Self {
+        self.storage = self.storage.list_capacity(capacity);
+        self
     }
-}
 
-impl Default for Client {
-    fn default() -> Self {
-        Self::new()
+    /// Sets the hash function for keys.
+    pub fn hasher(mut self, hasher: HasherKind) -> Self {
+        self.storage = self.storage.hasher(hasher);
+        self
     }
-}
 
-macro_rules! impl_command {
-    ($method:ident -> $ret:ty) => {
-        fn $method<K, RV>(&mut self, key: K) -> RedisResult<RV>
-        where
-            K: ToRedisArgs,
-            RV: FromRedisValue,
-        {
-            let mut cmd = Cmd::new();
-            cmd.arg(stringify!($method).to_uppercase()).arg(key);
-            let value = execute_command(&self.storage, &cmd)?;
-            RV::from_redis_value(value)
-        }
-    };
-    ($method:ident, $arg1:ident -> $ret:ty) => {
-        fn $method<K, $arg1, RV>(&mut self, key: K, $arg1: $arg1) -> RedisResult<RV>
-        where
-            K: ToRedisArgs,
-            $arg1: ToRedisArgs,
-            RV: FromRedisValue,
-        {
-            let mut cmd = Cmd::new();
-            cmd.arg(stringify!($method).to_uppercase()).arg(key).arg($arg1);
-            let value = execute_command(&self.storage, &cmd)?;
-            RV::from_redis_value(value)
-        }
-    };
-}
+    /// Sets the clock used for deadlines, TTLs and stream IDs.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.storage = self.storage.clock(clock);
+        self
+    }
 
-impl Commands for Client {
-    fn get<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("GET").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn set<K, V, RV>(&mut self, key: K, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("SET").arg(key).arg(value);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn set_options<K, V, RV>(
-        &mut self,
-        key: K,
-        value: V,
-        options: SetOptions,
-    ) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("SET").arg(key).arg(value);
-        if let Some(ex) = options.ex {
-            cmd.arg("EX").arg(ex.as_secs());
-        }
-        if let Some(px) = options.px {
-            cmd.arg("PX").arg(px.as_millis() as i64);
-        }
-        if options.nx {
-            cmd.arg("NX");
-        }
-        if options.xx {
-            cmd.arg("XX");
-        }
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn mset<K, V, RV>(&mut self, items: &[(K, V)]) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("MSET");
-        for (k, v) in items {
-            cmd.arg(k).arg(v);
-        }
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn mget<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("MGET").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn del<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("DEL").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn exists<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("EXISTS").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn append<K, V, RV>(&mut self, key: K, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("APPEND").arg(key).arg(value);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn getrange<K, RV>(&mut self, key: K, from: isize, to: isize) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("GETRANGE").arg(key).arg(from).arg(to);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn setrange<K, V, RV>(&mut self, key: K, offset: isize, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("SETRANGE").arg(key).arg(offset).arg(value);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn strlen<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("STRLEN").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn incr<K, V, RV>(&mut self, key: K, delta: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("INCRBY").arg(key).arg(delta);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn decr<K, V, RV>(&mut self, key: K, delta: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("DECRBY").arg(key).arg(delta);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn hget<K, F, RV>(&mut self, key: K, field: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("HGET").arg(key).arg(field);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn hmget<K, F, RV>(&mut self, key: K, fields: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("HMGET").arg(key).arg(fields);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn hset<K, F, V, RV>(&mut self, key: K, field: F, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("HSET").arg(key).arg(field).arg(value);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn hdel<K, F, RV>(&mut self, key: K, field: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("HDEL").arg(key).arg(field);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn hgetall<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("HGETALL").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn hkeys<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("HKEYS").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn hvals<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("HVALS").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn hlen<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("HLEN").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn hincr<K, F, D, RV>(&mut self, key: K, field: F, delta: D) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        D: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("HINCRBY").arg(key).arg(field).arg(delta);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn hexists<K, F, RV>(&mut self, key: K, field: F) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        F: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("HEXISTS").arg(key).arg(field);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn lpush<K, V, RV>(&mut self, key: K, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("LPUSH").arg(key).arg(value);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn rpush<K, V, RV>(&mut self, key: K, value: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("RPUSH").arg(key).arg(value);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn lpop<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("LPOP").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn rpop<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("RPOP").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn llen<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("LLEN").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn lrange<K, RV>(&mut self, key: K, start: isize, stop: isize) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("LRANGE").arg(key).arg(start).arg(stop);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn lindex<K, RV>(&mut self, key: K, index: isize) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("LINDEX").arg(key).arg(index);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn sadd<K, V, RV>(&mut self, key: K, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("SADD").arg(key).arg(member);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn srem<K, V, RV>(&mut self, key: K, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("SREM").arg(key).arg(member);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn smembers<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("SMEMBERS").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn sismember<K, V, RV>(&mut self, key: K, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("SISMEMBER").arg(key).arg(member);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn scard<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("SCARD").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn spop<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("SPOP").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn zadd<K, S, V, RV>(&mut self, key: K, score: S, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        S: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("ZADD").arg(key).arg(score).arg(member);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn zrem<K, V, RV>(&mut self, key: K, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("ZREM").arg(key).arg(member);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn zrange<K, RV>(&mut self, key: K, start: isize, stop: isize) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("ZRANGE").arg(key).arg(start).arg(stop);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn zrangebyscore<K, RV>(&mut self, key: K, min: &str, max: &str) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("ZRANGEBYSCORE").arg(key).arg(min).arg(max);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn zcard<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("ZCARD").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn zscore<K, V, RV>(&mut self, key: K, member: V) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        V: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("ZSCORE").arg(key).arg(member);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn zcount<K, RV>(&mut self, key: K, min: &str, max: &str) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("ZCOUNT").arg(key).arg(min).arg(max);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn expire<K>(&mut self, key: K, seconds: i64) -> RedisResult<bool>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("EXPIRE").arg(key).arg(seconds);
-        let value = execute_command(&self.storage, &cmd)?;
-        bool::from_redis_value(value).map_err(|_| RedisError::ParseError)
-    }
-
-    fn expire_at<K>(&mut self, key: K, ts: i64) -> RedisResult<bool>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("EXPIREAT").arg(key).arg(ts);
-        let value = execute_command(&self.storage, &cmd)?;
-        bool::from_redis_value(value).map_err(|_| RedisError::ParseError)
-    }
-
-    fn pexpire<K>(&mut self, key: K, ms: i64) -> RedisResult<bool>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("PEXPIRE").arg(key).arg(ms);
-        let value = execute_command(&self.storage, &cmd)?;
-        bool::from_redis_value(value).map_err(|_| RedisError::ParseError)
-    }
-
-    fn pexpire_at<K>(&mut self, key: K, ts: i64) -> RedisResult<bool>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("PEXPIREAT").arg(key).arg(ts);
-        let value = execute_command(&self.storage, &cmd)?;
-        bool::from_redis_value(value).map_err(|_| RedisError::ParseError)
-    }
-
-    fn ttl<K>(&mut self, key: K) -> RedisResult<IntegerReplyOrNoOp>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("TTL").arg(key);
-        let val: i64 = i64::from_redis_value(execute_command(&self.storage, &cmd)?)
-            .map_err(|_| RedisError::ParseError)?;
-        Ok(IntegerReplyOrNoOp::Integer(val))
-    }
-
-    fn pttl<K>(&mut self, key: K) -> RedisResult<IntegerReplyOrNoOp>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("PTTL").arg(key);
-        let val: i64 = i64::from_redis_value(execute_command(&self.storage, &cmd)?)
-            .map_err(|_| RedisError::ParseError)?;
-        Ok(IntegerReplyOrNoOp::Integer(val))
-    }
-
-    fn persist<K>(&mut self, key: K) -> RedisResult<bool>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("PERSIST").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        bool::from_redis_value(value).map_err(|_| RedisError::ParseError)
-    }
-
-    fn expire_time<K>(&mut self, key: K) -> RedisResult<IntegerReplyOrNoOp>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("EXPIRETIME").arg(key);
-        let val: i64 = i64::from_redis_value(execute_command(&self.storage, &cmd)?)
-            .map_err(|_| RedisError::ParseError)?;
-        Ok(IntegerReplyOrNoOp::Integer(val))
-    }
-
-    fn setbit<K>(&mut self, key: K, offset: usize, value: bool) -> RedisResult<bool>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("SETBIT").arg(key).arg(offset).arg(if value { 1i64 } else { 0i64 });
-        let val: i64 = i64::from_redis_value(execute_command(&self.storage, &cmd)?)
-            .map_err(|_| RedisError::ParseError)?;
-        Ok(val != 0)
-    }
-
-    fn getbit<K>(&mut self, key: K, offset: usize) -> RedisResult<bool>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("GETBIT").arg(key).arg(offset);
-        let val: i64 = i64::from_redis_value(execute_command(&self.storage, &cmd)?)
-            .map_err(|_| RedisError::ParseError)?;
-        Ok(val != 0)
-    }
-
-    fn bitcount<K>(&mut self, key: K) -> RedisResult<usize>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("BITCOUNT").arg(key);
-        let val: i64 = i64::from_redis_value(execute_command(&self.storage, &cmd)?)
-            .map_err(|_| RedisError::ParseError)?;
-        Ok(val as usize)
-    }
-
-    fn bitcount_range<K>(&mut self, key: K, start: usize, end: usize) -> RedisResult<usize>
-    where
-        K: ToRedisArgs,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("BITCOUNT").arg(key).arg(start).arg(end);
-        let val: i64 = i64::from_redis_value(execute_command(&self.storage, &cmd)?)
-            .map_err(|_| RedisError::ParseError)?;
-        Ok(val as usize)
-    }
-
-    fn bit_and<D, S, RV>(&mut self, dstkey: D, srckeys: S) -> RedisResult<RV>
-    where
-        D: ToRedisArgs,
-        S: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        Err(RedisError::NotSupported)
-    }
-
-    fn bit_or<D, S, RV>(&mut self, dstkey: D, srckeys: S) -> RedisResult<RV>
-    where
-        D: ToRedisArgs,
-        S: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        Err(RedisError::NotSupported)
-    }
-
-    fn bit_xor<D, S, RV>(&mut self, dstkey: D, srckeys: S) -> RedisResult<RV>
-    where
-        D: ToRedisArgs,
-        S: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        Err(RedisError::NotSupported)
-    }
-
-    fn bit_not<D, S, RV>(&mut self, dstkey: D, srckey: S) -> RedisResult<RV>
-    where
-        D: ToRedisArgs,
-        S: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        Err(RedisError::NotSupported)
-    }
-
-    fn keys<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("KEYS").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn key_type<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("TYPE").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn rename<K, N, RV>(&mut self, key: K, new_key: N) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        N: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("RENAME").arg(key).arg(new_key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn rename_nx<K, N, RV>(&mut self, key: K, new_key: N) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        N: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("RENAMENX").arg(key).arg(new_key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn unlink<K, RV>(&mut self, key: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("UNLINK").arg(key);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn copy<KSrc, KDst, Db, RV>(
-        &mut self,
-        source: KSrc,
-        destination: KDst,
-        _options: CopyOptions<Db>,
-    ) -> RedisResult<RV>
-    where
-        KSrc: ToRedisArgs,
-        KDst: ToRedisArgs,
-        Db: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("COPY").arg(source).arg(destination);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn ping<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("PING");
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn echo<K, RV>(&mut self, msg: K) -> RedisResult<RV>
-    where
-        K: ToRedisArgs,
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("ECHO").arg(msg);
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn flushdb<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("FLUSHDB");
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn flushall<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("FLUSHALL");
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn dbsize<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("DBSIZE");
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn lastsave<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("LASTSAVE");
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
-    }
-
-    fn time<RV>(&mut self) -> RedisResult<RV>
-    where
-        RV: FromRedisValue,
-    {
-        let mut cmd = Cmd::new();
-        cmd.arg("TIME");
-        let value = execute_command(&self.storage, &cmd)?;
-        RV::from_redis_value(value)
+    /// Creates the client.
+    ///
+    /// The expiration sweeper is not started; call [`Client::start`].
+    pub fn build(self) -> Client {
+        Client::from_storage(self.storage.build())
     }
 }
@@ -0,0 +1,97 @@
+//! Time sources for the storage engine.
+//!
+//! Every deadline, TTL reply and stream ID is computed from the engine's
+//! [`Clock`]. Deadlines are wall-clock times, stored as Unix-epoch
+//! milliseconds, so they can be compared with `EXPIREAT` timestamps.
+//!
+//! The default clock is [`SystemClock`]. Tests can install a [`TestClock`]
+//! and move time forward by hand instead of sleeping:
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use not_redis::blocking::Client;
+//! use not_redis::clock::TestClock;
+//! use not_redis::{Commands, StorageEngine};
+//!
+//! let clock = TestClock::new();
+//! let mut client = Client::from_storage(StorageEngine::new().with_clock(clock.clone()));
+//!
+//! let _: () = client.set("session", "data")?;
+//! client.expire("session", 10)?;
+//! clock.advance(Duration::from_secs(10));
+//! let exists: i64 = client.exists("session")?;
+//! assert_eq!(exists, 0);
+//! # Ok::<(), not_redis::RedisError>(())
+//! ```
+
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A source of the current time.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current wall-clock time.
+    fn system_time(&self) -> SystemTime;
+}
+
+/// The real clock, backed by [`SystemTime::now`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Converts `time` to milliseconds since the Unix epoch, clamping times
+/// before the epoch to zero.
+pub(crate) fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+}
+
+/// A clock that only moves when told to.
+///
+/// Clones share the same time, so a test can keep one clone and hand
+/// another to the engine.
+#[derive(Debug, Clone)]
+pub struct TestClock {
+    start: SystemTime,
+    elapsed_nanos: Arc<AtomicU64>,
+}
+
+impl TestClock {
+    /// Creates a clock frozen at the current time, truncated to whole
+    /// milliseconds so that deadlines fall exactly on `advance` steps.
+    pub fn new() -> Self {
+        Self {
+            start: UNIX_EPOCH + Duration::from_millis(unix_millis(SystemTime::now())),
+            elapsed_nanos: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Moves the clock forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        let nanos = u64::try_from(by.as_nanos()).unwrap_or(u64::MAX);
+        self.elapsed_nanos.fetch_add(nanos, Ordering::SeqCst);
+    }
+
+    /// Returns how far the clock has been moved since it was created.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_nanos.load(Ordering::SeqCst))
+    }
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for TestClock {
+    fn system_time(&self) -> SystemTime {
+        self.start + self.elapsed()
+    }
+}
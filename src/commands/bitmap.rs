@@ -0,0 +1,422 @@
+//! Bitmap command handlers: bit-level operations on string values.
+//!
+//! Bit `0` is the most significant bit of the first byte, as in Redis.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use super::string::clamp_range;
+use super::{BitFieldOffset, BitFieldOverflow, BitFieldType, BitUnit};
+use crate::error::{RedisError, RedisResult};
+use crate::storage::{RedisData, StorageEngine};
+use crate::types::Value;
+
+/// The largest bit offset Redis accepts, as strings are at most 512 MB.
+const MAX_BIT_OFFSET: i64 = 512 * 1024 * 1024 * 8 - 1;
+
+/// The operation `BITOP` applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BitOp {
+    And,
+    Or,
+    Xor,
+    Not,
+}
+
+/// One subcommand of `BITFIELD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BitFieldOp {
+    Get(BitFieldType, BitFieldOffset),
+    Set(BitFieldType, BitFieldOffset, i64),
+    IncrBy(BitFieldType, BitFieldOffset, i64),
+    Overflow(BitFieldOverflow),
+}
+
+/// `SETBIT key offset value`: sets or clears the bit at `offset` and
+/// returns its previous value.
+///
+/// The string is created or padded with zero bytes as needed.
+pub(crate) fn setbit(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    offset: i64,
+    value: i64,
+) -> RedisResult<Value> {
+    let offset = bit_offset(offset)?;
+    let value = match value {
+        0 => false,
+        1 => true,
+        _ => {
+            return Err(RedisError::InvalidArgument(
+                "bit is not an integer or out of range".to_string(),
+            ))
+        }
+    };
+    let (byte, mask) = (offset / 8, 0x80 >> (offset % 8));
+    storage.upsert(
+        key,
+        || RedisData::String(Vec::new()),
+        |data| {
+            let s = data.as_string_mut().ok_or(RedisError::WrongType)?;
+            if s.len() <= byte {
+                s.resize(byte + 1, 0);
+            }
+            let previous = s[byte] & mask != 0;
+            if value {
+                s[byte] |= mask;
+            } else {
+                s[byte] &= !mask;
+            }
+            Ok(Value::Int(previous as i64))
+        },
+    )
+}
+
+/// `GETBIT key offset`: returns the bit at `offset`, or `0` past the end of
+/// the string or if the key is missing.
+pub(crate) fn getbit(storage: &StorageEngine, key: &[u8], offset: i64) -> RedisResult<Value> {
+    let offset = bit_offset(offset)?;
+    storage
+        .read(key, |data| {
+            let s = data.as_string().ok_or(RedisError::WrongType)?;
+            let bit = offset < s.len() * 8 && bit_at(&s, offset);
+            Ok(Value::Int(bit as i64))
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `BITCOUNT key [start end [BYTE | BIT]]`: counts the set bits of the
+/// string at `key`, or of its inclusive `start..=end` range.
+///
+/// Negative offsets count from the end of the string.
+pub(crate) fn bitcount(
+    storage: &StorageEngine,
+    key: &[u8],
+    range: Option<(i64, i64, BitUnit)>,
+) -> RedisResult<Value> {
+    storage
+        .read(key, |data| {
+            let s = data.as_string().ok_or(RedisError::WrongType)?;
+            let bits = match range {
+                None => (!s.is_empty()).then(|| 0..s.len() * 8),
+                Some((start, end, unit)) => bit_range(s.len(), start, end, unit),
+            };
+            let count = bits.map_or(0, |bits| count_bits(&s, bits));
+            Ok(Value::Int(count as i64))
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `BITPOS key bit [start [end [BYTE | BIT]]]`: returns the position of the
+/// first bit set to `bit`, or `-1`.
+///
+/// Without `end`, the string counts as padded with zero bits on the right,
+/// so looking for a `0` in a string of ones finds the bit just past it.
+pub(crate) fn bitpos(
+    storage: &StorageEngine,
+    key: &[u8],
+    bit: bool,
+    start: Option<i64>,
+    end: Option<i64>,
+    unit: BitUnit,
+) -> RedisResult<Value> {
+    let Some(result) = storage.read(key, |data| {
+        let s = data.as_string().ok_or(RedisError::WrongType)?;
+        let Some(bits) = bit_range(s.len(), start.unwrap_or(0), end.unwrap_or(-1), unit) else {
+            return Ok(-1);
+        };
+        Ok(match first_bit(&s, bits.clone(), bit) {
+            Some(position) => position as i64,
+            None if !bit && end.is_none() => bits.end as i64,
+            None => -1,
+        })
+    }) else {
+        return Ok(Value::Int(if bit { -1 } else { 0 }));
+    };
+    result.map(Value::Int)
+}
+
+/// `BITOP operation destkey key [key ...]`: stores the bitwise `op` of the
+/// strings at `sources` in `dest` and returns its length.
+///
+/// Shorter strings are padded with zero bytes. If the result is empty,
+/// `dest` is deleted.
+pub(crate) fn bitop(
+    storage: &StorageEngine,
+    op: BitOp,
+    dest: Vec<u8>,
+    sources: &[Vec<u8>],
+) -> RedisResult<Value> {
+    let sources = storage
+        .read_many(sources, |data| data.as_string().map(Cow::into_owned))
+        .into_iter()
+        .map(|value| {
+            value
+                .unwrap_or(Some(Vec::new()))
+                .ok_or(RedisError::WrongType)
+        })
+        .collect::<RedisResult<Vec<_>>>()?;
+    let len = sources.iter().map(Vec::len).max().unwrap_or(0);
+    if len == 0 {
+        storage.remove(&dest);
+        return Ok(Value::Int(0));
+    }
+
+    let byte = |source: &Vec<u8>, i: usize| source.get(i).copied().unwrap_or(0);
+    let result: Vec<u8> = (0..len)
+        .map(|i| {
+            let mut bytes = sources.iter().map(|source| byte(source, i));
+            let first = bytes.next().unwrap_or(0);
+            match op {
+                BitOp::And => bytes.fold(first, |acc, b| acc & b),
+                BitOp::Or => bytes.fold(first, |acc, b| acc | b),
+                BitOp::Xor => bytes.fold(first, |acc, b| acc ^ b),
+                BitOp::Not => !first,
+            }
+        })
+        .collect();
+    storage.set(dest, RedisData::String(result), None);
+    Ok(Value::Int(len as i64))
+}
+
+/// `BITFIELD key [GET type offset | SET type offset value | INCRBY type
+/// offset increment | OVERFLOW WRAP | SAT | FAIL ...]`: reads and writes
+/// integer fields of arbitrary width in the string at `key`, replying with
+/// one entry per `GET`, `SET` and `INCRBY`.
+///
+/// Every operation is checked before any is applied. If there are writes,
+/// the string is created or padded up to the furthest written field and all
+/// operations run under one lock, in place. `read_only` is `BITFIELD_RO`,
+/// which only accepts `GET`.
+pub(crate) fn bitfield(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    ops: &[BitFieldOp],
+    read_only: bool,
+) -> RedisResult<Value> {
+    let mut offsets = Vec::with_capacity(ops.len());
+    let mut write_end = None;
+    for op in ops {
+        let offset = match *op {
+            BitFieldOp::Get(ty, offset) => field_offset(ty, offset)?,
+            BitFieldOp::Set(ty, offset, _) | BitFieldOp::IncrBy(ty, offset, _) => {
+                if read_only {
+                    return Err(RedisError::InvalidArgument(
+                        "BITFIELD_RO only supports the GET subcommand".to_string(),
+                    ));
+                }
+                let offset = field_offset(ty, offset)?;
+                let end = offset + ty.bits as usize;
+                write_end = Some(write_end.map_or(end, |write_end: usize| write_end.max(end)));
+                offset
+            }
+            BitFieldOp::Overflow(_) => 0,
+        };
+        offsets.push(offset);
+    }
+
+    let Some(write_end) = write_end else {
+        let gets = |s: &[u8]| {
+            let replies = ops
+                .iter()
+                .zip(&offsets)
+                .filter_map(|(op, &offset)| match *op {
+                    BitFieldOp::Get(ty, _) => Some(Value::Int(get_field(s, offset, ty) as i64)),
+                    _ => None,
+                });
+            Value::Array(replies.collect())
+        };
+        return storage
+            .read(&key, |data| {
+                let s = data.as_string().ok_or(RedisError::WrongType)?;
+                Ok(gets(&s))
+            })
+            .unwrap_or_else(|| Ok(gets(&[])));
+    };
+
+    storage.upsert(
+        key,
+        || RedisData::String(Vec::new()),
+        |data| {
+            let s = data.as_string_mut().ok_or(RedisError::WrongType)?;
+            if s.len() * 8 < write_end {
+                s.resize(write_end.div_ceil(8), 0);
+            }
+            let mut overflow = BitFieldOverflow::Wrap;
+            let mut replies = Vec::new();
+            for (op, &offset) in ops.iter().zip(&offsets) {
+                let reply = match *op {
+                    BitFieldOp::Get(ty, _) => Some(get_field(s, offset, ty)),
+                    BitFieldOp::Set(ty, _, value) => {
+                        let old = get_field(s, offset, ty);
+                        // Unsigned fields take the value's bits as unsigned,
+                        // so negative values are out of range.
+                        let value = if ty.signed {
+                            value as i128
+                        } else {
+                            value as u64 as i128
+                        };
+                        fit_field(ty, value, overflow).map(|new| {
+                            set_field(s, offset, ty, new);
+                            old
+                        })
+                    }
+                    BitFieldOp::IncrBy(ty, _, increment) => {
+                        let old = get_field(s, offset, ty);
+                        fit_field(ty, old + increment as i128, overflow).inspect(|&new| {
+                            set_field(s, offset, ty, new);
+                        })
+                    }
+                    BitFieldOp::Overflow(new) => {
+                        overflow = new;
+                        continue;
+                    }
+                };
+                replies.push(reply.map_or(Value::Null, |value| Value::Int(value as i64)));
+            }
+            Ok(Value::Array(replies))
+        },
+    )
+}
+
+pub(crate) fn invalid_bitfield_type() -> RedisError {
+    RedisError::InvalidArgument(
+        "Invalid bitfield type. Use something like i16 u8. Note that u64 is not supported but i64 is."
+            .to_string(),
+    )
+}
+
+pub(crate) fn invalid_bit_offset() -> RedisError {
+    RedisError::InvalidArgument("bit offset is not an integer or out of range".to_string())
+}
+
+/// Checks a field's type and resolves its offset to a bit offset, making
+/// sure the whole field fits in a string of the maximum size.
+fn field_offset(ty: BitFieldType, offset: BitFieldOffset) -> RedisResult<usize> {
+    let max_bits = if ty.signed { 64 } else { 63 };
+    if !(1..=max_bits).contains(&ty.bits) {
+        return Err(invalid_bitfield_type());
+    }
+    let offset = match offset {
+        BitFieldOffset::Bit(offset) => Some(offset),
+        BitFieldOffset::Index(index) => index.checked_mul(ty.bits as usize),
+    };
+    offset
+        .filter(|&offset| {
+            offset
+                .checked_add(ty.bits as usize - 1)
+                .is_some_and(|last| last as u64 <= MAX_BIT_OFFSET as u64)
+        })
+        .ok_or_else(invalid_bit_offset)
+}
+
+/// Reads the field of type `ty` at bit `offset`, with bits past the end of
+/// the string reading as zero.
+fn get_field(s: &[u8], offset: usize, ty: BitFieldType) -> i128 {
+    let bits = ty.bits as usize;
+    let mut raw: u64 = 0;
+    for i in offset..offset + bits {
+        let bit = i < s.len() * 8 && bit_at(s, i);
+        raw = (raw << 1) | bit as u64;
+    }
+    let value = raw as i128;
+    if ty.signed && raw >> (bits - 1) & 1 == 1 {
+        value - (1i128 << bits)
+    } else {
+        value
+    }
+}
+
+/// Writes the low `ty.bits` bits of `value` at bit `offset`, which the
+/// string already covers.
+fn set_field(s: &mut [u8], offset: usize, ty: BitFieldType, value: i128) {
+    let bits = ty.bits as usize;
+    for i in 0..bits {
+        let (byte, mask) = ((offset + i) / 8, 0x80 >> ((offset + i) % 8));
+        if value >> (bits - 1 - i) & 1 == 1 {
+            s[byte] |= mask;
+        } else {
+            s[byte] &= !mask;
+        }
+    }
+}
+
+/// Brings `value` into the range of `ty` as `overflow` says, or returns
+/// `None` if it is out of range and `overflow` is `FAIL`.
+fn fit_field(ty: BitFieldType, value: i128, overflow: BitFieldOverflow) -> Option<i128> {
+    let span = 1i128 << ty.bits;
+    let (min, max) = if ty.signed {
+        (-span / 2, span / 2 - 1)
+    } else {
+        (0, span - 1)
+    };
+    if (min..=max).contains(&value) {
+        return Some(value);
+    }
+    match overflow {
+        BitFieldOverflow::Wrap => {
+            let wrapped = value.rem_euclid(span);
+            Some(if wrapped > max {
+                wrapped - span
+            } else {
+                wrapped
+            })
+        }
+        BitFieldOverflow::Sat => Some(value.clamp(min, max)),
+        BitFieldOverflow::Fail => None,
+    }
+}
+
+fn bit_offset(offset: i64) -> RedisResult<usize> {
+    if (0..=MAX_BIT_OFFSET).contains(&offset) {
+        Ok(offset as usize)
+    } else {
+        Err(invalid_bit_offset())
+    }
+}
+
+fn bit_at(s: &[u8], offset: usize) -> bool {
+    s[offset / 8] & (0x80 >> (offset % 8)) != 0
+}
+
+/// Resolves an inclusive `start..=end` range in `unit`s against a string of
+/// `len` bytes, as a range of bits, or `None` if it is empty.
+fn bit_range(len: usize, start: i64, end: i64, unit: BitUnit) -> Option<Range<usize>> {
+    match unit {
+        BitUnit::Byte => clamp_range(len, start, end).map(|r| r.start * 8..r.end * 8),
+        BitUnit::Bit => clamp_range(len * 8, start, end),
+    }
+}
+
+/// Counts the set bits of `s` in the non-empty range `bits`.
+fn count_bits(s: &[u8], bits: Range<usize>) -> usize {
+    let (first, last) = (bits.start / 8, (bits.end - 1) / 8);
+    let head = 0xffu8 >> (bits.start % 8);
+    let tail = 0xffu8 << (7 - (bits.end - 1) % 8);
+    if first == last {
+        return (s[first] & head & tail).count_ones() as usize;
+    }
+    let middle: usize = s[first + 1..last]
+        .iter()
+        .map(|b| b.count_ones() as usize)
+        .sum();
+    (s[first] & head).count_ones() as usize + middle + (s[last] & tail).count_ones() as usize
+}
+
+/// Returns the offset of the first bit in `bits` that equals `bit`.
+fn first_bit(s: &[u8], bits: Range<usize>, bit: bool) -> Option<usize> {
+    let skip = if bit { 0x00 } else { 0xff };
+    let mut offset = bits.start;
+    while offset < bits.end {
+        // Skip whole bytes that cannot contain the bit.
+        if offset.is_multiple_of(8) && offset + 8 <= bits.end && s[offset / 8] == skip {
+            offset += 8;
+            continue;
+        }
+        if bit_at(s, offset) == bit {
+            return Some(offset);
+        }
+        offset += 1;
+    }
+    None
+}
@@ -0,0 +1,671 @@
+//! Hash command handlers.
+
+use rand::seq::{IteratorRandom, SliceRandom};
+
+use super::key::invalid_expire_time;
+use super::string::{deadline, nan_or_infinity, parse_integer};
+use super::{format_float, Expiry, FieldExistenceCheck, HashFieldExpirationOptions, SetExpiry};
+use crate::error::{RedisError, RedisResult};
+use crate::storage::{ExpireOption, HashValue, RedisData, StorageEngine};
+use crate::types::Value;
+
+/// `HSET key field value [field value ...]`: returns the number of fields
+/// that were added rather than updated.
+pub(crate) fn hset(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    pairs: Vec<(Vec<u8>, Vec<u8>)>,
+) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage.upsert(
+        key,
+        || storage.new_hash(),
+        |data| match data {
+            RedisData::Hash(h) => {
+                let mut added = 0;
+                for (field, value) in pairs {
+                    if h.insert(field, value, now).is_none() {
+                        added += 1;
+                    }
+                }
+                Ok(Value::Int(added))
+            }
+            _ => Err(RedisError::WrongType),
+        },
+    )
+}
+
+/// `HSETNX key field value`: sets `field` only if it is missing, returning
+/// `1` if it was set and `0` otherwise.
+pub(crate) fn hsetnx(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    field: Vec<u8>,
+    value: Vec<u8>,
+) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage.upsert(
+        key,
+        || storage.new_hash(),
+        |data| match data {
+            RedisData::Hash(h) => {
+                let vacant = !h.contains(&field, now);
+                if vacant {
+                    h.insert(field, value, now);
+                }
+                Ok(Value::Int(vacant as i64))
+            }
+            _ => Err(RedisError::WrongType),
+        },
+    )
+}
+
+/// `HGET key field`: returns the value of `field`, or null if it is missing.
+pub(crate) fn hget(storage: &StorageEngine, key: &[u8], field: &[u8]) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .read(key, |data| match data {
+            RedisData::Hash(h) => Ok(h
+                .get(field, now)
+                .map_or(Value::Null, |v| Value::String(v.clone()))),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Null))
+}
+
+/// `HGETALL key`: returns alternating field names and values.
+pub(crate) fn hgetall(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .read(key, |data| match data {
+            RedisData::Hash(h) => {
+                let mut res = Vec::with_capacity(h.len(now) * 2);
+                for (k, v) in h.iter(now) {
+                    res.push(Value::String(k.clone()));
+                    res.push(Value::String(v.clone()));
+                }
+                Ok(Value::Array(res))
+            }
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Array(Vec::new())))
+}
+
+/// `HDEL key field [field ...]`: returns the number of fields removed.
+pub(crate) fn hdel(storage: &StorageEngine, key: &[u8], fields: &[Vec<u8>]) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .write(key, |data| match data {
+            RedisData::Hash(h) => {
+                let removed = fields.iter().filter(|f| h.remove(f, now).is_some()).count();
+                Ok(Value::Int(removed as i64))
+            }
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `HMGET key field [field ...]`: returns the value of each field, or null
+/// for missing ones.
+pub(crate) fn hmget(storage: &StorageEngine, key: &[u8], fields: &[Vec<u8>]) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .read(key, |data| match data {
+            RedisData::Hash(h) => Ok(Value::Array(
+                fields
+                    .iter()
+                    .map(|f| {
+                        h.get(f, now)
+                            .map_or(Value::Null, |v| Value::String(v.clone()))
+                    })
+                    .collect(),
+            )),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or_else(|| Ok(Value::Array(vec![Value::Null; fields.len()])))
+}
+
+/// `HKEYS key`: returns every field name.
+pub(crate) fn hkeys(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .read(key, |data| match data {
+            RedisData::Hash(h) => Ok(Value::Array(
+                h.iter(now).map(|(k, _)| Value::String(k.clone())).collect(),
+            )),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Array(Vec::new())))
+}
+
+/// `HVALS key`: returns every value.
+pub(crate) fn hvals(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .read(key, |data| match data {
+            RedisData::Hash(h) => Ok(Value::Array(
+                h.iter(now).map(|(_, v)| Value::String(v.clone())).collect(),
+            )),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Array(Vec::new())))
+}
+
+/// `HLEN key`: returns the number of fields.
+pub(crate) fn hlen(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .read(key, |data| match data {
+            RedisData::Hash(h) => Ok(Value::Int(h.len(now) as i64)),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `HEXISTS key field`: returns `1` if `field` exists, `0` otherwise.
+pub(crate) fn hexists(storage: &StorageEngine, key: &[u8], field: &[u8]) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .read(key, |data| match data {
+            RedisData::Hash(h) => Ok(Value::Int(h.contains(field, now) as i64)),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `HSTRLEN key field`: returns the length of the value of `field`, or `0`
+/// if it is missing.
+pub(crate) fn hstrlen(storage: &StorageEngine, key: &[u8], field: &[u8]) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .read(key, |data| match data {
+            RedisData::Hash(h) => Ok(Value::Int(h.get(field, now).map_or(0, Vec::len) as i64)),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `HINCRBY key field increment`: adds `delta` to the integer value of
+/// `field`, starting from `0` if it is missing, and returns the result.
+pub(crate) fn hincrby(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    field: Vec<u8>,
+    delta: i64,
+) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage.upsert(
+        key,
+        || storage.new_hash(),
+        |data| {
+            let RedisData::Hash(h) = data else {
+                return Err(RedisError::WrongType);
+            };
+            let current = match h.get(&field, now) {
+                Some(value) => parse_integer(value).ok_or_else(|| {
+                    RedisError::InvalidArgument("hash value is not an integer".to_string())
+                })?,
+                None => 0,
+            };
+            let value = current.checked_add(delta).ok_or_else(|| {
+                RedisError::InvalidArgument("increment or decrement would overflow".to_string())
+            })?;
+            set_keeping_ttl(h, field, value.to_string().into_bytes(), now);
+            Ok(Value::Int(value))
+        },
+    )
+}
+
+/// `HINCRBYFLOAT key field increment`: adds `delta` to the number stored in
+/// `field`, starting from `0` if it is missing, and returns the result as a
+/// string.
+pub(crate) fn hincrbyfloat(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    field: Vec<u8>,
+    delta: f64,
+) -> RedisResult<Value> {
+    if !delta.is_finite() {
+        return Err(nan_or_infinity());
+    }
+    let now = storage.now_ms();
+    storage.upsert(
+        key,
+        || storage.new_hash(),
+        |data| {
+            let RedisData::Hash(h) = data else {
+                return Err(RedisError::WrongType);
+            };
+            let current = match h.get(&field, now) {
+                Some(value) => std::str::from_utf8(value)
+                    .ok()
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .filter(|f| f.is_finite())
+                    .ok_or_else(|| {
+                        RedisError::InvalidArgument("hash value is not a float".to_string())
+                    })?,
+                None => 0.0,
+            };
+            let value = current + delta;
+            if !value.is_finite() {
+                return Err(nan_or_infinity());
+            }
+            let value = format_float(value);
+            set_keeping_ttl(h, field, value.clone(), now);
+            Ok(Value::String(value))
+        },
+    )
+}
+
+/// Sets `field` to `value`, keeping the TTL of a live field as the
+/// increment commands do.
+fn set_keeping_ttl(h: &mut HashValue, field: Vec<u8>, value: Vec<u8>, now: u64) {
+    match h.get_mut(&field, now) {
+        Some(current) => *current = value,
+        None => {
+            h.insert(field, value, now);
+        }
+    }
+}
+
+/// The most fields a negative `HRANDFIELD` count may ask for.
+const MAX_REPEATED_FIELDS: i64 = 1 << 24;
+
+/// `HRANDFIELD key [count [WITHVALUES]]`: returns random fields.
+///
+/// Without `count` the reply is a single field, or null if the key is
+/// missing. A positive `count` returns up to `count` distinct fields; a
+/// negative one, down to `-16777216`, returns exactly `-count` fields, which
+/// may repeat. With `with_values`, each field is followed by its value.
+pub(crate) fn hrandfield(
+    storage: &StorageEngine,
+    key: &[u8],
+    count: Option<i64>,
+    with_values: bool,
+) -> RedisResult<Value> {
+    // Bound the count, as Redis does with `WITHVALUES`, so that the reply
+    // length fits. A negative count is a reply length in itself, so it gets
+    // a tighter bound that keeps the reply in memory.
+    if count.is_some_and(|count| !(-MAX_REPEATED_FIELDS..=i64::MAX / 2).contains(&count)) {
+        return Err(RedisError::InvalidArgument(
+            "value is out of range".to_string(),
+        ));
+    }
+    let now = storage.now_ms();
+    let picked = storage.read(key, |data| {
+        let RedisData::Hash(h) = data else {
+            return Err(RedisError::WrongType);
+        };
+        let mut rng = rand::thread_rng();
+        let picked: Vec<(&Vec<u8>, &Vec<u8>)> = match count {
+            Some(count) if count < 0 => {
+                let entries: Vec<_> = h.iter(now).collect();
+                if entries.is_empty() {
+                    Vec::new()
+                } else {
+                    (0..count.unsigned_abs())
+                        .filter_map(|_| entries.choose(&mut rng).copied())
+                        .collect()
+                }
+            }
+            Some(count) => {
+                let count = usize::try_from(count).unwrap_or(usize::MAX).min(h.len(now));
+                h.iter(now).choose_multiple(&mut rng, count)
+            }
+            None => h.iter(now).choose(&mut rng).into_iter().collect(),
+        };
+        Ok(picked
+            .into_iter()
+            .map(|(field, value)| (field.clone(), value.clone()))
+            .collect::<Vec<_>>())
+    });
+    let picked = picked.transpose()?.unwrap_or_default();
+    Ok(match count {
+        Some(_) => Value::Array(
+            picked
+                .into_iter()
+                .flat_map(|(field, value)| {
+                    let value = with_values.then_some(Value::String(value));
+                    std::iter::once(Value::String(field)).chain(value)
+                })
+                .collect(),
+        ),
+        None => picked
+            .into_iter()
+            .next()
+            .map_or(Value::Null, |(field, _)| Value::String(field)),
+    })
+}
+
+/// `HEXPIRE key seconds [NX | XX | GT | LT] FIELDS numfields field
+/// [field ...]`: sets a time to live on each field.
+///
+/// Replies per field like [`hpexpire_at`].
+pub(crate) fn hexpire(
+    storage: &StorageEngine,
+    key: &[u8],
+    seconds: i64,
+    option: ExpireOption,
+    fields: &[Vec<u8>],
+) -> RedisResult<Value> {
+    let ms = seconds
+        .checked_mul(1000)
+        .ok_or_else(|| invalid_expire_time("hexpire"))?;
+    relative_field_expire(storage, key, ms, option, fields, "hexpire")
+}
+
+/// `HPEXPIRE key milliseconds [NX | XX | GT | LT] FIELDS numfields field
+/// [field ...]`: like `HEXPIRE`, in milliseconds.
+pub(crate) fn hpexpire(
+    storage: &StorageEngine,
+    key: &[u8],
+    ms: i64,
+    option: ExpireOption,
+    fields: &[Vec<u8>],
+) -> RedisResult<Value> {
+    relative_field_expire(storage, key, ms, option, fields, "hpexpire")
+}
+
+/// `HEXPIREAT key unix-time-seconds [NX | XX | GT | LT] FIELDS numfields
+/// field [field ...]`: like `HPEXPIREAT`, in seconds.
+pub(crate) fn hexpire_at(
+    storage: &StorageEngine,
+    key: &[u8],
+    seconds: i64,
+    option: ExpireOption,
+    fields: &[Vec<u8>],
+) -> RedisResult<Value> {
+    let ms = seconds
+        .checked_mul(1000)
+        .ok_or_else(|| invalid_expire_time("hexpireat"))?;
+    field_expire_at(storage, key, ms, option, fields, "hexpireat")
+}
+
+/// `HPEXPIREAT key unix-time-milliseconds [NX | XX | GT | LT] FIELDS
+/// numfields field [field ...]`: sets the deadline of each field.
+///
+/// Replies with an array holding, for each field, `-2` if it is missing,
+/// `0` if the condition was not met, `1` if the deadline was set, or `2` if
+/// the deadline has passed and the field was deleted.
+pub(crate) fn hpexpire_at(
+    storage: &StorageEngine,
+    key: &[u8],
+    ms: i64,
+    option: ExpireOption,
+    fields: &[Vec<u8>],
+) -> RedisResult<Value> {
+    field_expire_at(storage, key, ms, option, fields, "hpexpireat")
+}
+
+/// Turns a relative field TTL into a deadline, rejecting ones that
+/// overflow.
+fn relative_field_expire(
+    storage: &StorageEngine,
+    key: &[u8],
+    ms: i64,
+    option: ExpireOption,
+    fields: &[Vec<u8>],
+    command: &str,
+) -> RedisResult<Value> {
+    if ms < 0 {
+        return Err(invalid_expire_time(command));
+    }
+    let at = (storage.now_ms() as i64)
+        .checked_add(ms)
+        .ok_or_else(|| invalid_expire_time(command))?;
+    field_expire_at(storage, key, at, option, fields, command)
+}
+
+fn field_expire_at(
+    storage: &StorageEngine,
+    key: &[u8],
+    at: i64,
+    option: ExpireOption,
+    fields: &[Vec<u8>],
+    command: &str,
+) -> RedisResult<Value> {
+    let at = u64::try_from(at).map_err(|_| invalid_expire_time(command))?;
+    let now = storage.now_ms();
+    let replies = storage.write(key, |data| {
+        let RedisData::Hash(h) = data else {
+            return Err(RedisError::WrongType);
+        };
+        Ok(fields
+            .iter()
+            .map(|field| {
+                if !h.contains(field, now) {
+                    -2
+                } else if !option.allows(h.deadline(field), at as i64) {
+                    0
+                } else if at <= now {
+                    h.remove(field, now);
+                    2
+                } else {
+                    h.set_deadline(field, at);
+                    1
+                }
+            })
+            .collect::<Vec<i64>>())
+    });
+    let replies = replies
+        .transpose()?
+        .unwrap_or_else(|| vec![-2; fields.len()]);
+    if replies.contains(&1) {
+        storage.schedule_field_expiry(key, at);
+    }
+    Ok(Value::Array(replies.into_iter().map(Value::Int).collect()))
+}
+
+/// `HTTL key FIELDS numfields field [field ...]`: returns the remaining
+/// time to live of each field in seconds, `-1` if it has none or `-2` if it
+/// is missing.
+pub(crate) fn httl(storage: &StorageEngine, key: &[u8], fields: &[Vec<u8>]) -> RedisResult<Value> {
+    field_deadlines(storage, key, fields, |at, now| {
+        ((at - now + 500) / 1000) as i64
+    })
+}
+
+/// `HPTTL key FIELDS numfields field [field ...]`: like `HTTL`, in
+/// milliseconds.
+pub(crate) fn hpttl(storage: &StorageEngine, key: &[u8], fields: &[Vec<u8>]) -> RedisResult<Value> {
+    field_deadlines(storage, key, fields, |at, now| (at - now) as i64)
+}
+
+/// `HEXPIRETIME key FIELDS numfields field [field ...]`: returns the
+/// deadline of each field as a Unix time in seconds, `-1` or `-2`.
+pub(crate) fn hexpire_time(
+    storage: &StorageEngine,
+    key: &[u8],
+    fields: &[Vec<u8>],
+) -> RedisResult<Value> {
+    field_deadlines(storage, key, fields, |at, _| ((at + 500) / 1000) as i64)
+}
+
+/// `HPEXPIRETIME key FIELDS numfields field [field ...]`: like
+/// `HEXPIRETIME`, in milliseconds.
+pub(crate) fn hpexpire_time(
+    storage: &StorageEngine,
+    key: &[u8],
+    fields: &[Vec<u8>],
+) -> RedisResult<Value> {
+    field_deadlines(storage, key, fields, |at, _| at as i64)
+}
+
+/// Replies with `reply(deadline, now)` for each live field with a deadline,
+/// `-1` for the others, and `-2` for missing fields.
+fn field_deadlines(
+    storage: &StorageEngine,
+    key: &[u8],
+    fields: &[Vec<u8>],
+    reply: impl Fn(u64, u64) -> i64,
+) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .read(key, |data| {
+            let RedisData::Hash(h) = data else {
+                return Err(RedisError::WrongType);
+            };
+            Ok(Value::Array(
+                fields
+                    .iter()
+                    .map(|field| match h.deadline(field) {
+                        _ if !h.contains(field, now) => Value::Int(-2),
+                        Some(at) => Value::Int(reply(at, now)),
+                        None => Value::Int(-1),
+                    })
+                    .collect(),
+            ))
+        })
+        .unwrap_or_else(|| Ok(Value::Array(vec![Value::Int(-2); fields.len()])))
+}
+
+/// `HPERSIST key FIELDS numfields field [field ...]`: removes the time to
+/// live of each field.
+///
+/// Replies with an array holding, for each field, `1` if its time to live
+/// was removed, `-1` if it had none, or `-2` if it is missing.
+pub(crate) fn hpersist(
+    storage: &StorageEngine,
+    key: &[u8],
+    fields: &[Vec<u8>],
+) -> RedisResult<Value> {
+    let now = storage.now_ms();
+    storage
+        .write(key, |data| {
+            let RedisData::Hash(h) = data else {
+                return Err(RedisError::WrongType);
+            };
+            Ok(Value::Array(
+                fields
+                    .iter()
+                    .map(|field| match h.contains(field, now) {
+                        false => Value::Int(-2),
+                        true if h.persist(field) => Value::Int(1),
+                        true => Value::Int(-1),
+                    })
+                    .collect(),
+            ))
+        })
+        .unwrap_or_else(|| Ok(Value::Array(vec![Value::Int(-2); fields.len()])))
+}
+
+/// `HGETEX key [EX | PX | EXAT | PXAT | PERSIST] FIELDS numfields field
+/// [field ...]`: returns the value of each field, or null for missing ones,
+/// and sets or removes their time to live.
+///
+/// A deadline that has already passed deletes the fields.
+pub(crate) fn hgetex(
+    storage: &StorageEngine,
+    key: &[u8],
+    expiry: Option<Expiry>,
+    fields: &[Vec<u8>],
+) -> RedisResult<Value> {
+    let Some(expiry) = expiry else {
+        return hmget(storage, key, fields);
+    };
+    let expire_at = deadline(storage, expiry, "hgetex")?;
+    let now = storage.now_ms();
+    let values = storage.write(key, |data| {
+        let RedisData::Hash(h) = data else {
+            return Err(RedisError::WrongType);
+        };
+        Ok(fields
+            .iter()
+            .map(|field| {
+                let value = h.get(field, now).cloned();
+                if value.is_some() {
+                    match expire_at {
+                        None => {
+                            h.persist(field);
+                        }
+                        Some(at) if at <= now => {
+                            h.remove(field, now);
+                        }
+                        Some(at) => h.set_deadline(field, at),
+                    }
+                }
+                value
+            })
+            .collect::<Vec<_>>())
+    });
+    let values = values
+        .transpose()?
+        .unwrap_or_else(|| vec![None; fields.len()]);
+    if let Some(at) = expire_at.filter(|&at| at > now) {
+        if values.iter().any(Option::is_some) {
+            storage.schedule_field_expiry(key, at);
+        }
+    }
+    Ok(Value::Array(
+        values
+            .into_iter()
+            .map(|value| value.map_or(Value::Null, Value::String))
+            .collect(),
+    ))
+}
+
+/// `HSETEX key [FNX | FXX] [EX | PX | EXAT | PXAT | KEEPTTL] FIELDS
+/// numfields field value [field value ...]`: sets the fields and their time
+/// to live.
+///
+/// Returns `1` if the fields were set, or `0` if the `FNX`/`FXX` condition
+/// failed. Without an expiration the fields' time to live is cleared, as
+/// `HSET` does; `KEEPTTL` keeps it.
+pub(crate) fn hsetex(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    options: HashFieldExpirationOptions,
+    pairs: Vec<(Vec<u8>, Vec<u8>)>,
+) -> RedisResult<Value> {
+    let expiry = match options.expiration {
+        Some(SetExpiry::EX(secs)) => Some(Expiry::EX(secs)),
+        Some(SetExpiry::PX(ms)) => Some(Expiry::PX(ms)),
+        Some(SetExpiry::EXAT(secs)) => Some(Expiry::EXAT(secs)),
+        Some(SetExpiry::PXAT(ms)) => Some(Expiry::PXAT(ms)),
+        Some(SetExpiry::KEEPTTL) | None => None,
+    };
+    let expire_at = match expiry {
+        Some(expiry) => deadline(storage, expiry, "hsetex")?,
+        None => None,
+    };
+    let keep_ttl = options.expiration == Some(SetExpiry::KEEPTTL);
+    let now = storage.now_ms();
+    let set_fields = |data: &mut RedisData| {
+        let RedisData::Hash(h) = data else {
+            return Err(RedisError::WrongType);
+        };
+        let allowed = match options.existence_check {
+            None => true,
+            Some(FieldExistenceCheck::FNX) => !pairs.iter().any(|(f, _)| h.contains(f, now)),
+            Some(FieldExistenceCheck::FXX) => pairs.iter().all(|(f, _)| h.contains(f, now)),
+        };
+        if !allowed {
+            return Ok(false);
+        }
+        for (field, value) in pairs {
+            match expire_at {
+                Some(at) if at <= now => {
+                    h.remove(&field, now);
+                }
+                Some(at) => {
+                    h.insert(field.clone(), value, now);
+                    h.set_deadline(&field, at);
+                }
+                None if keep_ttl => set_keeping_ttl(h, field, value, now),
+                None => {
+                    h.insert(field, value, now);
+                }
+            }
+        }
+        Ok(true)
+    };
+    let set = match options.existence_check {
+        // The fields must all exist, so a missing key is not created.
+        Some(FieldExistenceCheck::FXX) => storage.write(&key, set_fields).unwrap_or(Ok(false))?,
+        _ => storage.upsert(key.clone(), || storage.new_hash(), set_fields)?,
+    };
+    if let Some(at) = expire_at.filter(|&at| set && at > now) {
+        storage.schedule_field_expiry(&key, at);
+    }
+    Ok(Value::Int(set as i64))
+}
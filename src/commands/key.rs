@@ -0,0 +1,366 @@
+//! Keyspace command handlers: commands that work on keys of any type.
+
+use std::sync::Arc;
+
+use super::{server, ScanOptions};
+use crate::error::{RedisError, RedisResult};
+use crate::storage::{ExpireOption, RedisData, StorageEngine, StoredValue, Update};
+use crate::types::Value;
+
+/// `DEL key [key ...]`: returns the number of keys removed.
+///
+/// `UNLINK` is the same command: values are always freed in place.
+pub(crate) fn del(storage: &StorageEngine, keys: &[Vec<u8>]) -> RedisResult<Value> {
+    let removed = match keys {
+        [key] => storage.remove(key) as usize,
+        _ => storage.remove_many(keys),
+    };
+    Ok(Value::Int(removed as i64))
+}
+
+/// `EXISTS key [key ...]`: returns how many of the keys exist, counting a
+/// key given twice twice.
+///
+/// `TOUCH` is the same command, as there is no access time to update.
+pub(crate) fn exists(storage: &StorageEngine, keys: &[Vec<u8>]) -> RedisResult<Value> {
+    let found = match keys {
+        [key] => storage.exists(key) as usize,
+        _ => storage.read_many(keys, |_| ()).iter().flatten().count(),
+    };
+    Ok(Value::Int(found as i64))
+}
+
+/// `EXPIRE key seconds [NX | XX | GT | LT]`: returns `1` if the timeout was
+/// set, `0` if the key is missing or `option` prevented it.
+///
+/// A TTL of zero or less deletes the key.
+pub(crate) fn expire(
+    storage: &StorageEngine,
+    key: &[u8],
+    seconds: i64,
+    option: ExpireOption,
+) -> RedisResult<Value> {
+    let ms = seconds
+        .checked_mul(1000)
+        .ok_or_else(|| invalid_expire_time("expire"))?;
+    relative_expire(storage, key, ms, option, "expire")
+}
+
+/// `PEXPIRE key milliseconds [NX | XX | GT | LT]`: like `EXPIRE`, in
+/// milliseconds.
+pub(crate) fn pexpire(
+    storage: &StorageEngine,
+    key: &[u8],
+    ms: i64,
+    option: ExpireOption,
+) -> RedisResult<Value> {
+    relative_expire(storage, key, ms, option, "pexpire")
+}
+
+/// `EXPIREAT key unix-time-seconds [NX | XX | GT | LT]`: returns `1` if the
+/// deadline was set.
+///
+/// A deadline in the past deletes the key.
+pub(crate) fn expire_at(
+    storage: &StorageEngine,
+    key: &[u8],
+    seconds: i64,
+    option: ExpireOption,
+) -> RedisResult<Value> {
+    let ms = seconds
+        .checked_mul(1000)
+        .ok_or_else(|| invalid_expire_time("expireat"))?;
+    pexpire_at(storage, key, ms, option)
+}
+
+/// `PEXPIREAT key unix-time-milliseconds [NX | XX | GT | LT]`: like
+/// `EXPIREAT`, in milliseconds.
+pub(crate) fn pexpire_at(
+    storage: &StorageEngine,
+    key: &[u8],
+    ms: i64,
+    option: ExpireOption,
+) -> RedisResult<Value> {
+    Ok(Value::Int(storage.set_expiry_at(key, ms, option)))
+}
+
+/// Turns a relative TTL into a deadline, rejecting ones that overflow.
+fn relative_expire(
+    storage: &StorageEngine,
+    key: &[u8],
+    ms: i64,
+    option: ExpireOption,
+    command: &str,
+) -> RedisResult<Value> {
+    let at = (storage.now_ms() as i64)
+        .checked_add(ms)
+        .ok_or_else(|| invalid_expire_time(command))?;
+    Ok(Value::Int(storage.set_expiry_at(key, at, option)))
+}
+
+pub(crate) fn invalid_expire_time(command: &str) -> RedisError {
+    RedisError::InvalidArgument(format!("invalid expire time in '{}' command", command))
+}
+
+/// `EXPIRETIME key`: returns the deadline as a Unix time in seconds, `-1` or
+/// `-2`.
+pub(crate) fn expire_time(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    let at = storage.expire_time_query(key);
+    Ok(Value::Int(if at < 0 { at } else { (at + 500) / 1000 }))
+}
+
+/// `PEXPIRETIME key`: returns the deadline as a Unix time in milliseconds,
+/// `-1` or `-2`.
+pub(crate) fn pexpire_time(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    Ok(Value::Int(storage.expire_time_query(key)))
+}
+
+/// `TTL key`: returns the remaining time to live in seconds, `-1` or `-2`.
+pub(crate) fn ttl(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    Ok(Value::Int(storage.ttl_query(key)))
+}
+
+/// `PTTL key`: returns the remaining time to live in milliseconds, `-1` or
+/// `-2`.
+pub(crate) fn pttl(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    Ok(Value::Int(storage.pttl_query(key)))
+}
+
+/// `PERSIST key`: returns `1` if the key's timeout was removed, `0` if the
+/// key is missing or has no timeout.
+pub(crate) fn persist(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    Ok(Value::Int(storage.persist(key)))
+}
+
+/// `KEYS pattern`: returns every live key matching the glob-style `pattern`.
+pub(crate) fn keys(storage: &StorageEngine, pattern: &[u8]) -> RedisResult<Value> {
+    let keys = storage
+        .keys()
+        .into_iter()
+        .filter(|key| glob_match(pattern, key))
+        .map(Value::String)
+        .collect();
+    Ok(Value::Array(keys))
+}
+
+/// `SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]`: returns the
+/// cursor to continue from and the next batch of live keys.
+///
+/// As in Redis, `MATCH` and `TYPE` filter the batch after it is picked.
+pub(crate) fn scan(
+    storage: &StorageEngine,
+    cursor: u64,
+    options: &ScanOptions,
+) -> RedisResult<Value> {
+    let (next, keys) = storage.scan(cursor, options.count.unwrap_or(10));
+    let keys = keys
+        .into_iter()
+        .filter(|key| {
+            options
+                .pattern
+                .as_ref()
+                .is_none_or(|pattern| glob_match(pattern, key))
+        })
+        .filter(|key| {
+            options.key_type.as_ref().is_none_or(|key_type| {
+                storage
+                    .read(key, RedisData::type_name)
+                    .is_some_and(|name| key_type.eq_ignore_ascii_case(name.as_bytes()))
+            })
+        })
+        .map(Value::String)
+        .collect();
+    Ok(Value::Array(vec![
+        Value::String(next.to_string().into_bytes()),
+        Value::Array(keys),
+    ]))
+}
+
+/// `TYPE key`: returns the type name of the value at `key`, or `none`.
+pub(crate) fn key_type(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    let name = storage.read(key, RedisData::type_name).unwrap_or("none");
+    Ok(Value::String(name.as_bytes().to_vec()))
+}
+
+/// `RENAME key newkey`: moves the value (and its timeout) to `newkey`.
+pub(crate) fn rename(storage: &StorageEngine, key: &[u8], new_key: Vec<u8>) -> RedisResult<Value> {
+    storage
+        .rename(key, &new_key, true)
+        .ok_or_else(|| no_such_key(key))?;
+    Ok(Value::Okay)
+}
+
+/// `RENAMENX key newkey`: like `RENAME`, but only if `newkey` does not exist.
+///
+/// Returns `1` if the key was renamed, `0` if `newkey` already exists.
+pub(crate) fn renamenx(
+    storage: &StorageEngine,
+    key: &[u8],
+    new_key: Vec<u8>,
+) -> RedisResult<Value> {
+    let renamed = storage
+        .rename(key, &new_key, false)
+        .ok_or_else(|| no_such_key(key))?;
+    Ok(Value::Int(renamed as i64))
+}
+
+/// `COPY source destination [DB destination-db] [REPLACE]`: copies the
+/// value and its timeout, into the selected database unless `db` is given.
+///
+/// Returns `1` if the value was copied, `0` if `destination` already exists
+/// and `replace` is not set.
+pub(crate) fn copy(
+    storage: &StorageEngine,
+    source: &[u8],
+    destination: Vec<u8>,
+    db: Option<i64>,
+    replace: bool,
+) -> RedisResult<Value> {
+    let target = match db {
+        Some(db) => usize::try_from(db)
+            .ok()
+            .and_then(|db| storage.at_db(db))
+            .ok_or_else(server::db_index_out_of_range)?,
+        None => storage.clone(),
+    };
+    if target.selected_db() == storage.selected_db() && source == destination.as_slice() {
+        return Err(same_object());
+    }
+    let Some(stored) = storage.get(source) else {
+        return Ok(Value::Int(0));
+    };
+    let value = StoredValue {
+        data: Arc::new(Arc::unwrap_or_clone(stored.data)),
+        expire_at: stored.expire_at,
+    };
+    // The check and the write happen under one shard lock, so without
+    // `REPLACE` a value written to `destination` meanwhile is kept.
+    let copied = target.update(destination, |current| match current {
+        Some(_) if !replace => (Update::Keep, false),
+        _ => (Update::Set(value), true),
+    });
+    Ok(Value::Int(copied as i64))
+}
+
+/// `MOVE key db`: moves the key, with its timeout, to the database at `db`.
+///
+/// Returns `1` if the key was moved, `0` if it does not exist or `db`
+/// already has a key with that name.
+pub(crate) fn move_key(storage: &StorageEngine, key: &[u8], db: i64) -> RedisResult<Value> {
+    let db = usize::try_from(db)
+        .ok()
+        .filter(|&db| db < storage.db_count())
+        .ok_or_else(server::db_index_out_of_range)?;
+    if db == storage.selected_db() {
+        return Err(same_object());
+    }
+    Ok(Value::Int(storage.move_key(key, db) as i64))
+}
+
+fn same_object() -> RedisError {
+    RedisError::InvalidArgument("source and destination objects are the same".to_string())
+}
+
+pub(crate) fn no_such_key(key: &[u8]) -> RedisError {
+    RedisError::NoSuchKey(String::from_utf8_lossy(key).into_owned())
+}
+
+/// Matches `string` against a glob-style `pattern`, following Redis's rules.
+///
+/// Supports `*`, `?`, `[abc]`, `[^abc]`, `[a-z]` and `\` escapes.
+pub(crate) fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    while p < pattern.len() {
+        match pattern[p] {
+            b'*' => {
+                while p + 1 < pattern.len() && pattern[p + 1] == b'*' {
+                    p += 1;
+                }
+                if p + 1 == pattern.len() {
+                    return true;
+                }
+                return (s..=string.len()).any(|i| glob_match(&pattern[p + 1..], &string[i..]));
+            }
+            b'?' => {
+                if s >= string.len() {
+                    return false;
+                }
+                s += 1;
+            }
+            b'[' => {
+                if s >= string.len() {
+                    return false;
+                }
+                p += 1;
+                let negate = pattern.get(p) == Some(&b'^');
+                if negate {
+                    p += 1;
+                }
+                let mut matched = false;
+                loop {
+                    match pattern.get(p) {
+                        // An unterminated class ends at the end of the pattern.
+                        None => {
+                            p -= 1;
+                            break;
+                        }
+                        Some(b']') => break,
+                        Some(b'\\') if p + 1 < pattern.len() => {
+                            p += 1;
+                            matched |= pattern[p] == string[s];
+                        }
+                        Some(&lo) if p + 2 < pattern.len() && pattern[p + 1] == b'-' => {
+                            let hi = pattern[p + 2];
+                            let (lo, hi) = if lo <= hi { (lo, hi) } else { (hi, lo) };
+                            matched |= (lo..=hi).contains(&string[s]);
+                            p += 2;
+                        }
+                        Some(&c) => matched |= c == string[s],
+                    }
+                    p += 1;
+                }
+                if matched == negate {
+                    return false;
+                }
+                s += 1;
+            }
+            b'\\' if p + 1 < pattern.len() => {
+                p += 1;
+                if s >= string.len() || pattern[p] != string[s] {
+                    return false;
+                }
+                s += 1;
+            }
+            c => {
+                if s >= string.len() || c != string[s] {
+                    return false;
+                }
+                s += 1;
+            }
+        }
+        p += 1;
+    }
+    s == string.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"*", b"anything"));
+        assert!(glob_match(b"user:*", b"user:1"));
+        assert!(!glob_match(b"user:*", b"session:1"));
+        assert!(glob_match(b"h?llo", b"hello"));
+        assert!(!glob_match(b"h?llo", b"hllo"));
+        assert!(glob_match(b"h[ae]llo", b"hallo"));
+        assert!(!glob_match(b"h[ae]llo", b"hillo"));
+        assert!(glob_match(b"h[^e]llo", b"hallo"));
+        assert!(!glob_match(b"h[^e]llo", b"hello"));
+        assert!(glob_match(b"h[a-c]llo", b"hbllo"));
+        assert!(glob_match(b"a\\*b", b"a*b"));
+        assert!(!glob_match(b"a\\*b", b"axb"));
+        assert!(glob_match(b"*:*:end", b"a:b:end"));
+    }
+}
@@ -0,0 +1,361 @@
+//! List command handlers.
+
+use std::collections::VecDeque;
+
+use super::key::no_such_key;
+use super::{normalize_range, LposOptions};
+use crate::error::{RedisError, RedisResult};
+use crate::storage::{RedisData, StorageEngine};
+use crate::types::Value;
+
+/// `LPUSH key element [element ...]`: pushes each element onto the head in
+/// turn, so the last one ends up first.
+///
+/// Returns the length of the list after the push.
+pub(crate) fn lpush(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    values: Vec<Vec<u8>>,
+) -> RedisResult<Value> {
+    storage.upsert(key, || storage.new_list(), |data| push(data, values, true))
+}
+
+/// `RPUSH key element [element ...]`: returns the length of the list after
+/// the push.
+pub(crate) fn rpush(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    values: Vec<Vec<u8>>,
+) -> RedisResult<Value> {
+    storage.upsert(key, || storage.new_list(), |data| push(data, values, false))
+}
+
+/// `LPUSHX key element [element ...]`: like `LPUSH`, but only if the list
+/// exists. Returns `0` otherwise.
+pub(crate) fn lpushx(
+    storage: &StorageEngine,
+    key: &[u8],
+    values: Vec<Vec<u8>>,
+) -> RedisResult<Value> {
+    storage
+        .write(key, |data| push(data, values, true))
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `RPUSHX key element [element ...]`: like `RPUSH`, but only if the list
+/// exists. Returns `0` otherwise.
+pub(crate) fn rpushx(
+    storage: &StorageEngine,
+    key: &[u8],
+    values: Vec<Vec<u8>>,
+) -> RedisResult<Value> {
+    storage
+        .write(key, |data| push(data, values, false))
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+fn push(data: &mut RedisData, values: Vec<Vec<u8>>, front: bool) -> RedisResult<Value> {
+    let RedisData::List(l) = data else {
+        return Err(RedisError::WrongType);
+    };
+    for value in values {
+        if front {
+            l.push_front(value);
+        } else {
+            l.push_back(value);
+        }
+    }
+    Ok(Value::Int(l.len() as i64))
+}
+
+/// `LPOP key [count]`: removes and returns the first element, or null if
+/// the list is missing.
+///
+/// With `count`, returns an array of up to `count` elements instead, or null
+/// if the list is missing.
+pub(crate) fn lpop(
+    storage: &StorageEngine,
+    key: &[u8],
+    count: Option<usize>,
+) -> RedisResult<Value> {
+    pop(storage, key, count, true)
+}
+
+/// `RPOP key [count]`: like `LPOP`, from the tail.
+pub(crate) fn rpop(
+    storage: &StorageEngine,
+    key: &[u8],
+    count: Option<usize>,
+) -> RedisResult<Value> {
+    pop(storage, key, count, false)
+}
+
+fn pop(
+    storage: &StorageEngine,
+    key: &[u8],
+    count: Option<usize>,
+    front: bool,
+) -> RedisResult<Value> {
+    storage
+        .write(key, |data| {
+            let RedisData::List(l) = data else {
+                return Err(RedisError::WrongType);
+            };
+            let mut pop_one = || if front { l.pop_front() } else { l.pop_back() };
+            Ok(match count {
+                None => pop_one().map_or(Value::Null, Value::String),
+                Some(count) => Value::Array(
+                    std::iter::from_fn(pop_one)
+                        .take(count)
+                        .map(Value::String)
+                        .collect(),
+                ),
+            })
+        })
+        .unwrap_or(Ok(Value::Null))
+}
+
+/// `LLEN key`: returns the length of the list, or `0` if it is missing.
+pub(crate) fn llen(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    storage
+        .read(key, |data| match data {
+            RedisData::List(l) => Ok(Value::Int(l.len() as i64)),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `LRANGE key start stop`: returns the elements from `start` to `stop`,
+/// inclusive. Negative indexes count from the tail.
+pub(crate) fn lrange(
+    storage: &StorageEngine,
+    key: &[u8],
+    start: i64,
+    stop: i64,
+) -> RedisResult<Value> {
+    storage
+        .read(key, |data| {
+            let RedisData::List(l) = data else {
+                return Err(RedisError::WrongType);
+            };
+            let Some((start, stop)) = normalize_range(start, stop, l.len()) else {
+                return Ok(Value::Array(Vec::new()));
+            };
+            Ok(Value::Array(
+                l.range(start..=stop)
+                    .map(|v| Value::String(v.clone()))
+                    .collect(),
+            ))
+        })
+        .unwrap_or(Ok(Value::Array(Vec::new())))
+}
+
+/// `LINDEX key index`: returns the element at `index`, or null if it is out
+/// of range. Negative indexes count from the tail.
+pub(crate) fn lindex(storage: &StorageEngine, key: &[u8], index: i64) -> RedisResult<Value> {
+    storage
+        .read(key, |data| {
+            let RedisData::List(l) = data else {
+                return Err(RedisError::WrongType);
+            };
+            Ok(list_index(index, l.len()).map_or(Value::Null, |i| Value::String(l[i].clone())))
+        })
+        .unwrap_or(Ok(Value::Null))
+}
+
+/// `LSET key index element`: replaces the element at `index`.
+///
+/// Fails if the list is missing or `index` is out of range.
+pub(crate) fn lset(
+    storage: &StorageEngine,
+    key: &[u8],
+    index: i64,
+    value: Vec<u8>,
+) -> RedisResult<Value> {
+    storage
+        .write(key, |data| {
+            let RedisData::List(l) = data else {
+                return Err(RedisError::WrongType);
+            };
+            let i = list_index(index, l.len())
+                .ok_or_else(|| RedisError::InvalidArgument("index out of range".to_string()))?;
+            l[i] = value;
+            Ok(Value::Okay)
+        })
+        .unwrap_or_else(|| Err(no_such_key(key)))
+}
+
+/// `LINSERT key BEFORE | AFTER pivot element`: inserts `value` next to the
+/// first occurrence of `pivot`.
+///
+/// Returns the new length, `-1` if `pivot` was not found, or `0` if the list
+/// is missing.
+pub(crate) fn linsert(
+    storage: &StorageEngine,
+    key: &[u8],
+    before: bool,
+    pivot: &[u8],
+    value: Vec<u8>,
+) -> RedisResult<Value> {
+    storage
+        .write(key, |data| {
+            let RedisData::List(l) = data else {
+                return Err(RedisError::WrongType);
+            };
+            let Some(i) = l.iter().position(|v| v == pivot) else {
+                return Ok(Value::Int(-1));
+            };
+            l.insert(if before { i } else { i + 1 }, value);
+            Ok(Value::Int(l.len() as i64))
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `LREM key count element`: removes occurrences of `value` and returns how
+/// many were removed.
+///
+/// A positive `count` removes up to `count` from the head, a negative one up
+/// to `-count` from the tail, and `0` removes them all.
+pub(crate) fn lrem(
+    storage: &StorageEngine,
+    key: &[u8],
+    count: i64,
+    value: &[u8],
+) -> RedisResult<Value> {
+    storage
+        .write(key, |data| {
+            let RedisData::List(l) = data else {
+                return Err(RedisError::WrongType);
+            };
+            let limit = match count {
+                0 => usize::MAX,
+                count => usize::try_from(count.unsigned_abs()).unwrap_or(usize::MAX),
+            };
+            // Removing from the tail keeps the earliest matches instead.
+            let mut keep = if count < 0 {
+                let matches = l.iter().filter(|v| *v == value).count();
+                matches.saturating_sub(limit)
+            } else {
+                0
+            };
+            let mut removed = 0;
+            l.retain(|v| {
+                if v != value {
+                    true
+                } else if keep > 0 {
+                    keep -= 1;
+                    true
+                } else if removed < limit {
+                    removed += 1;
+                    false
+                } else {
+                    true
+                }
+            });
+            Ok(Value::Int(removed as i64))
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `LTRIM key start stop`: keeps only the elements from `start` to `stop`,
+/// inclusive. An empty range deletes the list.
+pub(crate) fn ltrim(
+    storage: &StorageEngine,
+    key: &[u8],
+    start: i64,
+    stop: i64,
+) -> RedisResult<Value> {
+    storage
+        .write(key, |data| {
+            let RedisData::List(l) = data else {
+                return Err(RedisError::WrongType);
+            };
+            match normalize_range(start, stop, l.len()) {
+                Some((start, stop)) => {
+                    l.truncate(stop + 1);
+                    l.drain(..start);
+                }
+                None => l.clear(),
+            }
+            Ok(Value::Okay)
+        })
+        .unwrap_or(Ok(Value::Okay))
+}
+
+/// `LPOS key element [RANK rank] [COUNT num-matches] [MAXLEN len]`: returns
+/// the index of the first match of `value`, or null.
+///
+/// `RANK` skips to the `rank`-th match, searching from the tail if it is
+/// negative. With `COUNT`, returns an array of up to that many indexes, or
+/// of every match for `0`. `MAXLEN` limits how many elements are compared.
+pub(crate) fn lpos(
+    storage: &StorageEngine,
+    key: &[u8],
+    value: &[u8],
+    options: LposOptions,
+) -> RedisResult<Value> {
+    let rank = options.rank.unwrap_or(1);
+    if rank == 0 {
+        return Err(RedisError::InvalidArgument(
+            "RANK can't be zero: use 1 to start from the first match, 2 from the second ... \
+             or use negative to start from the end of the list"
+                .to_string(),
+        ));
+    }
+    if rank == isize::MIN {
+        return Err(RedisError::InvalidArgument(
+            "value is out of range".to_string(),
+        ));
+    }
+    let skip = rank.unsigned_abs() - 1;
+    let limit = match options.count {
+        Some(0) => usize::MAX,
+        Some(count) => count,
+        None => 1,
+    };
+    let maxlen = match options.maxlen {
+        Some(0) | None => usize::MAX,
+        Some(maxlen) => maxlen,
+    };
+    let found = storage.read(key, |data| {
+        let RedisData::List(l) = data else {
+            return Err(RedisError::WrongType);
+        };
+        Ok(matches(l, value, rank < 0)
+            .take_while(|&(compared, _)| compared < maxlen)
+            .map(|(_, i)| Value::Int(i as i64))
+            .skip(skip)
+            .take(limit)
+            .collect::<Vec<_>>())
+    });
+    let found = found.transpose()?.unwrap_or_default();
+    Ok(match options.count {
+        Some(_) => Value::Array(found),
+        None => found.into_iter().next().unwrap_or(Value::Null),
+    })
+}
+
+/// Iterates over the indexes of the elements equal to `value`, from the tail
+/// if `reverse`, each paired with how many elements were compared before it.
+fn matches<'a>(
+    l: &'a VecDeque<Vec<u8>>,
+    value: &'a [u8],
+    reverse: bool,
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    let len = l.len();
+    (0..len)
+        .map(move |n| if reverse { len - 1 - n } else { n })
+        .enumerate()
+        .filter(move |&(_, i)| l[i] == value)
+}
+
+/// Resolves a Redis list index, where negative values count from the tail,
+/// or returns `None` if it is out of range.
+fn list_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 {
+        index.checked_add(len as i64)?
+    } else {
+        index
+    };
+    usize::try_from(index).ok().filter(|&i| i < len)
+}
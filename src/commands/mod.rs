@@ -0,0 +1,2083 @@
+//! Command types and the dispatcher that executes them against a
+//! [`StorageEngine`].
+//!
+//! Each data type has its own handler module. Handlers take already-parsed
+//! arguments and return a RESP [`Value`]. [`execute_command`] looks commands
+//! up by name in a static command table and runs them; every method of
+//! [`Commands`](crate::Commands) goes through it.
+
+pub(crate) mod bitmap;
+pub(crate) mod hash;
+pub(crate) mod key;
+pub(crate) mod list;
+pub(crate) mod server;
+pub(crate) mod set;
+pub(crate) mod stream;
+pub(crate) mod string;
+pub(crate) mod zset;
+
+use std::sync::LazyLock;
+
+use rustc_hash::FxHashMap;
+
+use crate::error::{RedisError, RedisResult};
+use crate::storage::types::ExpireCondition;
+use crate::storage::{ExpireOption, StorageEngine};
+use crate::types::{FromRedisValue, ToRedisArgs, Value};
+
+use bitmap::{BitFieldOp, BitOp};
+use string::LcsReply;
+
+/// A Redis command, built up one argument at a time.
+///
+/// The first argument is the command name. Build one with [`cmd`], then run
+/// it with [`query`](Cmd::query) or [`query_async`](Cmd::query_async).
+///
+/// # Example
+///
+/// ```rust
+/// use not_redis::{cmd, Client};
+///
+/// let mut client = Client::new();
+/// cmd("SET").arg("counter").arg(41).query::<()>(&mut client).unwrap();
+/// let value: i64 = cmd("GET").arg("counter").query(&mut client).unwrap();
+/// assert_eq!(value, 41);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Cmd {
+    args: Vec<Vec<u8>>,
+}
+
+/// Shortcut for creating a [`Cmd`] named `name`.
+pub fn cmd(name: &str) -> Cmd {
+    let mut cmd = Cmd::new();
+    cmd.arg(name);
+    cmd
+}
+
+impl Cmd {
+    /// Creates an empty command.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an argument to the command.
+    ///
+    /// Null values (such as `None`) do not produce an argument.
+    pub fn arg<T: ToRedisArgs>(&mut self, arg: T) -> &mut Self {
+        self.args
+            .extend(arg.to_redis_args().into_iter().filter_map(value_to_bytes));
+        self
+    }
+
+    /// Returns the raw arguments of the command, including its name.
+    pub fn args(&self) -> &[Vec<u8>] {
+        &self.args
+    }
+
+    /// Appends `FIELDS numfields` followed by the fields, as the hash field
+    /// expiration commands take them.
+    pub(crate) fn fields_arg<F: ToRedisArgs>(&mut self, fields: F) -> &mut Self {
+        let fields: Vec<_> = fields
+            .to_redis_args()
+            .into_iter()
+            .filter_map(value_to_bytes)
+            .collect();
+        self.arg("FIELDS").arg(fields.len());
+        self.args.extend(fields);
+        self
+    }
+
+    /// Executes the command on `con` and converts the reply to `T`.
+    pub fn query<T: FromRedisValue>(&self, con: &mut impl ConnectionLike) -> RedisResult<T> {
+        T::from_redis_value(con.req_command(self)?)
+    }
+
+    /// Executes the command on `con` and converts the reply to `T`.
+    ///
+    /// Commands run in-process and never block on I/O, so this is the same
+    /// as [`query`](Cmd::query); it exists so code written against the
+    /// `redis` crate's async API ports over unchanged.
+    pub async fn query_async<T: FromRedisValue>(
+        &self,
+        con: &mut impl ConnectionLike,
+    ) -> RedisResult<T> {
+        self.query(con)
+    }
+}
+
+/// A connection that can execute a [`Cmd`].
+///
+/// Every `ConnectionLike` type gets the full [`Commands`](crate::Commands)
+/// API for free.
+pub trait ConnectionLike {
+    /// Executes `cmd` and returns the raw reply.
+    fn req_command(&mut self, cmd: &Cmd) -> RedisResult<Value>;
+}
+
+/// Options for the `COPY` command.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use not_redis::{blocking::Client, Commands, CopyOptions};
+///
+/// let mut client = Client::new();
+/// let copied: bool = client
+///     .copy("src", "dst", CopyOptions::default().replace(true))
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct CopyOptions<Db> {
+    db: Option<Db>,
+    replace: bool,
+}
+
+impl Default for CopyOptions<&'static str> {
+    fn default() -> Self {
+        Self {
+            db: None,
+            replace: false,
+        }
+    }
+}
+
+impl<Db> CopyOptions<Db> {
+    /// Sets the target database for the copy.
+    pub fn db<Db2>(self, db: Db2) -> CopyOptions<Db2> {
+        CopyOptions {
+            db: Some(db),
+            replace: self.replace,
+        }
+    }
+
+    /// Overwrites the destination key if it already exists.
+    pub fn replace(mut self, replace: bool) -> Self {
+        self.replace = replace;
+        self
+    }
+}
+
+impl<Db: ToRedisArgs> CopyOptions<Db> {
+    pub(crate) fn write_args(&self, cmd: &mut Cmd) {
+        if let Some(db) = &self.db {
+            cmd.arg("DB");
+            cmd.args
+                .extend(db.to_redis_args().into_iter().filter_map(value_to_bytes));
+        }
+        if self.replace {
+            cmd.arg("REPLACE");
+        }
+    }
+}
+
+/// The condition of a conditional `SET`.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExistenceCheck {
+    /// Only set the key if it does not already exist.
+    NX,
+    /// Only set the key if it already exists.
+    XX,
+}
+
+/// The expiration of a value written by `SET`.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetExpiry {
+    /// Expire after this many seconds.
+    EX(u64),
+    /// Expire after this many milliseconds.
+    PX(u64),
+    /// Expire at this Unix time, in seconds.
+    EXAT(u64),
+    /// Expire at this Unix time, in milliseconds.
+    PXAT(u64),
+    /// Keep the key's current time to live.
+    KEEPTTL,
+}
+
+/// The new expiration of a key read by `GETEX`.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    /// Expire after this many seconds.
+    EX(u64),
+    /// Expire after this many milliseconds.
+    PX(u64),
+    /// Expire at this Unix time, in seconds.
+    EXAT(u64),
+    /// Expire at this Unix time, in milliseconds.
+    PXAT(u64),
+    /// Remove the key's time to live.
+    PERSIST,
+}
+
+/// Options for the `SET` command.
+///
+/// By default `SET` overwrites any existing value and clears its time to
+/// live. The options make the write conditional, give the new value an
+/// expiration, or return the value it replaced.
+///
+/// # Example
+///
+/// ```rust
+/// use not_redis::{blocking::Client, Commands, ExistenceCheck, SetExpiry, SetOptions};
+///
+/// let mut client = Client::new();
+/// let options = SetOptions::default()
+///     .conditional_set(ExistenceCheck::NX)
+///     .with_expiration(SetExpiry::PX(30_000));
+/// let acquired: bool = client.set_options("lock", "owner-1", options).unwrap();
+/// assert!(acquired);
+/// let acquired: bool = client.set_options("lock", "owner-2", options).unwrap();
+/// assert!(!acquired);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SetOptions {
+    conditional_set: Option<ExistenceCheck>,
+    get: bool,
+    expiration: Option<SetExpiry>,
+}
+
+impl SetOptions {
+    /// Only sets the key if the check passes.
+    pub fn conditional_set(mut self, existence_check: ExistenceCheck) -> Self {
+        self.conditional_set = Some(existence_check);
+        self
+    }
+
+    /// Returns the value the key held before the write, or null, instead of
+    /// `OK`.
+    pub fn get(mut self, get: bool) -> Self {
+        self.get = get;
+        self
+    }
+
+    /// Sets the expiration of the new value.
+    pub fn with_expiration(mut self, expiration: SetExpiry) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    pub(crate) fn write_args(&self, cmd: &mut Cmd) {
+        match self.conditional_set {
+            Some(ExistenceCheck::NX) => cmd.arg("NX"),
+            Some(ExistenceCheck::XX) => cmd.arg("XX"),
+            None => cmd,
+        };
+        if self.get {
+            cmd.arg("GET");
+        }
+        match self.expiration {
+            Some(SetExpiry::EX(secs)) => cmd.arg("EX").arg(secs),
+            Some(SetExpiry::PX(ms)) => cmd.arg("PX").arg(ms),
+            Some(SetExpiry::EXAT(secs)) => cmd.arg("EXAT").arg(secs),
+            Some(SetExpiry::PXAT(ms)) => cmd.arg("PXAT").arg(ms),
+            Some(SetExpiry::KEEPTTL) => cmd.arg("KEEPTTL"),
+            None => cmd,
+        };
+    }
+}
+
+impl Expiry {
+    pub(crate) fn write_args(self, cmd: &mut Cmd) {
+        match self {
+            Expiry::EX(secs) => cmd.arg("EX").arg(secs),
+            Expiry::PX(ms) => cmd.arg("PX").arg(ms),
+            Expiry::EXAT(secs) => cmd.arg("EXAT").arg(secs),
+            Expiry::PXAT(ms) => cmd.arg("PXAT").arg(ms),
+            Expiry::PERSIST => cmd.arg("PERSIST"),
+        };
+    }
+}
+
+/// The condition of an `HSETEX`, checked against every field written.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldExistenceCheck {
+    /// Only set the fields if none of them exist.
+    FNX,
+    /// Only set the fields if all of them exist.
+    FXX,
+}
+
+/// Options for the `HSETEX` command.
+///
+/// By default `HSETEX` sets every field and clears their time to live, like
+/// `HSET`. The options make the write conditional or give the fields an
+/// expiration.
+///
+/// # Example
+///
+/// ```rust
+/// use not_redis::blocking::Client;
+/// use not_redis::{Commands, FieldExistenceCheck, HashFieldExpirationOptions, SetExpiry};
+///
+/// let mut client = Client::new();
+/// let options = HashFieldExpirationOptions::default()
+///     .set_existence_check(FieldExistenceCheck::FNX)
+///     .set_expiration(SetExpiry::EX(60));
+/// let set: bool = client.hset_ex("session", &options, &[("token", "abc")]).unwrap();
+/// assert!(set);
+/// let set: bool = client.hset_ex("session", &options, &[("token", "def")]).unwrap();
+/// assert!(!set);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HashFieldExpirationOptions {
+    existence_check: Option<FieldExistenceCheck>,
+    expiration: Option<SetExpiry>,
+}
+
+impl HashFieldExpirationOptions {
+    /// Only sets the fields if the check passes.
+    pub fn set_existence_check(mut self, field_existence_check: FieldExistenceCheck) -> Self {
+        self.existence_check = Some(field_existence_check);
+        self
+    }
+
+    /// Sets the expiration of the fields.
+    pub fn set_expiration(mut self, expiration: SetExpiry) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    pub(crate) fn write_args(&self, cmd: &mut Cmd) {
+        match self.existence_check {
+            Some(FieldExistenceCheck::FNX) => cmd.arg("FNX"),
+            Some(FieldExistenceCheck::FXX) => cmd.arg("FXX"),
+            None => cmd,
+        };
+        match self.expiration {
+            Some(SetExpiry::EX(secs)) => cmd.arg("EX").arg(secs),
+            Some(SetExpiry::PX(ms)) => cmd.arg("PX").arg(ms),
+            Some(SetExpiry::EXAT(secs)) => cmd.arg("EXAT").arg(secs),
+            Some(SetExpiry::PXAT(ms)) => cmd.arg("PXAT").arg(ms),
+            Some(SetExpiry::KEEPTTL) => cmd.arg("KEEPTTL"),
+            None => cmd,
+        };
+    }
+}
+
+/// Options for the `LPOS` command.
+///
+/// By default `LPOS` returns the index of the first match. The options pick
+/// a later match, search from the tail, return several matches or bound how
+/// many elements are compared.
+///
+/// # Example
+///
+/// ```rust
+/// use not_redis::{blocking::Client, Commands, LposOptions};
+///
+/// let mut client = Client::new();
+/// let _: () = client.rpush("letters", &["a", "b", "c", "b", "a"]).unwrap();
+/// let last: i64 = client.lpos("letters", "b", LposOptions::default().rank(-1)).unwrap();
+/// assert_eq!(last, 3);
+/// let all: Vec<i64> = client.lpos("letters", "a", LposOptions::default().count(0)).unwrap();
+/// assert_eq!(all, vec![0, 4]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LposOptions {
+    rank: Option<isize>,
+    count: Option<usize>,
+    maxlen: Option<usize>,
+}
+
+impl LposOptions {
+    /// Skips to the `rank`-th match, counting from the tail if `rank` is
+    /// negative. It must not be zero.
+    pub fn rank(mut self, rank: isize) -> Self {
+        self.rank = Some(rank);
+        self
+    }
+
+    /// Returns the indexes of up to `count` matches, or of every match for
+    /// `0`, instead of a single index.
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Compares at most `maxlen` elements, or all of them for `0`.
+    pub fn maxlen(mut self, maxlen: usize) -> Self {
+        self.maxlen = Some(maxlen);
+        self
+    }
+
+    pub(crate) fn write_args(&self, cmd: &mut Cmd) {
+        if let Some(rank) = self.rank {
+            cmd.arg("RANK").arg(rank);
+        }
+        if let Some(count) = self.count {
+            cmd.arg("COUNT").arg(count);
+        }
+        if let Some(maxlen) = self.maxlen {
+            cmd.arg("MAXLEN").arg(maxlen);
+        }
+    }
+}
+
+/// Options for the `SCAN` command.
+///
+/// By default each call returns about 10 keys of any type. `MATCH` and
+/// `TYPE` filter a batch after it is picked, so a call may return fewer keys
+/// than its count, or none, before the scan is done.
+///
+/// # Example
+///
+/// ```rust
+/// use not_redis::{blocking::Client, Commands, ScanOptions};
+///
+/// let mut client = Client::new();
+/// let _: () = client.mset(&[("user:1", "a"), ("user:2", "b"), ("order:1", "c")]).unwrap();
+/// let options = ScanOptions::default().pattern("user:*").count(100);
+/// let mut users: Vec<String> = Vec::new();
+/// let mut cursor = 0;
+/// loop {
+///     let (next, keys): (u64, Vec<String>) = client.scan(cursor, options.clone()).unwrap();
+///     users.extend(keys);
+///     if next == 0 {
+///         break;
+///     }
+///     cursor = next;
+/// }
+/// users.sort();
+/// assert_eq!(users, ["user:1", "user:2"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanOptions {
+    pattern: Option<Vec<u8>>,
+    count: Option<usize>,
+    key_type: Option<Vec<u8>>,
+}
+
+impl ScanOptions {
+    /// Only returns keys matching the glob-style `pattern`.
+    pub fn pattern(mut self, pattern: impl AsRef<[u8]>) -> Self {
+        self.pattern = Some(pattern.as_ref().to_vec());
+        self
+    }
+
+    /// Looks at about `count` keys per call. It must be at least 1.
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Only returns keys holding a value of this type, as named by `TYPE`.
+    pub fn key_type(mut self, key_type: impl AsRef<[u8]>) -> Self {
+        self.key_type = Some(key_type.as_ref().to_vec());
+        self
+    }
+
+    pub(crate) fn write_args(&self, cmd: &mut Cmd) {
+        if let Some(pattern) = &self.pattern {
+            cmd.arg("MATCH").arg(pattern.clone());
+        }
+        if let Some(count) = self.count {
+            cmd.arg("COUNT").arg(count);
+        }
+        if let Some(key_type) = &self.key_type {
+            cmd.arg("TYPE").arg(key_type.clone());
+        }
+    }
+}
+
+/// The unit of the `start` and `end` offsets of `BITCOUNT` and `BITPOS`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BitUnit {
+    /// Offsets count bytes.
+    #[default]
+    Byte,
+    /// Offsets count bits.
+    Bit,
+}
+
+impl BitUnit {
+    pub(crate) fn write_args(self, cmd: &mut Cmd) {
+        match self {
+            BitUnit::Byte => cmd.arg("BYTE"),
+            BitUnit::Bit => cmd.arg("BIT"),
+        };
+    }
+}
+
+/// The integer encoding of a `BITFIELD` field.
+///
+/// Signed fields may be up to 64 bits wide and unsigned ones up to 63, so
+/// that every value fits the signed 64-bit integer reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitFieldType {
+    signed: bool,
+    bits: u8,
+}
+
+impl BitFieldType {
+    /// A signed field of `bits` bits, written `i<bits>`.
+    pub fn signed(bits: u8) -> Self {
+        BitFieldType { signed: true, bits }
+    }
+
+    /// An unsigned field of `bits` bits, written `u<bits>`.
+    pub fn unsigned(bits: u8) -> Self {
+        BitFieldType {
+            signed: false,
+            bits,
+        }
+    }
+}
+
+/// Where a `BITFIELD` field starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitFieldOffset {
+    /// A bit offset.
+    Bit(usize),
+    /// The `n`-th field of the type, written `#n`: bit offset `n * width`.
+    Index(usize),
+}
+
+/// What `BITFIELD` does when a `SET` or `INCRBY` goes out of the range of
+/// its type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BitFieldOverflow {
+    /// Wraps around, in two's complement for signed fields.
+    #[default]
+    Wrap,
+    /// Saturates at the minimum or maximum value.
+    Sat,
+    /// Leaves the field unchanged and replies null for that operation.
+    Fail,
+}
+
+/// The subcommands of a `BITFIELD` call, applied in order.
+///
+/// An [`overflow`](Self::overflow) applies to the `SET` and `INCRBY`
+/// operations that follow it.
+///
+/// # Example
+///
+/// ```
+/// use not_redis::blocking::Client;
+/// use not_redis::{BitFieldOffset, BitFieldOps, BitFieldOverflow, BitFieldType, Commands};
+///
+/// let mut client = Client::new();
+/// let ops = BitFieldOps::default()
+///     .overflow(BitFieldOverflow::Sat)
+///     .incr_by(BitFieldType::unsigned(4), BitFieldOffset::Index(1), 20)
+///     .get(BitFieldType::unsigned(8), BitFieldOffset::Bit(0));
+/// let replies = client.bitfield("counters", &ops).unwrap();
+/// assert_eq!(replies, vec![Some(15), Some(15)]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BitFieldOps {
+    ops: Vec<BitFieldOp>,
+}
+
+impl BitFieldOps {
+    /// Reads a field.
+    pub fn get(mut self, ty: BitFieldType, offset: BitFieldOffset) -> Self {
+        self.ops.push(BitFieldOp::Get(ty, offset));
+        self
+    }
+
+    /// Writes a field, replying with its previous value.
+    pub fn set(mut self, ty: BitFieldType, offset: BitFieldOffset, value: i64) -> Self {
+        self.ops.push(BitFieldOp::Set(ty, offset, value));
+        self
+    }
+
+    /// Adds to a field, replying with its new value.
+    pub fn incr_by(mut self, ty: BitFieldType, offset: BitFieldOffset, increment: i64) -> Self {
+        self.ops.push(BitFieldOp::IncrBy(ty, offset, increment));
+        self
+    }
+
+    /// Sets the overflow behavior of the writes that follow.
+    pub fn overflow(mut self, overflow: BitFieldOverflow) -> Self {
+        self.ops.push(BitFieldOp::Overflow(overflow));
+        self
+    }
+
+    pub(crate) fn write_args(&self, cmd: &mut Cmd) {
+        for op in &self.ops {
+            match *op {
+                BitFieldOp::Get(ty, offset) => {
+                    cmd.arg("GET");
+                    ty.write_args(cmd);
+                    offset.write_args(cmd);
+                }
+                BitFieldOp::Set(ty, offset, value) => {
+                    cmd.arg("SET");
+                    ty.write_args(cmd);
+                    offset.write_args(cmd);
+                    cmd.arg(value);
+                }
+                BitFieldOp::IncrBy(ty, offset, increment) => {
+                    cmd.arg("INCRBY");
+                    ty.write_args(cmd);
+                    offset.write_args(cmd);
+                    cmd.arg(increment);
+                }
+                BitFieldOp::Overflow(overflow) => {
+                    cmd.arg("OVERFLOW").arg(match overflow {
+                        BitFieldOverflow::Wrap => "WRAP",
+                        BitFieldOverflow::Sat => "SAT",
+                        BitFieldOverflow::Fail => "FAIL",
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl BitFieldType {
+    fn write_args(self, cmd: &mut Cmd) {
+        let sign = if self.signed { 'i' } else { 'u' };
+        cmd.arg(format!("{sign}{}", self.bits));
+    }
+}
+
+impl BitFieldOffset {
+    fn write_args(self, cmd: &mut Cmd) {
+        match self {
+            BitFieldOffset::Bit(offset) => cmd.arg(offset),
+            BitFieldOffset::Index(index) => cmd.arg(format!("#{index}")),
+        };
+    }
+}
+
+impl ExpireOption {
+    pub(crate) fn write_args(self, cmd: &mut Cmd) {
+        match self.condition {
+            ExpireCondition::Always => {}
+            ExpireCondition::Nx => {
+                cmd.arg("NX");
+            }
+            ExpireCondition::Gt => {
+                cmd.arg("GT");
+            }
+            ExpireCondition::Lt => {
+                cmd.arg("LT");
+            }
+        }
+        if self.xx {
+            cmd.arg("XX");
+        }
+    }
+}
+
+/// An integer reply that uses negative values to signal special cases.
+///
+/// Commands such as `TTL` reply with `-2` when the key does not exist and
+/// `-1` when the key exists but the reply does not apply to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegerReplyOrNoOp {
+    /// A regular, non-negative integer reply.
+    IntegerReply(usize),
+    /// The key does not exist (`-2`).
+    NotExists,
+    /// The key exists but the reply does not apply to it (`-1`).
+    ExistsButNotRelevant,
+}
+
+impl IntegerReplyOrNoOp {
+    /// Returns the raw integer Redis would have replied with.
+    pub fn raw(&self) -> isize {
+        match self {
+            IntegerReplyOrNoOp::IntegerReply(n) => *n as isize,
+            IntegerReplyOrNoOp::NotExists => -2,
+            IntegerReplyOrNoOp::ExistsButNotRelevant => -1,
+        }
+    }
+}
+
+impl FromRedisValue for IntegerReplyOrNoOp {
+    fn from_redis_value(v: Value) -> RedisResult<Self> {
+        match i64::from_redis_value(v)? {
+            -2 => Ok(IntegerReplyOrNoOp::NotExists),
+            -1 => Ok(IntegerReplyOrNoOp::ExistsButNotRelevant),
+            n => usize::try_from(n)
+                .map(IntegerReplyOrNoOp::IntegerReply)
+                .map_err(|_| RedisError::ParseError),
+        }
+    }
+}
+
+/// A command handler. Receives the arguments that follow the command name.
+///
+/// The engine is borrowed mutably so connection commands such as `SELECT`
+/// can change the handle's selected database.
+type Handler = fn(&mut StorageEngine, &[Vec<u8>]) -> RedisResult<Value>;
+
+/// An entry in the command table.
+struct CommandSpec {
+    /// The uppercase command name.
+    name: &'static str,
+    /// Argument count including the command name; negative means "at least".
+    arity: i32,
+    handler: Handler,
+}
+
+/// Every command [`execute_command`] knows how to run.
+///
+/// To add a command, write its handler in the module for its data type and
+/// register it here.
+static COMMAND_TABLE: &[CommandSpec] = &[
+    // Strings
+    CommandSpec {
+        name: "GET",
+        arity: 2,
+        handler: |storage, args| string::get(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "SET",
+        arity: -3,
+        handler: |storage, args| {
+            let options = parse_set_options(&args[2..])?;
+            string::set(storage, args[0].clone(), args[1].clone(), options)
+        },
+    },
+    CommandSpec {
+        name: "MSET",
+        arity: -3,
+        handler: |storage, args| string::mset(storage, parse_pairs("mset", args)?),
+    },
+    CommandSpec {
+        name: "MSETNX",
+        arity: -3,
+        handler: |storage, args| string::msetnx(storage, parse_pairs("msetnx", args)?),
+    },
+    CommandSpec {
+        name: "MGET",
+        arity: -2,
+        handler: |storage, args| string::mget(storage, args),
+    },
+    CommandSpec {
+        name: "SETNX",
+        arity: 3,
+        handler: |storage, args| string::setnx(storage, args[0].clone(), args[1].clone()),
+    },
+    CommandSpec {
+        name: "SETEX",
+        arity: 4,
+        handler: |storage, args| {
+            string::setex(
+                storage,
+                args[0].clone(),
+                parse_int(&args[1])?,
+                args[2].clone(),
+            )
+        },
+    },
+    CommandSpec {
+        name: "PSETEX",
+        arity: 4,
+        handler: |storage, args| {
+            string::psetex(
+                storage,
+                args[0].clone(),
+                parse_int(&args[1])?,
+                args[2].clone(),
+            )
+        },
+    },
+    CommandSpec {
+        name: "INCR",
+        arity: 2,
+        handler: |storage, args| string::incrby(storage, args[0].clone(), 1),
+    },
+    CommandSpec {
+        name: "DECR",
+        arity: 2,
+        handler: |storage, args| string::incrby(storage, args[0].clone(), -1),
+    },
+    CommandSpec {
+        name: "INCRBY",
+        arity: 3,
+        handler: |storage, args| string::incrby(storage, args[0].clone(), parse_int(&args[1])?),
+    },
+    CommandSpec {
+        name: "DECRBY",
+        arity: 3,
+        handler: |storage, args| string::decrby(storage, args[0].clone(), parse_int(&args[1])?),
+    },
+    CommandSpec {
+        name: "INCRBYFLOAT",
+        arity: 3,
+        handler: |storage, args| {
+            string::incrbyfloat(storage, args[0].clone(), parse_float(&args[1])?)
+        },
+    },
+    CommandSpec {
+        name: "APPEND",
+        arity: 3,
+        handler: |storage, args| string::append(storage, args[0].clone(), &args[1]),
+    },
+    CommandSpec {
+        name: "GETRANGE",
+        arity: 4,
+        handler: |storage, args| {
+            string::getrange(
+                storage,
+                &args[0],
+                parse_int(&args[1])?,
+                parse_int(&args[2])?,
+            )
+        },
+    },
+    CommandSpec {
+        name: "SETRANGE",
+        arity: 4,
+        handler: |storage, args| {
+            string::setrange(storage, args[0].clone(), parse_int(&args[1])?, &args[2])
+        },
+    },
+    CommandSpec {
+        name: "STRLEN",
+        arity: 2,
+        handler: |storage, args| string::strlen(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "LCS",
+        arity: -3,
+        handler: |storage, args| {
+            let reply = parse_lcs_options(&args[2..])?;
+            string::lcs(storage, &args[0], &args[1], reply)
+        },
+    },
+    CommandSpec {
+        name: "SETBIT",
+        arity: 4,
+        handler: |storage, args| {
+            bitmap::setbit(
+                storage,
+                args[0].clone(),
+                parse_int(&args[1])?,
+                parse_int(&args[2])?,
+            )
+        },
+    },
+    CommandSpec {
+        name: "GETBIT",
+        arity: 3,
+        handler: |storage, args| bitmap::getbit(storage, &args[0], parse_int(&args[1])?),
+    },
+    CommandSpec {
+        name: "BITCOUNT",
+        arity: -2,
+        handler: |storage, args| {
+            let range = match &args[1..] {
+                [] => None,
+                [start, end, unit @ ..] if unit.len() <= 1 => Some((
+                    parse_int(start)?,
+                    parse_int(end)?,
+                    parse_bit_unit(unit.first())?,
+                )),
+                _ => return Err(syntax_error()),
+            };
+            bitmap::bitcount(storage, &args[0], range)
+        },
+    },
+    CommandSpec {
+        name: "BITPOS",
+        arity: -3,
+        handler: |storage, args| {
+            let bit = match parse_int(&args[1])? {
+                0 => false,
+                1 => true,
+                _ => {
+                    return Err(RedisError::InvalidArgument(
+                        "The bit argument must be 1 or 0.".to_string(),
+                    ))
+                }
+            };
+            if args.len() > 5 {
+                return Err(syntax_error());
+            }
+            let start = args.get(2).map(|arg| parse_int(arg)).transpose()?;
+            let end = args.get(3).map(|arg| parse_int(arg)).transpose()?;
+            let unit = parse_bit_unit(args.get(4))?;
+            bitmap::bitpos(storage, &args[0], bit, start, end, unit)
+        },
+    },
+    CommandSpec {
+        name: "BITOP",
+        arity: -4,
+        handler: |storage, args| {
+            let op = match args[0].to_ascii_uppercase().as_slice() {
+                b"AND" => BitOp::And,
+                b"OR" => BitOp::Or,
+                b"XOR" => BitOp::Xor,
+                b"NOT" if args.len() == 3 => BitOp::Not,
+                b"NOT" => {
+                    return Err(RedisError::InvalidArgument(
+                        "BITOP NOT must be called with a single source key.".to_string(),
+                    ))
+                }
+                _ => return Err(syntax_error()),
+            };
+            bitmap::bitop(storage, op, args[1].clone(), &args[2..])
+        },
+    },
+    CommandSpec {
+        name: "BITFIELD",
+        arity: -2,
+        handler: |storage, args| {
+            let ops = parse_bitfield_ops(&args[1..])?;
+            bitmap::bitfield(storage, args[0].clone(), &ops, false)
+        },
+    },
+    CommandSpec {
+        name: "BITFIELD_RO",
+        arity: -2,
+        handler: |storage, args| {
+            let ops = parse_bitfield_ops(&args[1..])?;
+            bitmap::bitfield(storage, args[0].clone(), &ops, true)
+        },
+    },
+    CommandSpec {
+        name: "GETSET",
+        arity: 3,
+        handler: |storage, args| string::getset(storage, args[0].clone(), args[1].clone()),
+    },
+    CommandSpec {
+        name: "GETDEL",
+        arity: 2,
+        handler: |storage, args| string::getdel(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "GETEX",
+        arity: -2,
+        handler: |storage, args| {
+            let expiry = parse_getex_expiry(&args[1..], "getex")?;
+            string::getex(storage, &args[0], expiry)
+        },
+    },
+    // Keyspace
+    CommandSpec {
+        name: "DEL",
+        arity: -2,
+        handler: |storage, args| key::del(storage, args),
+    },
+    CommandSpec {
+        name: "UNLINK",
+        arity: -2,
+        handler: |storage, args| key::del(storage, args),
+    },
+    CommandSpec {
+        name: "EXISTS",
+        arity: -2,
+        handler: |storage, args| key::exists(storage, args),
+    },
+    CommandSpec {
+        name: "TOUCH",
+        arity: -2,
+        handler: |storage, args| key::exists(storage, args),
+    },
+    CommandSpec {
+        name: "EXPIRE",
+        arity: -3,
+        handler: |storage, args| {
+            let option = parse_expire_option(&args[2..])?;
+            key::expire(storage, &args[0], parse_int(&args[1])?, option)
+        },
+    },
+    CommandSpec {
+        name: "PEXPIRE",
+        arity: -3,
+        handler: |storage, args| {
+            let option = parse_expire_option(&args[2..])?;
+            key::pexpire(storage, &args[0], parse_int(&args[1])?, option)
+        },
+    },
+    CommandSpec {
+        name: "EXPIREAT",
+        arity: -3,
+        handler: |storage, args| {
+            let option = parse_expire_option(&args[2..])?;
+            key::expire_at(storage, &args[0], parse_int(&args[1])?, option)
+        },
+    },
+    CommandSpec {
+        name: "PEXPIREAT",
+        arity: -3,
+        handler: |storage, args| {
+            let option = parse_expire_option(&args[2..])?;
+            key::pexpire_at(storage, &args[0], parse_int(&args[1])?, option)
+        },
+    },
+    CommandSpec {
+        name: "EXPIRETIME",
+        arity: 2,
+        handler: |storage, args| key::expire_time(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "PEXPIRETIME",
+        arity: 2,
+        handler: |storage, args| key::pexpire_time(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "TTL",
+        arity: 2,
+        handler: |storage, args| key::ttl(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "PTTL",
+        arity: 2,
+        handler: |storage, args| key::pttl(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "PERSIST",
+        arity: 2,
+        handler: |storage, args| key::persist(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "KEYS",
+        arity: 2,
+        handler: |storage, args| key::keys(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "SCAN",
+        arity: -2,
+        handler: |storage, args| {
+            let cursor = u64::try_from(parse_int(&args[0])?)
+                .map_err(|_| RedisError::InvalidArgument("invalid cursor".to_string()))?;
+            key::scan(storage, cursor, &parse_scan_options(&args[1..])?)
+        },
+    },
+    CommandSpec {
+        name: "TYPE",
+        arity: 2,
+        handler: |storage, args| key::key_type(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "RENAME",
+        arity: 3,
+        handler: |storage, args| key::rename(storage, &args[0], args[1].clone()),
+    },
+    CommandSpec {
+        name: "RENAMENX",
+        arity: 3,
+        handler: |storage, args| key::renamenx(storage, &args[0], args[1].clone()),
+    },
+    CommandSpec {
+        name: "COPY",
+        arity: -3,
+        handler: |storage, args| {
+            let mut replace = false;
+            let mut db = None;
+            let mut rest = args[2..].iter();
+            while let Some(opt) = rest.next() {
+                if opt.eq_ignore_ascii_case(b"REPLACE") {
+                    replace = true;
+                } else if opt.eq_ignore_ascii_case(b"DB") {
+                    db = Some(parse_int(rest.next().ok_or_else(syntax_error)?)?);
+                } else {
+                    return Err(syntax_error());
+                }
+            }
+            key::copy(storage, &args[0], args[1].clone(), db, replace)
+        },
+    },
+    CommandSpec {
+        name: "MOVE",
+        arity: 3,
+        handler: |storage, args| key::move_key(storage, &args[0], parse_int(&args[1])?),
+    },
+    // Hashes
+    CommandSpec {
+        name: "HSET",
+        arity: -4,
+        handler: |storage, args| {
+            hash::hset(storage, args[0].clone(), parse_pairs("HSET", &args[1..])?)
+        },
+    },
+    CommandSpec {
+        name: "HSETNX",
+        arity: 4,
+        handler: |storage, args| {
+            hash::hsetnx(storage, args[0].clone(), args[1].clone(), args[2].clone())
+        },
+    },
+    CommandSpec {
+        name: "HGET",
+        arity: 3,
+        handler: |storage, args| hash::hget(storage, &args[0], &args[1]),
+    },
+    CommandSpec {
+        name: "HMGET",
+        arity: -3,
+        handler: |storage, args| hash::hmget(storage, &args[0], &args[1..]),
+    },
+    CommandSpec {
+        name: "HGETALL",
+        arity: 2,
+        handler: |storage, args| hash::hgetall(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "HKEYS",
+        arity: 2,
+        handler: |storage, args| hash::hkeys(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "HVALS",
+        arity: 2,
+        handler: |storage, args| hash::hvals(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "HLEN",
+        arity: 2,
+        handler: |storage, args| hash::hlen(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "HEXISTS",
+        arity: 3,
+        handler: |storage, args| hash::hexists(storage, &args[0], &args[1]),
+    },
+    CommandSpec {
+        name: "HSTRLEN",
+        arity: 3,
+        handler: |storage, args| hash::hstrlen(storage, &args[0], &args[1]),
+    },
+    CommandSpec {
+        name: "HDEL",
+        arity: -3,
+        handler: |storage, args| hash::hdel(storage, &args[0], &args[1..]),
+    },
+    CommandSpec {
+        name: "HINCRBY",
+        arity: 4,
+        handler: |storage, args| {
+            hash::hincrby(
+                storage,
+                args[0].clone(),
+                args[1].clone(),
+                parse_int(&args[2])?,
+            )
+        },
+    },
+    CommandSpec {
+        name: "HINCRBYFLOAT",
+        arity: 4,
+        handler: |storage, args| {
+            hash::hincrbyfloat(
+                storage,
+                args[0].clone(),
+                args[1].clone(),
+                parse_float(&args[2])?,
+            )
+        },
+    },
+    CommandSpec {
+        name: "HRANDFIELD",
+        arity: -2,
+        handler: |storage, args| {
+            let (count, with_values) = match &args[1..] {
+                [] => (None, false),
+                [count] => (Some(parse_int(count)?), false),
+                [count, option] if option.eq_ignore_ascii_case(b"WITHVALUES") => {
+                    (Some(parse_int(count)?), true)
+                }
+                _ => return Err(syntax_error()),
+            };
+            hash::hrandfield(storage, &args[0], count, with_values)
+        },
+    },
+    CommandSpec {
+        name: "HEXPIRE",
+        arity: -6,
+        handler: |storage, args| {
+            let (options, fields) = parse_fields(&args[2..], 1)?;
+            let option = parse_expire_option(options)?;
+            hash::hexpire(storage, &args[0], parse_int(&args[1])?, option, fields)
+        },
+    },
+    CommandSpec {
+        name: "HPEXPIRE",
+        arity: -6,
+        handler: |storage, args| {
+            let (options, fields) = parse_fields(&args[2..], 1)?;
+            let option = parse_expire_option(options)?;
+            hash::hpexpire(storage, &args[0], parse_int(&args[1])?, option, fields)
+        },
+    },
+    CommandSpec {
+        name: "HEXPIREAT",
+        arity: -6,
+        handler: |storage, args| {
+            let (options, fields) = parse_fields(&args[2..], 1)?;
+            let option = parse_expire_option(options)?;
+            hash::hexpire_at(storage, &args[0], parse_int(&args[1])?, option, fields)
+        },
+    },
+    CommandSpec {
+        name: "HPEXPIREAT",
+        arity: -6,
+        handler: |storage, args| {
+            let (options, fields) = parse_fields(&args[2..], 1)?;
+            let option = parse_expire_option(options)?;
+            hash::hpexpire_at(storage, &args[0], parse_int(&args[1])?, option, fields)
+        },
+    },
+    CommandSpec {
+        name: "HTTL",
+        arity: -5,
+        handler: |storage, args| hash::httl(storage, &args[0], parse_fields_only(&args[1..])?),
+    },
+    CommandSpec {
+        name: "HPTTL",
+        arity: -5,
+        handler: |storage, args| hash::hpttl(storage, &args[0], parse_fields_only(&args[1..])?),
+    },
+    CommandSpec {
+        name: "HEXPIRETIME",
+        arity: -5,
+        handler: |storage, args| {
+            hash::hexpire_time(storage, &args[0], parse_fields_only(&args[1..])?)
+        },
+    },
+    CommandSpec {
+        name: "HPEXPIRETIME",
+        arity: -5,
+        handler: |storage, args| {
+            hash::hpexpire_time(storage, &args[0], parse_fields_only(&args[1..])?)
+        },
+    },
+    CommandSpec {
+        name: "HPERSIST",
+        arity: -5,
+        handler: |storage, args| hash::hpersist(storage, &args[0], parse_fields_only(&args[1..])?),
+    },
+    CommandSpec {
+        name: "HGETEX",
+        arity: -5,
+        handler: |storage, args| {
+            let (options, fields) = parse_fields(&args[1..], 1)?;
+            let expiry = parse_getex_expiry(options, "hgetex")?;
+            hash::hgetex(storage, &args[0], expiry, fields)
+        },
+    },
+    CommandSpec {
+        name: "HSETEX",
+        arity: -6,
+        handler: |storage, args| {
+            let (options, fields) = parse_fields(&args[1..], 2)?;
+            let options = parse_hsetex_options(options)?;
+            let pairs = fields
+                .chunks_exact(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect();
+            hash::hsetex(storage, args[0].clone(), options, pairs)
+        },
+    },
+    // Lists
+    CommandSpec {
+        name: "LPUSH",
+        arity: -3,
+        handler: |storage, args| list::lpush(storage, args[0].clone(), args[1..].to_vec()),
+    },
+    CommandSpec {
+        name: "RPUSH",
+        arity: -3,
+        handler: |storage, args| list::rpush(storage, args[0].clone(), args[1..].to_vec()),
+    },
+    CommandSpec {
+        name: "LPUSHX",
+        arity: -3,
+        handler: |storage, args| list::lpushx(storage, &args[0], args[1..].to_vec()),
+    },
+    CommandSpec {
+        name: "RPUSHX",
+        arity: -3,
+        handler: |storage, args| list::rpushx(storage, &args[0], args[1..].to_vec()),
+    },
+    CommandSpec {
+        name: "LPOP",
+        arity: -2,
+        handler: |storage, args| list::lpop(storage, &args[0], parse_pop_count(&args[1..])?),
+    },
+    CommandSpec {
+        name: "RPOP",
+        arity: -2,
+        handler: |storage, args| list::rpop(storage, &args[0], parse_pop_count(&args[1..])?),
+    },
+    CommandSpec {
+        name: "LLEN",
+        arity: 2,
+        handler: |storage, args| list::llen(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "LRANGE",
+        arity: 4,
+        handler: |storage, args| {
+            list::lrange(
+                storage,
+                &args[0],
+                parse_int(&args[1])?,
+                parse_int(&args[2])?,
+            )
+        },
+    },
+    CommandSpec {
+        name: "LINDEX",
+        arity: 3,
+        handler: |storage, args| list::lindex(storage, &args[0], parse_int(&args[1])?),
+    },
+    CommandSpec {
+        name: "LSET",
+        arity: 4,
+        handler: |storage, args| {
+            list::lset(storage, &args[0], parse_int(&args[1])?, args[2].clone())
+        },
+    },
+    CommandSpec {
+        name: "LINSERT",
+        arity: 5,
+        handler: |storage, args| {
+            let before = match args[1].to_ascii_uppercase().as_slice() {
+                b"BEFORE" => true,
+                b"AFTER" => false,
+                _ => return Err(syntax_error()),
+            };
+            list::linsert(storage, &args[0], before, &args[2], args[3].clone())
+        },
+    },
+    CommandSpec {
+        name: "LREM",
+        arity: 4,
+        handler: |storage, args| list::lrem(storage, &args[0], parse_int(&args[1])?, &args[2]),
+    },
+    CommandSpec {
+        name: "LTRIM",
+        arity: 4,
+        handler: |storage, args| {
+            list::ltrim(
+                storage,
+                &args[0],
+                parse_int(&args[1])?,
+                parse_int(&args[2])?,
+            )
+        },
+    },
+    CommandSpec {
+        name: "LPOS",
+        arity: -3,
+        handler: |storage, args| {
+            list::lpos(storage, &args[0], &args[1], parse_lpos_options(&args[2..])?)
+        },
+    },
+    // Sets
+    CommandSpec {
+        name: "SADD",
+        arity: -3,
+        handler: |storage, args| set::sadd(storage, args[0].clone(), args[1..].iter().cloned()),
+    },
+    CommandSpec {
+        name: "SREM",
+        arity: -3,
+        handler: |storage, args| set::srem(storage, &args[0], &args[1..]),
+    },
+    CommandSpec {
+        name: "SMEMBERS",
+        arity: 2,
+        handler: |storage, args| set::smembers(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "SISMEMBER",
+        arity: 3,
+        handler: |storage, args| set::sismember(storage, &args[0], &args[1]),
+    },
+    CommandSpec {
+        name: "SCARD",
+        arity: 2,
+        handler: |storage, args| set::scard(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "SPOP",
+        arity: -2,
+        handler: |storage, args| {
+            let count = match &args[1..] {
+                [] => None,
+                [count] => Some(parse_count(count)?),
+                _ => return Err(syntax_error()),
+            };
+            set::spop(storage, &args[0], count)
+        },
+    },
+    // Sorted sets
+    CommandSpec {
+        name: "ZADD",
+        arity: -4,
+        handler: |storage, args| {
+            if args[1..].len() % 2 != 0 {
+                return Err(syntax_error());
+            }
+            let members = args[1..]
+                .chunks(2)
+                .map(|pair| Ok((parse_float(&pair[0])?, pair[1].clone())))
+                .collect::<RedisResult<Vec<_>>>()?;
+            zset::zadd(storage, args[0].clone(), members)
+        },
+    },
+    CommandSpec {
+        name: "ZREM",
+        arity: -3,
+        handler: |storage, args| zset::zrem(storage, &args[0], &args[1..]),
+    },
+    CommandSpec {
+        name: "ZRANGE",
+        arity: -4,
+        handler: |storage, args| {
+            let withscores = parse_withscores(&args[3..])?;
+            zset::zrange(
+                storage,
+                &args[0],
+                parse_int(&args[1])?,
+                parse_int(&args[2])?,
+                withscores,
+            )
+        },
+    },
+    CommandSpec {
+        name: "ZRANGEBYSCORE",
+        arity: -4,
+        handler: |storage, args| {
+            let withscores = parse_withscores(&args[3..])?;
+            zset::zrangebyscore(
+                storage,
+                &args[0],
+                zset::ScoreBound::parse(&args[1])?,
+                zset::ScoreBound::parse(&args[2])?,
+                withscores,
+            )
+        },
+    },
+    CommandSpec {
+        name: "ZCARD",
+        arity: 2,
+        handler: |storage, args| zset::zcard(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "ZSCORE",
+        arity: 3,
+        handler: |storage, args| zset::zscore(storage, &args[0], &args[1]),
+    },
+    CommandSpec {
+        name: "ZCOUNT",
+        arity: 4,
+        handler: |storage, args| {
+            zset::zcount(
+                storage,
+                &args[0],
+                zset::ScoreBound::parse(&args[1])?,
+                zset::ScoreBound::parse(&args[2])?,
+            )
+        },
+    },
+    // Streams
+    CommandSpec {
+        name: "XADD",
+        arity: -5,
+        handler: |storage, args| {
+            let fields = parse_pairs("xadd", &args[2..])?;
+            stream::xadd(storage, &args[0], &args[1], fields)
+        },
+    },
+    CommandSpec {
+        name: "XLEN",
+        arity: 2,
+        handler: |storage, args| stream::xlen(storage, &args[0]),
+    },
+    CommandSpec {
+        name: "XTRIM",
+        arity: -4,
+        handler: |storage, args| {
+            let (approximate, threshold) = match &args[1..] {
+                [strategy, threshold] if strategy.eq_ignore_ascii_case(b"MAXLEN") => {
+                    (false, threshold)
+                }
+                [strategy, mode, threshold]
+                    if strategy.eq_ignore_ascii_case(b"MAXLEN")
+                        && (mode.as_slice() == b"~" || mode.as_slice() == b"=") =>
+                {
+                    (mode.as_slice() == b"~", threshold)
+                }
+                _ => return Err(syntax_error()),
+            };
+            stream::xtrim(storage, &args[0], parse_count(threshold)?, approximate)
+        },
+    },
+    CommandSpec {
+        name: "XDEL",
+        arity: -3,
+        handler: |storage, args| stream::xdel(storage, &args[0], &args[1..]),
+    },
+    CommandSpec {
+        name: "XRANGE",
+        arity: -4,
+        handler: |storage, args| {
+            let count = parse_stream_count(&args[3..])?;
+            stream::xrange(storage, &args[0], &args[1], &args[2], count)
+        },
+    },
+    CommandSpec {
+        name: "XREVRANGE",
+        arity: -4,
+        handler: |storage, args| {
+            let count = parse_stream_count(&args[3..])?;
+            stream::xrevrange(storage, &args[0], &args[1], &args[2], count)
+        },
+    },
+    // Server
+    CommandSpec {
+        name: "PING",
+        arity: -1,
+        handler: |_, args| match args {
+            [] => server::ping(None),
+            [msg] => server::ping(Some(msg.clone())),
+            _ => Err(wrong_arity("ping")),
+        },
+    },
+    CommandSpec {
+        name: "ECHO",
+        arity: 2,
+        handler: |_, args| server::echo(args[0].clone()),
+    },
+    CommandSpec {
+        name: "SELECT",
+        arity: 2,
+        handler: |storage, args| server::select(storage, parse_int(&args[0])?),
+    },
+    CommandSpec {
+        name: "SWAPDB",
+        arity: 3,
+        handler: |storage, args| {
+            let invalid =
+                |which: &str| RedisError::InvalidArgument(format!("invalid {} DB index", which));
+            let a = parse_int(&args[0]).map_err(|_| invalid("first"))?;
+            let b = parse_int(&args[1]).map_err(|_| invalid("second"))?;
+            server::swapdb(storage, a, b)
+        },
+    },
+    CommandSpec {
+        name: "DBSIZE",
+        arity: 1,
+        handler: |storage, _| server::dbsize(storage),
+    },
+    CommandSpec {
+        name: "FLUSHDB",
+        arity: -1,
+        handler: |storage, args| {
+            parse_flush_mode(args)?;
+            server::flushdb(storage)
+        },
+    },
+    CommandSpec {
+        name: "FLUSHALL",
+        arity: -1,
+        handler: |storage, args| {
+            parse_flush_mode(args)?;
+            server::flushall(storage)
+        },
+    },
+    CommandSpec {
+        name: "TIME",
+        arity: 1,
+        handler: |storage, _| server::time(storage),
+    },
+    CommandSpec {
+        name: "LASTSAVE",
+        arity: 1,
+        handler: |storage, _| server::lastsave(storage),
+    },
+];
+
+static COMMANDS_BY_NAME: LazyLock<FxHashMap<&'static str, &'static CommandSpec>> =
+    LazyLock::new(|| COMMAND_TABLE.iter().map(|spec| (spec.name, spec)).collect());
+
+/// Executes a command against the storage engine.
+///
+/// The command name is matched case-insensitively. Unknown commands fail
+/// with [`RedisError::CommandNotFound`].
+pub(crate) fn execute_command(storage: &mut StorageEngine, cmd: &Cmd) -> RedisResult<Value> {
+    let Some((name, args)) = cmd.args.split_first() else {
+        return Err(RedisError::InvalidArgument("empty command".to_string()));
+    };
+    let name = String::from_utf8_lossy(name).to_ascii_uppercase();
+    let Some(spec) = COMMANDS_BY_NAME.get(name.as_str()) else {
+        return Err(RedisError::CommandNotFound(name));
+    };
+    check_arity(spec.name, cmd.args.len(), spec.arity)?;
+    (spec.handler)(storage, args)
+}
+
+/// Converts a single RESP value into the raw bytes of a command argument.
+///
+/// Returns `None` for values that do not produce an argument, such as null.
+pub(crate) fn value_to_bytes(v: Value) -> Option<Vec<u8>> {
+    match v {
+        Value::String(s) => Some(s),
+        Value::Int(n) => Some(n.to_string().into_bytes()),
+        Value::Bool(b) => Some((if b { "1" } else { "0" }).as_bytes().to_vec()),
+        _ => None,
+    }
+}
+
+/// Normalizes a Redis-style inclusive `start..=stop` range over `len` items.
+///
+/// Negative indexes count from the end. Returns `None` when the range is
+/// empty.
+pub(crate) fn normalize_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (start + len).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        stop + len
+    } else {
+        stop.min(len - 1)
+    };
+    if start > stop || start >= len {
+        return None;
+    }
+    Some((start as usize, stop as usize))
+}
+
+/// Formats a float the way Redis replies to `INCRBYFLOAT` and
+/// `HINCRBYFLOAT`: in plain decimal notation with trailing zeros trimmed.
+///
+/// Redis prints 17 significant digits of a `long double`, which hides the
+/// binary rounding error of ordinary decimal input. An `f64` carries about
+/// two digits fewer, so fractions are rounded to 15 significant digits to
+/// the same effect: `0.1 + 0.2` formats as `0.3`. Integral values are
+/// printed exactly.
+pub(crate) fn format_float(value: f64) -> Vec<u8> {
+    let value = if value.fract() == 0.0 {
+        value
+    } else {
+        format!("{value:.14e}").parse().unwrap_or(value)
+    };
+    // `Display` never uses an exponent and prints the shortest digits that
+    // round-trip, so the rounded value comes out with its zeros trimmed.
+    value.to_string().into_bytes()
+}
+
+fn check_arity(name: &str, argc: usize, arity: i32) -> RedisResult<()> {
+    let ok = if arity >= 0 {
+        argc == arity as usize
+    } else {
+        argc >= arity.unsigned_abs() as usize
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(wrong_arity(name))
+    }
+}
+
+fn wrong_arity(name: &str) -> RedisError {
+    RedisError::InvalidArgument(format!(
+        "wrong number of arguments for '{}' command",
+        name.to_ascii_lowercase()
+    ))
+}
+
+/// Accepts the optional `ASYNC` or `SYNC` of `FLUSHDB` and `FLUSHALL`.
+///
+/// Both flush before replying: dropping the data in the background would
+/// gain nothing without a server loop to keep serving.
+fn parse_flush_mode(args: &[Vec<u8>]) -> RedisResult<()> {
+    match args {
+        [] => Ok(()),
+        [mode] if mode.eq_ignore_ascii_case(b"ASYNC") || mode.eq_ignore_ascii_case(b"SYNC") => {
+            Ok(())
+        }
+        _ => Err(syntax_error()),
+    }
+}
+
+fn syntax_error() -> RedisError {
+    RedisError::InvalidArgument("syntax error".to_string())
+}
+
+fn parse_int(arg: &[u8]) -> RedisResult<i64> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| {
+            RedisError::InvalidArgument("value is not an integer or out of range".to_string())
+        })
+}
+
+fn parse_count(arg: &[u8]) -> RedisResult<usize> {
+    usize::try_from(parse_int(arg)?).map_err(|_| {
+        RedisError::InvalidArgument("value is out of range, must be positive".to_string())
+    })
+}
+
+fn parse_float(arg: &[u8]) -> RedisResult<f64> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|f| !f.is_nan())
+        .ok_or_else(|| RedisError::InvalidArgument("value is not a valid float".to_string()))
+}
+
+/// Parses the `NX`, `XX`, `GT` and `LT` flags of the `EXPIRE` family.
+///
+/// `XX` may be combined with `GT` or `LT`. The other combinations are
+/// rejected, as they are by Redis.
+fn parse_expire_option(args: &[Vec<u8>]) -> RedisResult<ExpireOption> {
+    let (mut nx, mut xx, mut gt, mut lt) = (false, false, false, false);
+    for arg in args {
+        match arg.to_ascii_uppercase().as_slice() {
+            b"NX" => nx = true,
+            b"XX" => xx = true,
+            b"GT" => gt = true,
+            b"LT" => lt = true,
+            _ => return Err(syntax_error()),
+        }
+    }
+    let option = match (nx, xx, gt, lt) {
+        (false, _, false, false) => ExpireOption::NONE,
+        (true, false, false, false) => ExpireOption::NX,
+        (false, _, true, false) => ExpireOption::GT,
+        (false, _, false, true) => ExpireOption::LT,
+        (true, ..) => {
+            return Err(RedisError::InvalidArgument(
+                "NX and XX, GT or LT options at the same time are not compatible".to_string(),
+            ))
+        }
+        (false, _, true, true) => {
+            return Err(RedisError::InvalidArgument(
+                "GT and LT options at the same time are not compatible".to_string(),
+            ))
+        }
+    };
+    Ok(option.xx(xx))
+}
+
+/// Parses the optional `BYTE` or `BIT` of `BITCOUNT` and `BITPOS`.
+fn parse_bit_unit(arg: Option<&Vec<u8>>) -> RedisResult<BitUnit> {
+    match arg.map(|arg| arg.to_ascii_uppercase()).as_deref() {
+        None | Some(b"BYTE") => Ok(BitUnit::Byte),
+        Some(b"BIT") => Ok(BitUnit::Bit),
+        Some(_) => Err(syntax_error()),
+    }
+}
+
+/// Parses the subcommands of `BITFIELD` and `BITFIELD_RO`.
+fn parse_bitfield_ops(args: &[Vec<u8>]) -> RedisResult<Vec<BitFieldOp>> {
+    let mut ops = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut next = || args.next().ok_or_else(syntax_error);
+        let op = match arg.to_ascii_uppercase().as_slice() {
+            b"GET" => BitFieldOp::Get(
+                parse_bitfield_type(next()?)?,
+                parse_bitfield_offset(next()?)?,
+            ),
+            b"SET" => BitFieldOp::Set(
+                parse_bitfield_type(next()?)?,
+                parse_bitfield_offset(next()?)?,
+                parse_int(next()?)?,
+            ),
+            b"INCRBY" => BitFieldOp::IncrBy(
+                parse_bitfield_type(next()?)?,
+                parse_bitfield_offset(next()?)?,
+                parse_int(next()?)?,
+            ),
+            b"OVERFLOW" => BitFieldOp::Overflow(match next()?.to_ascii_uppercase().as_slice() {
+                b"WRAP" => BitFieldOverflow::Wrap,
+                b"SAT" => BitFieldOverflow::Sat,
+                b"FAIL" => BitFieldOverflow::Fail,
+                _ => {
+                    return Err(RedisError::InvalidArgument(
+                        "Invalid OVERFLOW type specified".to_string(),
+                    ))
+                }
+            }),
+            _ => return Err(syntax_error()),
+        };
+        ops.push(op);
+    }
+    Ok(ops)
+}
+
+/// Parses a `BITFIELD` type such as `i8` or `u16`. The width is checked by
+/// the command itself.
+fn parse_bitfield_type(arg: &[u8]) -> RedisResult<BitFieldType> {
+    let bits = std::str::from_utf8(arg.get(1..).unwrap_or_default())
+        .ok()
+        .filter(|bits| bits.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|bits| bits.parse().ok());
+    match (arg.first().map(u8::to_ascii_lowercase), bits) {
+        (Some(b'i'), Some(bits)) => Ok(BitFieldType::signed(bits)),
+        (Some(b'u'), Some(bits)) => Ok(BitFieldType::unsigned(bits)),
+        _ => Err(bitmap::invalid_bitfield_type()),
+    }
+}
+
+/// Parses a `BITFIELD` offset: a bit offset, or `#n` for the `n`-th field.
+fn parse_bitfield_offset(arg: &[u8]) -> RedisResult<BitFieldOffset> {
+    let (index, digits) = match arg.strip_prefix(b"#") {
+        Some(digits) => (true, digits),
+        None => (false, arg),
+    };
+    let offset = parse_int(digits)
+        .ok()
+        .and_then(|offset| usize::try_from(offset).ok())
+        .ok_or_else(bitmap::invalid_bit_offset)?;
+    Ok(if index {
+        BitFieldOffset::Index(offset)
+    } else {
+        BitFieldOffset::Bit(offset)
+    })
+}
+
+/// Splits the arguments of `MSET`, `MSETNX` and `HSET` into pairs.
+fn parse_pairs(name: &str, args: &[Vec<u8>]) -> RedisResult<Vec<(Vec<u8>, Vec<u8>)>> {
+    if !args.len().is_multiple_of(2) {
+        return Err(wrong_arity(name));
+    }
+    Ok(args
+        .chunks_exact(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect())
+}
+
+/// Parses the options of `SET`, which may come in any order.
+fn parse_set_options(args: &[Vec<u8>]) -> RedisResult<SetOptions> {
+    let mut options = SetOptions::default();
+    let mut rest = args.iter();
+    while let Some(opt) = rest.next() {
+        let opt = opt.to_ascii_uppercase();
+        match opt.as_slice() {
+            b"NX" if options.conditional_set.is_none() => {
+                options.conditional_set = Some(ExistenceCheck::NX);
+            }
+            b"XX" if options.conditional_set.is_none() => {
+                options.conditional_set = Some(ExistenceCheck::XX);
+            }
+            b"GET" => options.get = true,
+            b"KEEPTTL" if options.expiration.is_none() => {
+                options.expiration = Some(SetExpiry::KEEPTTL);
+            }
+            b"EX" | b"PX" | b"EXAT" | b"PXAT" if options.expiration.is_none() => {
+                let time = parse_expire_time(rest.next(), "set")?;
+                options.expiration = Some(match opt.as_slice() {
+                    b"EX" => SetExpiry::EX(time),
+                    b"PX" => SetExpiry::PX(time),
+                    b"EXAT" => SetExpiry::EXAT(time),
+                    _ => SetExpiry::PXAT(time),
+                });
+            }
+            _ => return Err(syntax_error()),
+        }
+    }
+    Ok(options)
+}
+
+/// Parses the options of `LCS`. `MINMATCHLEN` and `WITHMATCHLEN` only
+/// matter with `IDX`.
+fn parse_lcs_options(args: &[Vec<u8>]) -> RedisResult<LcsReply> {
+    let (mut len, mut idx, mut min_match_len, mut with_match_len) = (false, false, 0, false);
+    let mut rest = args.iter();
+    while let Some(opt) = rest.next() {
+        match opt.to_ascii_uppercase().as_slice() {
+            b"LEN" => len = true,
+            b"IDX" => idx = true,
+            b"WITHMATCHLEN" => with_match_len = true,
+            b"MINMATCHLEN" => {
+                let n = parse_int(rest.next().ok_or_else(syntax_error)?)?;
+                min_match_len = usize::try_from(n).unwrap_or(0);
+            }
+            _ => return Err(syntax_error()),
+        }
+    }
+    match (len, idx) {
+        (true, true) => Err(RedisError::InvalidArgument(
+            "If you want both the length and indexes, please just use IDX.".to_string(),
+        )),
+        (true, false) => Ok(LcsReply::Len),
+        (false, true) => Ok(LcsReply::Idx {
+            min_match_len,
+            with_match_len,
+        }),
+        (false, false) => Ok(LcsReply::String),
+    }
+}
+
+/// Parses the optional expiration of `GETEX`.
+fn parse_getex_expiry(args: &[Vec<u8>], command: &str) -> RedisResult<Option<Expiry>> {
+    let Some((opt, rest)) = args.split_first() else {
+        return Ok(None);
+    };
+    let opt = opt.to_ascii_uppercase();
+    let expiry = match (opt.as_slice(), rest) {
+        (b"PERSIST", []) => Expiry::PERSIST,
+        (b"EX", [time]) => Expiry::EX(parse_expire_time(Some(time), command)?),
+        (b"PX", [time]) => Expiry::PX(parse_expire_time(Some(time), command)?),
+        (b"EXAT", [time]) => Expiry::EXAT(parse_expire_time(Some(time), command)?),
+        (b"PXAT", [time]) => Expiry::PXAT(parse_expire_time(Some(time), command)?),
+        _ => return Err(syntax_error()),
+    };
+    Ok(Some(expiry))
+}
+
+/// Parses the options of `HSETEX`, which may come in any order.
+fn parse_hsetex_options(args: &[Vec<u8>]) -> RedisResult<HashFieldExpirationOptions> {
+    let mut options = HashFieldExpirationOptions::default();
+    let mut rest = args.iter();
+    while let Some(opt) = rest.next() {
+        let opt = opt.to_ascii_uppercase();
+        match opt.as_slice() {
+            b"FNX" if options.existence_check.is_none() => {
+                options.existence_check = Some(FieldExistenceCheck::FNX);
+            }
+            b"FXX" if options.existence_check.is_none() => {
+                options.existence_check = Some(FieldExistenceCheck::FXX);
+            }
+            b"KEEPTTL" if options.expiration.is_none() => {
+                options.expiration = Some(SetExpiry::KEEPTTL);
+            }
+            b"EX" | b"PX" | b"EXAT" | b"PXAT" if options.expiration.is_none() => {
+                let time = parse_expire_time(rest.next(), "hsetex")?;
+                options.expiration = Some(match opt.as_slice() {
+                    b"EX" => SetExpiry::EX(time),
+                    b"PX" => SetExpiry::PX(time),
+                    b"EXAT" => SetExpiry::EXAT(time),
+                    _ => SetExpiry::PXAT(time),
+                });
+            }
+            _ => return Err(syntax_error()),
+        }
+    }
+    Ok(options)
+}
+
+/// Parses the optional count of `LPOP` and `RPOP`.
+fn parse_pop_count(args: &[Vec<u8>]) -> RedisResult<Option<usize>> {
+    match args {
+        [] => Ok(None),
+        [count] => Ok(Some(parse_count(count)?)),
+        _ => Err(syntax_error()),
+    }
+}
+
+/// Parses the optional `COUNT count` of `XRANGE` and `XREVRANGE`.
+fn parse_stream_count(args: &[Vec<u8>]) -> RedisResult<Option<usize>> {
+    match args {
+        [] => Ok(None),
+        [opt, count] if opt.eq_ignore_ascii_case(b"COUNT") => Ok(Some(parse_count(count)?)),
+        _ => Err(syntax_error()),
+    }
+}
+
+/// Parses the options of `SCAN`, which may come in any order.
+fn parse_scan_options(args: &[Vec<u8>]) -> RedisResult<ScanOptions> {
+    let mut options = ScanOptions::default();
+    let mut rest = args.iter();
+    while let Some(opt) = rest.next() {
+        let value = rest.next().ok_or_else(syntax_error)?;
+        match opt.to_ascii_uppercase().as_slice() {
+            b"MATCH" => options.pattern = Some(value.clone()),
+            b"COUNT" => match parse_int(value)? {
+                count if count < 1 => return Err(syntax_error()),
+                count => options.count = Some(count as usize),
+            },
+            b"TYPE" => options.key_type = Some(value.clone()),
+            _ => return Err(syntax_error()),
+        }
+    }
+    Ok(options)
+}
+
+/// Parses the options of `LPOS`, which may come in any order.
+fn parse_lpos_options(args: &[Vec<u8>]) -> RedisResult<LposOptions> {
+    let mut options = LposOptions::default();
+    let mut rest = args.iter();
+    while let Some(opt) = rest.next() {
+        let opt = opt.to_ascii_uppercase();
+        let value = parse_int(rest.next().ok_or_else(syntax_error)?)?;
+        match opt.as_slice() {
+            b"RANK" => {
+                options.rank = Some(isize::try_from(value).map_err(|_| {
+                    RedisError::InvalidArgument("value is out of range".to_string())
+                })?);
+            }
+            b"COUNT" => {
+                options.count = Some(usize::try_from(value).map_err(|_| {
+                    RedisError::InvalidArgument("COUNT can't be negative".to_string())
+                })?);
+            }
+            b"MAXLEN" => {
+                options.maxlen = Some(usize::try_from(value).map_err(|_| {
+                    RedisError::InvalidArgument("MAXLEN can't be negative".to_string())
+                })?);
+            }
+            _ => return Err(syntax_error()),
+        }
+    }
+    Ok(options)
+}
+
+/// The options and the fields of a hash field command.
+type FieldArgs<'a> = (&'a [Vec<u8>], &'a [Vec<u8>]);
+
+/// Splits the arguments of a hash field command at `FIELDS numfields`,
+/// returning the options before it and the fields after it.
+///
+/// `per_field` is the number of arguments each field takes: `2` for
+/// `HSETEX`, which gives each field a value.
+fn parse_fields(args: &[Vec<u8>], per_field: usize) -> RedisResult<FieldArgs<'_>> {
+    let position = args
+        .iter()
+        .position(|arg| arg.eq_ignore_ascii_case(b"FIELDS"))
+        .ok_or_else(|| {
+            RedisError::InvalidArgument(
+                "Mandatory argument FIELDS is missing or not at the right position".to_string(),
+            )
+        })?;
+    let (options, rest) = args.split_at(position);
+    let count = rest
+        .get(1)
+        .and_then(|count| parse_int(count).ok())
+        .and_then(|count| usize::try_from(count).ok())
+        .filter(|&count| count > 0)
+        .ok_or_else(|| {
+            RedisError::InvalidArgument("Number of fields must be a positive integer".to_string())
+        })?;
+    let fields = &rest[2..];
+    if count.checked_mul(per_field) != Some(fields.len()) {
+        return Err(RedisError::InvalidArgument(
+            "The `numfields` parameter must match the number of arguments".to_string(),
+        ));
+    }
+    Ok((options, fields))
+}
+
+/// Like [`parse_fields`], for commands that take no options.
+fn parse_fields_only(args: &[Vec<u8>]) -> RedisResult<&[Vec<u8>]> {
+    match parse_fields(args, 1)? {
+        ([], fields) => Ok(fields),
+        _ => Err(syntax_error()),
+    }
+}
+
+/// Parses the time that follows `EX`, `PX`, `EXAT` or `PXAT`.
+fn parse_expire_time(arg: Option<&Vec<u8>>, command: &str) -> RedisResult<u64> {
+    let time = parse_int(arg.ok_or_else(syntax_error)?)?;
+    u64::try_from(time).map_err(|_| key::invalid_expire_time(command))
+}
+
+fn parse_withscores(args: &[Vec<u8>]) -> RedisResult<bool> {
+    match args {
+        [] => Ok(false),
+        [opt] if opt.eq_ignore_ascii_case(b"WITHSCORES") => Ok(true),
+        _ => Err(syntax_error()),
+    }
+}
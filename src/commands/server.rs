@@ -0,0 +1,77 @@
+//! Server and connection command handlers.
+
+use std::time::UNIX_EPOCH;
+
+use crate::error::{RedisError, RedisResult};
+use crate::storage::StorageEngine;
+use crate::types::Value;
+
+/// `PING [message]`: returns `PONG`, or `message` if one is given.
+pub(crate) fn ping(message: Option<Vec<u8>>) -> RedisResult<Value> {
+    Ok(Value::String(message.unwrap_or_else(|| b"PONG".to_vec())))
+}
+
+/// `ECHO message`: returns `message`.
+pub(crate) fn echo(message: Vec<u8>) -> RedisResult<Value> {
+    Ok(Value::String(message))
+}
+
+/// `SELECT index`: switches the handle to the database at `index`.
+pub(crate) fn select(storage: &mut StorageEngine, index: i64) -> RedisResult<Value> {
+    let selected = usize::try_from(index).is_ok_and(|index| storage.select(index));
+    if !selected {
+        return Err(db_index_out_of_range());
+    }
+    Ok(Value::Okay)
+}
+
+/// `SWAPDB index1 index2`: exchanges the contents of two databases.
+pub(crate) fn swapdb(storage: &StorageEngine, a: i64, b: i64) -> RedisResult<Value> {
+    let (Ok(a), Ok(b)) = (usize::try_from(a), usize::try_from(b)) else {
+        return Err(db_index_out_of_range());
+    };
+    if !storage.swap_db(a, b) {
+        return Err(db_index_out_of_range());
+    }
+    Ok(Value::Okay)
+}
+
+pub(crate) fn db_index_out_of_range() -> RedisError {
+    RedisError::InvalidArgument("DB index is out of range".to_string())
+}
+
+/// `DBSIZE`: returns the number of keys in the selected database.
+pub(crate) fn dbsize(storage: &StorageEngine) -> RedisResult<Value> {
+    Ok(Value::Int(storage.len() as i64))
+}
+
+/// `FLUSHDB`: removes every key in the selected database.
+pub(crate) fn flushdb(storage: &StorageEngine) -> RedisResult<Value> {
+    storage.flush();
+    Ok(Value::Okay)
+}
+
+/// `FLUSHALL`: removes every key in every database.
+pub(crate) fn flushall(storage: &StorageEngine) -> RedisResult<Value> {
+    storage.flush_all();
+    Ok(Value::Okay)
+}
+
+/// `TIME`: returns the current Unix time as `[seconds, microseconds]`.
+pub(crate) fn time(storage: &StorageEngine) -> RedisResult<Value> {
+    let now = storage
+        .system_time()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok(Value::Array(vec![
+        Value::String(now.as_secs().to_string().into_bytes()),
+        Value::String(now.subsec_micros().to_string().into_bytes()),
+    ]))
+}
+
+/// `LASTSAVE`: returns the Unix time of the last save.
+///
+/// Nothing is ever saved, so this is the time the engine was created.
+pub(crate) fn lastsave(storage: &StorageEngine) -> RedisResult<Value> {
+    Ok(Value::Int(storage.created_at() as i64))
+}
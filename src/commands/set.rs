@@ -0,0 +1,106 @@
+//! Set command handlers.
+
+use rand::seq::IteratorRandom;
+
+use crate::error::{RedisError, RedisResult};
+use crate::storage::{RedisData, StorageEngine};
+use crate::types::Value;
+
+/// `SADD key member [member ...]`: returns the number of members added.
+pub(crate) fn sadd(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    members: impl IntoIterator<Item = Vec<u8>>,
+) -> RedisResult<Value> {
+    storage.upsert(
+        key,
+        || storage.new_set(),
+        |data| match data {
+            RedisData::Set(s) => {
+                let mut added = 0;
+                for member in members {
+                    if s.insert(member) {
+                        added += 1;
+                    }
+                }
+                Ok(Value::Int(added))
+            }
+            _ => Err(RedisError::WrongType),
+        },
+    )
+}
+
+/// `SREM key member [member ...]`: returns the number of members removed.
+pub(crate) fn srem(storage: &StorageEngine, key: &[u8], members: &[Vec<u8>]) -> RedisResult<Value> {
+    storage
+        .write(key, |data| match data {
+            RedisData::Set(s) => {
+                let removed = members.iter().filter(|m| s.remove(*m)).count();
+                Ok(Value::Int(removed as i64))
+            }
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `SMEMBERS key`: returns every member of the set.
+pub(crate) fn smembers(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    storage
+        .read(key, |data| match data {
+            RedisData::Set(s) => {
+                let members: Vec<Value> = s.iter().map(|m| Value::String(m.clone())).collect();
+                Ok(Value::Array(members))
+            }
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Array(Vec::new())))
+}
+
+/// `SISMEMBER key member`: returns `1` if `member` is in the set, `0` otherwise.
+pub(crate) fn sismember(storage: &StorageEngine, key: &[u8], member: &[u8]) -> RedisResult<Value> {
+    storage
+        .read(key, |data| match data {
+            RedisData::Set(s) => Ok(Value::Int(s.contains(member) as i64)),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `SCARD key`: returns the number of members in the set.
+pub(crate) fn scard(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    storage
+        .read(key, |data| match data {
+            RedisData::Set(s) => Ok(Value::Int(s.len() as i64)),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `SPOP key [count]`: removes and returns random members.
+///
+/// Without `count` the reply is a single member (or null); with `count` it is
+/// an array of up to `count` members.
+pub(crate) fn spop(
+    storage: &StorageEngine,
+    key: &[u8],
+    count: Option<usize>,
+) -> RedisResult<Value> {
+    let popped = storage.write(key, |data| {
+        let RedisData::Set(s) = data else {
+            return Err(RedisError::WrongType);
+        };
+        let picked: Vec<Vec<u8>> = s
+            .iter()
+            .cloned()
+            .choose_multiple(&mut rand::thread_rng(), count.unwrap_or(1));
+        for member in &picked {
+            s.remove(member);
+        }
+        Ok(picked)
+    });
+    let picked = popped.transpose()?.unwrap_or_default();
+    Ok(match count {
+        Some(_) => Value::Array(picked.into_iter().map(Value::String).collect()),
+        None => picked.into_iter().next().map_or(Value::Null, Value::String),
+    })
+}
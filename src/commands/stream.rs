@@ -0,0 +1,88 @@
+//! Stream command handlers.
+
+use crate::error::{RedisError, RedisResult};
+use crate::storage::{StorageEngine, StreamEntry};
+use crate::types::Value;
+
+/// `XADD key id field value [field value ...]`: appends an entry and returns
+/// its ID. An `id` of `*` is generated from the current time.
+pub(crate) fn xadd(
+    storage: &StorageEngine,
+    key: &[u8],
+    id: &[u8],
+    fields: Vec<(Vec<u8>, Vec<u8>)>,
+) -> RedisResult<Value> {
+    let id = (id != b"*").then_some(id);
+    storage
+        .xadd(key, id, fields)
+        .map(Value::String)
+        .ok_or(RedisError::WrongType)
+}
+
+/// `XLEN key`: returns the number of entries in the stream.
+pub(crate) fn xlen(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    Ok(Value::Int(storage.xlen(key).unwrap_or(0) as i64))
+}
+
+/// `XTRIM key MAXLEN [~|=] threshold`: returns the number of entries removed.
+pub(crate) fn xtrim(
+    storage: &StorageEngine,
+    key: &[u8],
+    maxlen: usize,
+    approximate: bool,
+) -> RedisResult<Value> {
+    let removed = storage.xtrim(key, maxlen, approximate).unwrap_or(0);
+    Ok(Value::Int(removed as i64))
+}
+
+/// `XDEL key id [id ...]`: returns the number of entries deleted.
+pub(crate) fn xdel(storage: &StorageEngine, key: &[u8], ids: &[Vec<u8>]) -> RedisResult<Value> {
+    let ids = ids.iter().map(Vec::as_slice).collect();
+    Ok(Value::Int(storage.xdel(key, ids).unwrap_or(0) as i64))
+}
+
+/// `XRANGE key start end [COUNT count]`: returns the entries from `start` to
+/// `end`, oldest first.
+pub(crate) fn xrange(
+    storage: &StorageEngine,
+    key: &[u8],
+    start: &[u8],
+    end: &[u8],
+    count: Option<usize>,
+) -> RedisResult<Value> {
+    Ok(entries_reply(
+        storage.xrange(key, start, end, count).unwrap_or_default(),
+    ))
+}
+
+/// `XREVRANGE key end start [COUNT count]`: returns the entries from `end`
+/// down to `start`, newest first.
+pub(crate) fn xrevrange(
+    storage: &StorageEngine,
+    key: &[u8],
+    end: &[u8],
+    start: &[u8],
+    count: Option<usize>,
+) -> RedisResult<Value> {
+    Ok(entries_reply(
+        storage
+            .xrevrange(key, start, end, count)
+            .unwrap_or_default(),
+    ))
+}
+
+/// Replies with each entry as its ID followed by its fields and values.
+fn entries_reply(entries: Vec<StreamEntry>) -> Value {
+    let entries = entries
+        .into_iter()
+        .map(|(id, fields)| {
+            let mut entry = vec![Value::String(id)];
+            for (field, value) in fields {
+                entry.push(Value::String(field));
+                entry.push(Value::String(value));
+            }
+            Value::Array(entry)
+        })
+        .collect();
+    Value::Array(entries)
+}
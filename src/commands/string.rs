@@ -0,0 +1,569 @@
+//! String command handlers.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use super::key::invalid_expire_time;
+use super::{format_float, ExistenceCheck, Expiry, SetExpiry, SetOptions};
+use crate::error::{RedisError, RedisResult};
+use crate::storage::{RedisData, StorageEngine, StoredValue, Update};
+use crate::types::Value;
+
+/// `GET key`: returns the string stored at `key`, or null if it is missing.
+pub(crate) fn get(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    storage
+        .read(key, |data| match data.as_string() {
+            Some(s) => Ok(Value::String(s.into_owned())),
+            None => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Null))
+}
+
+/// `SET key value [NX | XX] [GET] [EX | PX | EXAT | PXAT | KEEPTTL]`:
+/// stores `value` at `key`.
+///
+/// Returns `OK`, or null if the `NX`/`XX` condition failed. With `GET`,
+/// returns the previous value instead, or null if there was none.
+pub(crate) fn set(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    value: Vec<u8>,
+    options: SetOptions,
+) -> RedisResult<Value> {
+    set_generic(storage, key, value, options, "set")
+}
+
+/// `MSET key value [key value ...]`: sets every key at once, clearing any
+/// time to live.
+pub(crate) fn mset(storage: &StorageEngine, items: Vec<(Vec<u8>, Vec<u8>)>) -> RedisResult<Value> {
+    storage.set_many(
+        items
+            .into_iter()
+            .map(|(key, value)| (key, RedisData::String(value))),
+    );
+    Ok(Value::Okay)
+}
+
+/// `MSETNX key value [key value ...]`: like `MSET`, but sets nothing if any
+/// of the keys exists.
+///
+/// Returns `1` if the keys were set, `0` otherwise.
+pub(crate) fn msetnx(
+    storage: &StorageEngine,
+    items: Vec<(Vec<u8>, Vec<u8>)>,
+) -> RedisResult<Value> {
+    let set = storage.set_many_if_vacant(
+        items
+            .into_iter()
+            .map(|(key, value)| (key, RedisData::String(value))),
+    );
+    Ok(Value::Int(set as i64))
+}
+
+/// `MGET key [key ...]`: returns the string at each key, or null for keys
+/// that are missing or hold another type.
+pub(crate) fn mget(storage: &StorageEngine, keys: &[Vec<u8>]) -> RedisResult<Value> {
+    let values = storage.read_many(keys, |data| data.as_string().map(Cow::into_owned));
+    Ok(Value::Array(
+        values
+            .into_iter()
+            .map(|value| value.flatten().map_or(Value::Null, Value::String))
+            .collect(),
+    ))
+}
+
+/// `SETNX key value`: sets `key` only if it does not exist.
+///
+/// Returns `1` if the key was set, `0` otherwise.
+pub(crate) fn setnx(storage: &StorageEngine, key: Vec<u8>, value: Vec<u8>) -> RedisResult<Value> {
+    let options = SetOptions::default().conditional_set(ExistenceCheck::NX);
+    let reply = set_generic(storage, key, value, options, "setnx")?;
+    Ok(Value::Int((reply == Value::Okay) as i64))
+}
+
+/// `SETEX key seconds value`: sets `key` with a time to live in seconds.
+pub(crate) fn setex(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    seconds: i64,
+    value: Vec<u8>,
+) -> RedisResult<Value> {
+    let seconds = u64::try_from(seconds).map_err(|_| invalid_expire_time("setex"))?;
+    let options = SetOptions::default().with_expiration(SetExpiry::EX(seconds));
+    set_generic(storage, key, value, options, "setex")
+}
+
+/// `PSETEX key milliseconds value`: sets `key` with a time to live in
+/// milliseconds.
+pub(crate) fn psetex(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    ms: i64,
+    value: Vec<u8>,
+) -> RedisResult<Value> {
+    let ms = u64::try_from(ms).map_err(|_| invalid_expire_time("psetex"))?;
+    let options = SetOptions::default().with_expiration(SetExpiry::PX(ms));
+    set_generic(storage, key, value, options, "psetex")
+}
+
+/// `GETSET key value`: sets `key` and returns its previous value, or null.
+pub(crate) fn getset(storage: &StorageEngine, key: Vec<u8>, value: Vec<u8>) -> RedisResult<Value> {
+    let options = SetOptions::default().get(true);
+    set_generic(storage, key, value, options, "getset")
+}
+
+/// `GETDEL key`: deletes `key` and returns its value, or null if it is
+/// missing.
+pub(crate) fn getdel(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    storage.update(key, |current| {
+        match current.map(|stored| stored.data.as_string()) {
+            None => (Update::Keep, Ok(Value::Null)),
+            Some(Some(s)) => (Update::Delete, Ok(Value::String(s.into_owned()))),
+            Some(None) => (Update::Keep, Err(RedisError::WrongType)),
+        }
+    })
+}
+
+/// `GETEX key [EX | PX | EXAT | PXAT | PERSIST]`: returns the value of
+/// `key`, or null, and optionally changes its time to live.
+pub(crate) fn getex(
+    storage: &StorageEngine,
+    key: &[u8],
+    expiry: Option<Expiry>,
+) -> RedisResult<Value> {
+    let Some(expiry) = expiry else {
+        return get(storage, key);
+    };
+    let expire_at = deadline(storage, expiry, "getex")?;
+    let now = storage.now_ms();
+    storage.update(key, |current| {
+        let Some(stored) = current else {
+            return (Update::Keep, Ok(Value::Null));
+        };
+        let Some(s) = stored.data.as_string() else {
+            return (Update::Keep, Err(RedisError::WrongType));
+        };
+        let reply = Value::String(s.into_owned());
+        let update = match expire_at {
+            Some(at) if at <= now => Update::Delete,
+            _ => Update::Set(StoredValue {
+                data: Arc::clone(&stored.data),
+                expire_at,
+            }),
+        };
+        (update, Ok(reply))
+    })
+}
+
+/// `INCRBY key increment`: adds `delta` to the integer stored at `key`,
+/// starting from `0` if it is missing, and returns the result.
+///
+/// `INCR`, `DECR` and `DECRBY` are built on this. The key keeps its time to
+/// live, and the result is stored as [`RedisData::Int`].
+pub(crate) fn incrby(storage: &StorageEngine, key: Vec<u8>, delta: i64) -> RedisResult<Value> {
+    storage.upsert(
+        key,
+        || RedisData::Int(0),
+        |data| {
+            let current = match data {
+                RedisData::Int(n) => *n,
+                RedisData::String(s) => parse_integer(s).ok_or_else(not_an_integer)?,
+                _ => return Err(RedisError::WrongType),
+            };
+            let value = current.checked_add(delta).ok_or_else(|| {
+                RedisError::InvalidArgument("increment or decrement would overflow".to_string())
+            })?;
+            *data = RedisData::Int(value);
+            Ok(Value::Int(value))
+        },
+    )
+}
+
+/// `DECRBY key decrement`: subtracts `delta` from the integer stored at
+/// `key`.
+pub(crate) fn decrby(storage: &StorageEngine, key: Vec<u8>, delta: i64) -> RedisResult<Value> {
+    let delta = delta
+        .checked_neg()
+        .ok_or_else(|| RedisError::InvalidArgument("decrement would overflow".to_string()))?;
+    incrby(storage, key, delta)
+}
+
+/// `INCRBYFLOAT key increment`: adds `delta` to the number stored at `key`,
+/// starting from `0` if it is missing, and returns the result as a string.
+pub(crate) fn incrbyfloat(storage: &StorageEngine, key: Vec<u8>, delta: f64) -> RedisResult<Value> {
+    if !delta.is_finite() {
+        return Err(nan_or_infinity());
+    }
+    storage.upsert(
+        key,
+        || RedisData::Int(0),
+        |data| {
+            let current = match data {
+                RedisData::Int(n) => *n as f64,
+                RedisData::String(s) => std::str::from_utf8(s)
+                    .ok()
+                    .and_then(|s| s.parse::<f64>().ok())
+                    .filter(|f| f.is_finite())
+                    .ok_or_else(|| {
+                        RedisError::InvalidArgument("value is not a valid float".to_string())
+                    })?,
+                _ => return Err(RedisError::WrongType),
+            };
+            let value = current + delta;
+            if !value.is_finite() {
+                return Err(nan_or_infinity());
+            }
+            let value = format_float(value);
+            *data = RedisData::String(value.clone());
+            Ok(Value::String(value))
+        },
+    )
+}
+
+/// `APPEND key value`: appends `value` to the string at `key`, creating it
+/// if it is missing, and returns the new length.
+pub(crate) fn append(storage: &StorageEngine, key: Vec<u8>, value: &[u8]) -> RedisResult<Value> {
+    check_string_len(value.len())?;
+    storage.upsert(
+        key,
+        || RedisData::String(Vec::new()),
+        |data| {
+            let s = data.as_string_mut().ok_or(RedisError::WrongType)?;
+            check_string_len(s.len() + value.len())?;
+            s.extend_from_slice(value);
+            Ok(Value::Int(s.len() as i64))
+        },
+    )
+}
+
+/// `GETRANGE key start end`: returns the bytes of the string at `key`
+/// between `start` and `end`, both inclusive.
+///
+/// Negative offsets count from the end of the string, and the range is
+/// clamped to it. A missing key reads as an empty string.
+pub(crate) fn getrange(
+    storage: &StorageEngine,
+    key: &[u8],
+    start: i64,
+    end: i64,
+) -> RedisResult<Value> {
+    storage
+        .read(key, |data| {
+            let s = data.as_string().ok_or(RedisError::WrongType)?;
+            let range = clamp_range(s.len(), start, end).map_or(&[][..], |r| &s[r]);
+            Ok(Value::String(range.to_vec()))
+        })
+        .unwrap_or(Ok(Value::String(Vec::new())))
+}
+
+/// `SETRANGE key offset value`: overwrites the string at `key` with `value`
+/// starting at `offset`, and returns the new length.
+///
+/// The string is padded with zero bytes if it is shorter than `offset`. An
+/// empty `value` changes nothing and does not create the key.
+pub(crate) fn setrange(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    offset: i64,
+    value: &[u8],
+) -> RedisResult<Value> {
+    let offset = usize::try_from(offset)
+        .map_err(|_| RedisError::InvalidArgument("offset is out of range".to_string()))?;
+    if value.is_empty() {
+        return strlen(storage, &key);
+    }
+    let end = offset.saturating_add(value.len());
+    check_string_len(end)?;
+    storage.upsert(
+        key,
+        || RedisData::String(Vec::new()),
+        |data| {
+            let s = data.as_string_mut().ok_or(RedisError::WrongType)?;
+            if s.len() < end {
+                s.resize(end, 0);
+            }
+            s[offset..end].copy_from_slice(value);
+            Ok(Value::Int(s.len() as i64))
+        },
+    )
+}
+
+/// `STRLEN key`: returns the length of the string at `key`, or `0` if it is
+/// missing.
+pub(crate) fn strlen(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    storage
+        .read(key, |data| {
+            let s = data.as_string().ok_or(RedisError::WrongType)?;
+            Ok(Value::Int(s.len() as i64))
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// The reply `LCS` gives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LcsReply {
+    /// The longest common subsequence itself.
+    String,
+    /// Only its length (`LEN`).
+    Len,
+    /// The ranges that make it up, from the end of the strings back (`IDX`).
+    Idx {
+        min_match_len: usize,
+        with_match_len: bool,
+    },
+}
+
+/// `LCS key1 key2 [LEN] [IDX] [MINMATCHLEN len] [WITHMATCHLEN]`: finds the
+/// longest common subsequence of the strings at `key1` and `key2`.
+///
+/// Missing keys read as empty strings. With `IDX`, replies like Redis over
+/// RESP2: `["matches", [[[start1, end1], [start2, end2], len?], ...], "len",
+/// n]`, where each match is a contiguous run of the subsequence.
+pub(crate) fn lcs(
+    storage: &StorageEngine,
+    key1: &[u8],
+    key2: &[u8],
+    reply: LcsReply,
+) -> RedisResult<Value> {
+    let read = |key| {
+        storage
+            .read(key, |data| data.as_string().map(Cow::into_owned))
+            .unwrap_or(Some(Vec::new()))
+            .ok_or(RedisError::WrongType)
+    };
+    let (a, b) = (read(key1)?, read(key2)?);
+    check_lcs_table(a.len(), b.len())?;
+
+    // table[i * (b.len() + 1) + j] is the LCS length of a[..i] and b[..j].
+    let width = b.len() + 1;
+    let mut table = vec![0u32; (a.len() + 1) * width];
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            table[i * width + j] = if a[i - 1] == b[j - 1] {
+                table[(i - 1) * width + j - 1] + 1
+            } else {
+                table[(i - 1) * width + j].max(table[i * width + j - 1])
+            };
+        }
+    }
+    let len = table[a.len() * width + b.len()] as usize;
+
+    if reply == LcsReply::Len {
+        return Ok(Value::Int(len as i64));
+    }
+
+    // Walk back from the end, collecting the subsequence and its runs.
+    let mut subsequence = vec![0u8; len];
+    let mut matches = Vec::new();
+    let mut run: Option<(usize, usize, usize)> = None;
+    let (mut i, mut j, mut k) = (a.len(), b.len(), len);
+    let mut emit = |run: (usize, usize, usize)| {
+        let (a_start, b_start, run_len) = run;
+        if let LcsReply::Idx {
+            min_match_len,
+            with_match_len,
+        } = reply
+        {
+            if run_len >= min_match_len {
+                let range = |start: usize| {
+                    Value::Array(vec![
+                        Value::Int(start as i64),
+                        Value::Int((start + run_len - 1) as i64),
+                    ])
+                };
+                let mut entry = vec![range(a_start), range(b_start)];
+                if with_match_len {
+                    entry.push(Value::Int(run_len as i64));
+                }
+                matches.push(Value::Array(entry));
+            }
+        }
+    };
+    while i > 0 && j > 0 {
+        if a[i - 1] == b[j - 1] {
+            i -= 1;
+            j -= 1;
+            k -= 1;
+            subsequence[k] = a[i];
+            run = match run {
+                Some((a_start, b_start, run_len)) if a_start == i + 1 && b_start == j + 1 => {
+                    Some((i, j, run_len + 1))
+                }
+                Some(previous) => {
+                    emit(previous);
+                    Some((i, j, 1))
+                }
+                None => Some((i, j, 1)),
+            };
+        } else {
+            if table[(i - 1) * width + j] > table[i * width + j - 1] {
+                i -= 1;
+            } else {
+                j -= 1;
+            }
+            if let Some(previous) = run.take() {
+                emit(previous);
+            }
+        }
+    }
+    if let Some(previous) = run {
+        emit(previous);
+    }
+
+    match reply {
+        LcsReply::Idx { .. } => Ok(Value::Array(vec![
+            Value::String(b"matches".to_vec()),
+            Value::Array(matches),
+            Value::String(b"len".to_vec()),
+            Value::Int(len as i64),
+        ])),
+        _ => Ok(Value::String(subsequence)),
+    }
+}
+
+/// Redis's default `proto-max-bulk-len`: the longest string a command may
+/// build.
+const MAX_STRING_LEN: usize = 512 * 1024 * 1024;
+
+fn check_string_len(len: usize) -> RedisResult<()> {
+    if len > MAX_STRING_LEN {
+        return Err(RedisError::InvalidArgument(
+            "string exceeds maximum allowed size (proto-max-bulk-len)".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Refuses an `LCS` whose table for strings of `a_len` and `b_len` bytes
+/// would not fit in `MAX_STRING_LEN`, as Redis does, instead of trying to
+/// allocate it.
+fn check_lcs_table(a_len: usize, b_len: usize) -> RedisResult<()> {
+    if a_len >= u32::MAX as usize - 1 || b_len >= u32::MAX as usize - 1 {
+        return Err(RedisError::InvalidArgument(
+            "String too long for LCS".to_string(),
+        ));
+    }
+    let table_size = (a_len + 1)
+        .checked_mul(b_len + 1)
+        .and_then(|cells| cells.checked_mul(std::mem::size_of::<u32>()));
+    if table_size.is_none_or(|size| size > MAX_STRING_LEN) {
+        return Err(RedisError::InvalidArgument(
+            "Insufficient memory, transient memory for LCS exceeds proto-max-bulk-len".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Resolves the inclusive `start`/`end` offsets of `GETRANGE` against a
+/// string of `len` bytes, or `None` if the range is empty.
+///
+/// `BITCOUNT` and `BITPOS` resolve their ranges the same way.
+pub(crate) fn clamp_range(len: usize, start: i64, end: i64) -> Option<std::ops::Range<usize>> {
+    if start < 0 && end < 0 && start > end {
+        return None;
+    }
+    let len = len as i64;
+    let start = if start < 0 { len + start } else { start }.max(0);
+    let end = if end < 0 { len + end } else { end }.max(0).min(len - 1);
+    (len > 0 && start <= end).then(|| start as usize..end as usize + 1)
+}
+
+/// Parses a stored string as an integer, accepting only the canonical form
+/// Redis accepts: no sign other than `-`, no leading zeros or whitespace.
+pub(crate) fn parse_integer(s: &[u8]) -> Option<i64> {
+    std::str::from_utf8(s)
+        .ok()
+        .and_then(|text| text.parse::<i64>().ok())
+        .filter(|n| n.to_string().as_bytes() == s)
+}
+
+pub(super) fn nan_or_infinity() -> RedisError {
+    RedisError::InvalidArgument("increment would produce NaN or Infinity".to_string())
+}
+
+fn not_an_integer() -> RedisError {
+    RedisError::InvalidArgument("value is not an integer or out of range".to_string())
+}
+
+fn set_generic(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    value: Vec<u8>,
+    options: SetOptions,
+    command: &str,
+) -> RedisResult<Value> {
+    if options == SetOptions::default() {
+        storage.set(key, RedisData::String(value), None);
+        return Ok(Value::Okay);
+    }
+    let expiry = match options.expiration {
+        Some(SetExpiry::EX(secs)) => Some(Expiry::EX(secs)),
+        Some(SetExpiry::PX(ms)) => Some(Expiry::PX(ms)),
+        Some(SetExpiry::EXAT(secs)) => Some(Expiry::EXAT(secs)),
+        Some(SetExpiry::PXAT(ms)) => Some(Expiry::PXAT(ms)),
+        Some(SetExpiry::KEEPTTL) | None => None,
+    };
+    let expire_at = match expiry {
+        Some(expiry) => deadline(storage, expiry, command)?,
+        None => None,
+    };
+    let now = storage.now_ms();
+    storage.update(key, |current| {
+        let previous = match current.map(|stored| stored.data.as_string()) {
+            Some(Some(s)) if options.get => Value::String(s.into_owned()),
+            Some(None) if options.get => return (Update::Keep, Err(RedisError::WrongType)),
+            _ => Value::Null,
+        };
+        let allowed = match options.conditional_set {
+            None => true,
+            Some(ExistenceCheck::NX) => current.is_none(),
+            Some(ExistenceCheck::XX) => current.is_some(),
+        };
+        let reply = match (options.get, allowed) {
+            (true, _) => previous,
+            (false, true) => Value::Okay,
+            (false, false) => Value::Null,
+        };
+        if !allowed {
+            return (Update::Keep, Ok(reply));
+        }
+        let expire_at = match options.expiration {
+            Some(SetExpiry::KEEPTTL) => current.and_then(|stored| stored.expire_at),
+            _ => expire_at,
+        };
+        let update = match expire_at {
+            // A deadline in the past still replaces the old value.
+            Some(at) if at <= now => Update::Delete,
+            _ => Update::Set(StoredValue {
+                data: Arc::new(RedisData::String(value)),
+                expire_at,
+            }),
+        };
+        (update, Ok(reply))
+    })
+}
+
+/// Turns `expiry` into a deadline in Unix-epoch milliseconds, or `None` for
+/// `PERSIST`.
+///
+/// Like Redis, rejects times that are not positive or that overflow.
+pub(crate) fn deadline(
+    storage: &StorageEngine,
+    expiry: Expiry,
+    command: &str,
+) -> RedisResult<Option<u64>> {
+    let (amount, scale, relative) = match expiry {
+        Expiry::EX(secs) => (secs, 1000, true),
+        Expiry::PX(ms) => (ms, 1, true),
+        Expiry::EXAT(secs) => (secs, 1000, false),
+        Expiry::PXAT(ms) => (ms, 1, false),
+        Expiry::PERSIST => return Ok(None),
+    };
+    let offset = if relative { storage.now_ms() } else { 0 };
+    amount
+        .checked_mul(scale)
+        .and_then(|ms| ms.checked_add(offset))
+        .filter(|&at| amount > 0 && at <= i64::MAX as u64)
+        .map(Some)
+        .ok_or_else(|| invalid_expire_time(command))
+}
@@ -0,0 +1,190 @@
+//! Sorted set command handlers.
+
+use std::collections::BTreeMap;
+
+use crate::error::{RedisError, RedisResult};
+use crate::storage::{RedisData, StorageEngine};
+use crate::types::Value;
+
+use super::normalize_range;
+
+/// One end of a score range, as accepted by `ZRANGEBYSCORE` and `ZCOUNT`.
+///
+/// A leading `(` makes the bound exclusive; `-inf` and `+inf` are accepted.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScoreBound {
+    value: f64,
+    exclusive: bool,
+}
+
+impl ScoreBound {
+    pub(crate) fn parse(arg: &[u8]) -> RedisResult<Self> {
+        let (exclusive, raw) = match arg.strip_prefix(b"(") {
+            Some(rest) => (true, rest),
+            None => (false, arg),
+        };
+        let value = std::str::from_utf8(raw)
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|f| !f.is_nan())
+            .ok_or_else(|| RedisError::InvalidArgument("min or max is not a float".to_string()))?;
+        Ok(Self { value, exclusive })
+    }
+
+    fn admits_above(&self, score: f64) -> bool {
+        if self.exclusive {
+            score > self.value
+        } else {
+            score >= self.value
+        }
+    }
+
+    fn admits_below(&self, score: f64) -> bool {
+        if self.exclusive {
+            score < self.value
+        } else {
+            score <= self.value
+        }
+    }
+}
+
+/// Returns the members of a sorted set ordered by score, then by member.
+fn sorted(z: &BTreeMap<Vec<u8>, f64>) -> Vec<(&Vec<u8>, f64)> {
+    let mut items: Vec<(&Vec<u8>, f64)> = z.iter().map(|(m, s)| (m, *s)).collect();
+    items.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(b.0)));
+    items
+}
+
+fn format_score(score: f64) -> Value {
+    Value::String(score.to_string().into_bytes())
+}
+
+fn reply(items: Vec<(&Vec<u8>, f64)>, withscores: bool) -> Value {
+    let mut res = Vec::with_capacity(if withscores {
+        items.len() * 2
+    } else {
+        items.len()
+    });
+    for (member, score) in items {
+        res.push(Value::String(member.clone()));
+        if withscores {
+            res.push(format_score(score));
+        }
+    }
+    Value::Array(res)
+}
+
+/// Runs `f` against the sorted set at `key`, or returns `missing` if the key
+/// does not exist.
+fn with_zset<T>(
+    storage: &StorageEngine,
+    key: &[u8],
+    missing: T,
+    f: impl FnOnce(&BTreeMap<Vec<u8>, f64>) -> T,
+) -> RedisResult<T> {
+    storage
+        .read(key, |data| match data {
+            RedisData::ZSet(z) => Ok(f(z)),
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(missing))
+}
+
+/// `ZADD key score member [score member ...]`: returns the number of new members.
+pub(crate) fn zadd(
+    storage: &StorageEngine,
+    key: Vec<u8>,
+    members: Vec<(f64, Vec<u8>)>,
+) -> RedisResult<Value> {
+    storage.upsert(
+        key,
+        || RedisData::ZSet(BTreeMap::new()),
+        |data| match data {
+            RedisData::ZSet(z) => {
+                let mut added = 0;
+                for (score, member) in members {
+                    if z.insert(member, score).is_none() {
+                        added += 1;
+                    }
+                }
+                Ok(Value::Int(added))
+            }
+            _ => Err(RedisError::WrongType),
+        },
+    )
+}
+
+/// `ZREM key member [member ...]`: returns the number of members removed.
+pub(crate) fn zrem(storage: &StorageEngine, key: &[u8], members: &[Vec<u8>]) -> RedisResult<Value> {
+    storage
+        .write(key, |data| match data {
+            RedisData::ZSet(z) => {
+                let removed = members.iter().filter(|m| z.remove(*m).is_some()).count();
+                Ok(Value::Int(removed as i64))
+            }
+            _ => Err(RedisError::WrongType),
+        })
+        .unwrap_or(Ok(Value::Int(0)))
+}
+
+/// `ZRANGE key start stop [WITHSCORES]`: returns members by rank.
+pub(crate) fn zrange(
+    storage: &StorageEngine,
+    key: &[u8],
+    start: i64,
+    stop: i64,
+    withscores: bool,
+) -> RedisResult<Value> {
+    with_zset(storage, key, Value::Array(Vec::new()), |z| {
+        let items = sorted(z);
+        match normalize_range(start, stop, items.len()) {
+            Some((from, to)) => reply(items[from..=to].to_vec(), withscores),
+            None => Value::Array(Vec::new()),
+        }
+    })
+}
+
+/// `ZRANGEBYSCORE key min max [WITHSCORES]`: returns members within a score range.
+pub(crate) fn zrangebyscore(
+    storage: &StorageEngine,
+    key: &[u8],
+    min: ScoreBound,
+    max: ScoreBound,
+    withscores: bool,
+) -> RedisResult<Value> {
+    with_zset(storage, key, Value::Array(Vec::new()), |z| {
+        let items = sorted(z)
+            .into_iter()
+            .filter(|(_, s)| min.admits_above(*s) && max.admits_below(*s))
+            .collect();
+        reply(items, withscores)
+    })
+}
+
+/// `ZCARD key`: returns the number of members in the sorted set.
+pub(crate) fn zcard(storage: &StorageEngine, key: &[u8]) -> RedisResult<Value> {
+    with_zset(storage, key, Value::Int(0), |z| Value::Int(z.len() as i64))
+}
+
+/// `ZSCORE key member`: returns the score of `member`, or null if it is missing.
+pub(crate) fn zscore(storage: &StorageEngine, key: &[u8], member: &[u8]) -> RedisResult<Value> {
+    with_zset(storage, key, Value::Null, |z| {
+        z.get(member).map_or(Value::Null, |s| format_score(*s))
+    })
+}
+
+/// `ZCOUNT key min max`: returns the number of members within a score range.
+pub(crate) fn zcount(
+    storage: &StorageEngine,
+    key: &[u8],
+    min: ScoreBound,
+    max: ScoreBound,
+) -> RedisResult<Value> {
+    with_zset(storage, key, Value::Int(0), |z| {
+        let count = z
+            .values()
+            .filter(|s| min.admits_above(**s) && max.admits_below(**s))
+            .count();
+        Value::Int(count as i64)
+    })
+}
@@ -5,6 +5,7 @@ use thiserror::Error;
 /// This enum represents the various errors that can occur when
 /// interacting with the Redis-like store.
 #[derive(Error, Debug)]
+#[allow(missing_docs)]
 pub enum RedisError {
     #[error("Cannot parse value")]
     ParseError,
@@ -18,11 +19,9 @@ pub enum RedisError {
     CommandNotFound(String),
     #[error("Invalid argument: {0}")]
     InvalidArgument(String),
-    #[error("IO error: {0}")]
-    IoError(String),
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
 
-/// A specialized Result type for Redis operations.
+/// A specialized `Result` type for Redis operations.
 pub type RedisResult<T> = Result<T, RedisError>;
@@ -1,35 +1,42 @@
 use not_redis::Client;
 
-#[tokio::main]
-async fn main() {
+fn main() {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(run());
+}
+
+async fn run() {
     let mut client = Client::new();
     client.start().await;
 
     // String operations
-    client.set("key", "value").await.unwrap();
+    let _: () = client.set("key", "value").await.unwrap();
     let val: String = client.get("key").await.unwrap();
     println!("key = {}", val);
 
     // Hash operations
-    client.hset("user:1", "name", "Alice").await.unwrap();
-    client.hset("user:1", "age", "30").await.unwrap();
+    let _: () = client.hset("user:1", "name", "Alice").await.unwrap();
+    let _: () = client.hset("user:1", "age", "30").await.unwrap();
     let name: String = client.hget("user:1", "name").await.unwrap();
     println!("user:1 name = {}", name);
 
     // TTL operations
-    client.set("temp", "data").await.unwrap();
+    let _: () = client.set("temp", "data").await.unwrap();
     client.expire("temp", 60).await.unwrap();
-    let ttl: i64 = client.ttl("temp").await.unwrap();
+    let ttl = client.ttl("temp").await.unwrap().raw();
     println!("temp TTL = {} seconds", ttl);
 
     // List operations
-    client.lpush("mylist", "first").await.unwrap();
-    client.rpush("mylist", "second").await.unwrap();
+    let _: () = client.lpush("mylist", "first").await.unwrap();
+    let _: () = client.rpush("mylist", "second").await.unwrap();
     let len: i64 = client.llen("mylist").await.unwrap();
     println!("mylist len = {}", len);
 
     // Set operations
-    client.sadd("myset", "member1").await.unwrap();
+    let _: () = client.sadd("myset", "member1").await.unwrap();
     let members: Vec<String> = client.smembers("myset").await.unwrap();
     println!("myset members = {:?}", members);
 
@@ -0,0 +1,228 @@
+//! Executors that drive background work, such as the expiration sweeper.
+//!
+//! The storage engine never spawns tasks directly. It hands a periodic task to
+//! an [`Executor`], so the crate works with Tokio, with another async runtime,
+//! or with plain threads:
+//!
+//! - [`ThreadExecutor`] runs each task on a dedicated OS thread.
+//! - [`TokioExecutor`] runs each task on the current Tokio runtime. It requires
+//!   the `tokio` cargo feature, which is enabled by default.
+//!
+//! Other runtimes can be supported by implementing [`Executor`]:
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use not_redis::runtime::{Executor, PeriodicTask};
+//!
+//! struct MyExecutor;
+//!
+//! impl Executor for MyExecutor {
+//!     fn spawn_periodic(&self, interval: Duration, mut task: PeriodicTask) {
+//!         std::thread::spawn(move || loop {
+//!             std::thread::sleep(interval);
+//!             if task().is_break() {
+//!                 break;
+//!             }
+//!         });
+//!     }
+//! }
+//! ```
+//!
+//! Starting the sweeper returns a [`SweeperHandle`] that stops it. The
+//! sweeper also stops on its own once every clone of its
+//! [`StorageEngine`](crate::StorageEngine) has been dropped.
+
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// A unit of background work that an [`Executor`] runs on every tick.
+///
+/// The task returns [`ControlFlow::Break`] when it has finished for good;
+/// the executor must then stop calling it and drop it.
+pub type PeriodicTask = Box<dyn FnMut() -> ControlFlow<()> + Send + 'static>;
+
+/// Runs periodic background tasks.
+pub trait Executor {
+    /// Runs `task` every `interval`, starting one `interval` from now, until
+    /// it returns [`ControlFlow::Break`].
+    ///
+    /// The task must run off the caller's thread; this method returns
+    /// immediately.
+    fn spawn_periodic(&self, interval: Duration, task: PeriodicTask);
+}
+
+/// A handle to a running expiration sweeper.
+///
+/// Dropping the handle does not stop the sweeper; it keeps running until
+/// [`shutdown`](Self::shutdown) is called or the last clone of its
+/// [`StorageEngine`](crate::StorageEngine) is dropped.
+#[derive(Debug, Clone)]
+pub struct SweeperHandle {
+    state: Arc<SweeperState>,
+}
+
+#[derive(Debug, Default)]
+struct SweeperState {
+    stop: AtomicBool,
+    finished: Mutex<Completion>,
+    finished_cond: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct Completion {
+    finished: bool,
+    wakers: Vec<Waker>,
+}
+
+impl SweeperHandle {
+    /// Wraps `tick` into a [`PeriodicTask`] controlled by the returned handle.
+    ///
+    /// The task stops once the handle asks it to or `tick` returns
+    /// [`ControlFlow::Break`]. It counts as finished when the executor drops
+    /// it, which also covers executors that shut down without finishing.
+    pub(crate) fn wrap(
+        mut tick: impl FnMut() -> ControlFlow<()> + Send + 'static,
+    ) -> (Self, PeriodicTask) {
+        let state = Arc::new(SweeperState::default());
+        let guard = FinishGuard(Arc::clone(&state));
+        let task = Box::new(move || {
+            let state = &guard.0;
+            if state.stop.load(Ordering::Acquire) {
+                return ControlFlow::Break(());
+            }
+            tick()
+        });
+        (Self { state }, task)
+    }
+
+    /// Asks the sweeper to stop without waiting for it.
+    ///
+    /// The sweeper notices on its next tick.
+    pub fn stop(&self) {
+        self.state.stop.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` once the sweeper has exited.
+    pub fn is_finished(&self) -> bool {
+        self.state.finished.lock().unwrap().finished
+    }
+
+    /// Stops the sweeper and waits for it to exit.
+    ///
+    /// The sweeper notices on its next tick, so this takes up to one sweep
+    /// interval.
+    pub async fn shutdown(self) {
+        self.stop();
+        Finished(&self.state).await
+    }
+
+    /// Stops the sweeper and blocks the current thread until it exits.
+    ///
+    /// Do not call this from the thread that drives the sweeper, such as a
+    /// single-threaded Tokio runtime; use [`shutdown`](Self::shutdown)
+    /// there instead.
+    pub fn shutdown_blocking(self) {
+        self.stop();
+        let mut completion = self.state.finished.lock().unwrap();
+        while !completion.finished {
+            completion = self.state.finished_cond.wait(completion).unwrap();
+        }
+    }
+}
+
+/// Marks the sweeper finished when the task that owns it is dropped.
+struct FinishGuard(Arc<SweeperState>);
+
+impl Drop for FinishGuard {
+    fn drop(&mut self) {
+        let wakers = {
+            let mut completion = self.0.finished.lock().unwrap();
+            completion.finished = true;
+            std::mem::take(&mut completion.wakers)
+        };
+        self.0.finished_cond.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// Resolves once the sweeper has finished.
+struct Finished<'a>(&'a SweeperState);
+
+impl Future for Finished<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut completion = self.0.finished.lock().unwrap();
+        if completion.finished {
+            return Poll::Ready(());
+        }
+        if !completion.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            completion.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// An [`Executor`] that runs each task on its own OS thread.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadExecutor;
+
+impl Executor for ThreadExecutor {
+    fn spawn_periodic(&self, interval: Duration, mut task: PeriodicTask) {
+        std::thread::Builder::new()
+            .name("not_redis-sweeper".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(interval);
+                if task().is_break() {
+                    break;
+                }
+            })
+            .expect("failed to spawn sweeper thread");
+    }
+}
+
+/// An [`Executor`] that runs each task on the current Tokio runtime.
+///
+/// # Panics
+///
+/// [`spawn_periodic`](Executor::spawn_periodic) panics if called outside a
+/// Tokio runtime.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioExecutor;
+
+#[cfg(feature = "tokio")]
+impl Executor for TokioExecutor {
+    fn spawn_periodic(&self, interval: Duration, mut task: PeriodicTask) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            // The first tick completes immediately.
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if task().is_break() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// The executor used by [`Client::start`](crate::Client::start):
+/// [`TokioExecutor`] when the `tokio` feature is enabled, otherwise
+/// [`ThreadExecutor`].
+#[cfg(feature = "tokio")]
+pub type DefaultExecutor = TokioExecutor;
+
+/// The executor used by [`Client::start`](crate::Client::start):
+/// [`TokioExecutor`] when the `tokio` feature is enabled, otherwise
+/// [`ThreadExecutor`].
+#[cfg(not(feature = "tokio"))]
+pub type DefaultExecutor = ThreadExecutor;
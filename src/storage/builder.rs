@@ -0,0 +1,155 @@
+//! Builder for configuring a [`StorageEngine`].
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::engine::StorageEngine;
+use super::expire::{DEFAULT_EFFORT, MAX_EFFORT, MIN_EFFORT};
+use super::hasher::HasherKind;
+use crate::clock::{Clock, SystemClock};
+
+/// Builds a [`StorageEngine`] with non-default settings.
+///
+/// Every setting has a default matching [`StorageEngine::new`], so only the
+/// ones that matter need to be set:
+///
+/// ```rust
+/// use std::time::Duration;
+/// use not_redis::StorageEngine;
+///
+/// let storage = StorageEngine::builder()
+///     .shards(64)
+///     .initial_capacity(1_000_000)
+///     .sweep_interval(Duration::from_millis(50))
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct StorageEngineBuilder {
+    pub(super) databases: usize,
+    pub(super) shards: usize,
+    pub(super) initial_capacity: usize,
+    pub(super) sweep_interval: Duration,
+    pub(super) active_expire_effort: u8,
+    pub(super) collection_capacity: CollectionCapacity,
+    pub(super) hasher: HasherKind,
+    pub(super) clock: Arc<dyn Clock>,
+}
+
+/// Capacity reserved for collections created by a write.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CollectionCapacity {
+    pub(crate) hash: usize,
+    pub(crate) set: usize,
+    pub(crate) list: usize,
+}
+
+impl Default for StorageEngineBuilder {
+    fn default() -> Self {
+        Self {
+            databases: 16,
+            shards: 2,
+            initial_capacity: 0,
+            sweep_interval: Duration::from_millis(100),
+            active_expire_effort: DEFAULT_EFFORT,
+            collection_capacity: CollectionCapacity {
+                hash: 200,
+                set: 0,
+                list: 0,
+            },
+            hasher: HasherKind::default(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl StorageEngineBuilder {
+    /// Creates a builder with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of databases. Defaults to 16, as in Redis.
+    ///
+    /// Databases are numbered from 0 and chosen with
+    /// [`StorageEngine::select`]. There is always at least one.
+    pub fn databases(mut self, count: usize) -> Self {
+        self.databases = count.max(1);
+        self
+    }
+
+    /// Sets the number of shards each database's keyspace is split into.
+    /// Defaults to 2.
+    ///
+    /// Each shard has its own lock, so more shards means less contention
+    /// between threads writing different keys. A good value is a few times
+    /// the number of threads using the engine. The count is rounded up to a
+    /// power of two, and is at least 2.
+    pub fn shards(mut self, shards: usize) -> Self {
+        self.shards = shards.max(2).next_power_of_two();
+        self
+    }
+
+    /// Reserves room for `capacity` keys in database 0 up front. Defaults
+    /// to 0.
+    pub fn initial_capacity(mut self, capacity: usize) -> Self {
+        self.initial_capacity = capacity;
+        self
+    }
+
+    /// Sets how often the background sweeper runs. Defaults to 100ms.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn sweep_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "sweep interval must be non-zero");
+        self.sweep_interval = interval;
+        self
+    }
+
+    /// Sets how hard the background sweeper works, from 1 (the default) to
+    /// 10. See [`StorageEngine::with_active_expire_effort`].
+    pub fn active_expire_effort(mut self, effort: u8) -> Self {
+        self.active_expire_effort = effort.clamp(MIN_EFFORT, MAX_EFFORT);
+        self
+    }
+
+    /// Sets how many fields to reserve when a write creates a hash.
+    /// Defaults to 200.
+    pub fn hash_capacity(mut self, capacity: usize) -> Self {
+        self.collection_capacity.hash = capacity;
+        self
+    }
+
+    /// Sets how many members to reserve when a write creates a set.
+    /// Defaults to 0.
+    pub fn set_capacity(mut self, capacity: usize) -> Self {
+        self.collection_capacity.set = capacity;
+        self
+    }
+
+    /// Sets how many elements to reserve when a write creates a list.
+    /// Defaults to 0.
+    pub fn list_capacity(mut self, capacity: usize) -> Self {
+        self.collection_capacity.list = capacity;
+        self
+    }
+
+    /// Sets the hash function for keys. Defaults to [`HasherKind::Fx`].
+    pub fn hasher(mut self, hasher: HasherKind) -> Self {
+        self.hasher = hasher;
+        self
+    }
+
+    /// Sets the clock used for deadlines, TTLs and stream IDs. Defaults to
+    /// [`SystemClock`].
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Creates the storage engine.
+    pub fn build(self) -> StorageEngine {
+        StorageEngine::from_builder(self)
+    }
+}
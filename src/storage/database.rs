@@ -0,0 +1,554 @@
+//! Numbered databases: one keyspace and its expiration index each.
+
+use dashmap::mapref::entry::Entry;
+use dashmap::mapref::multiple::RefMulti;
+use dashmap::DashMap;
+use rustc_hash::FxHasher;
+use std::hash::Hasher;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+
+use super::expire::ExpirationManager;
+use super::hasher::KeyHasher;
+use super::types::{RedisData, StoredValue};
+
+/// The numbered databases of an engine.
+///
+/// Each slot holds its database behind an `Arc`, so `SWAPDB` can exchange
+/// two databases, data and deadlines together, by swapping the pointers.
+/// Operations clone the `Arc` and release the slot lock before touching
+/// the data, so a swap never waits on a command.
+pub(super) struct Databases {
+    slots: Box<[RwLock<Arc<Database>>]>,
+    /// The database the next sweep cycle starts from.
+    pub(super) sweep_cursor: AtomicUsize,
+}
+
+impl Databases {
+    pub(super) fn new(slots: impl IntoIterator<Item = Database>) -> Self {
+        Self {
+            slots: slots
+                .into_iter()
+                .map(|db| RwLock::new(Arc::new(db)))
+                .collect(),
+            sweep_cursor: AtomicUsize::new(0),
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the database at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub(super) fn get(&self, index: usize) -> Arc<Database> {
+        Arc::clone(&self.slots[index].read().unwrap())
+    }
+
+    /// Exchanges the databases at `a` and `b`.
+    pub(super) fn swap(&self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        // Lock in index order so two concurrent swaps cannot deadlock.
+        let (low, high) = (a.min(b), a.max(b));
+        let mut low = self.slots[low].write().unwrap();
+        let mut high = self.slots[high].write().unwrap();
+        std::mem::swap(&mut *low, &mut *high);
+    }
+}
+
+/// What [`Database::update`] does with a key once its callback returns.
+pub(crate) enum Update {
+    /// Leave the key as it is.
+    Keep,
+    /// Store a new value and deadline, replacing any previous ones.
+    Set(StoredValue),
+    /// Remove the key.
+    Delete,
+}
+
+/// A single database: a keyspace, the deadlines of its volatile keys, and
+/// the hashes with volatile fields.
+///
+/// Methods that check expiry take the current time, in Unix-epoch
+/// milliseconds, from the engine's clock.
+pub(super) struct Database {
+    pub(super) data: DashMap<Vec<u8>, StoredValue, KeyHasher>,
+    pub(super) expiration: ExpirationManager,
+    /// Hashes with field TTLs, under their earliest field deadline.
+    pub(super) field_expiration: ExpirationManager,
+    pub(super) high_water_mark: AtomicUsize,
+    pub(super) current_len: AtomicUsize,
+    /// Held by commands that touch several keys, exclusively by those that
+    /// write, so they never see part of each other's work even when the keys
+    /// live in different shards. Single-key commands never take it.
+    pub(super) batch: RwLock<()>,
+}
+
+impl Database {
+    pub(super) fn new(capacity: usize, hasher: KeyHasher, shards: usize) -> Self {
+        Self {
+            data: DashMap::with_capacity_and_hasher_and_shard_amount(capacity, hasher, shards),
+            expiration: ExpirationManager::default(),
+            field_expiration: ExpirationManager::default(),
+            high_water_mark: AtomicUsize::new(0),
+            current_len: AtomicUsize::new(0),
+            batch: RwLock::new(()),
+        }
+    }
+
+    /// Stores `value` at `key`, replacing any previous value and deadline.
+    pub(super) fn insert(&self, key: Vec<u8>, value: StoredValue) {
+        // The deadlines are rescheduled under the shard lock, so a concurrent
+        // write to the key cannot leave the index out of step with the value.
+        match self.data.entry(key) {
+            Entry::Occupied(mut entry) => {
+                // Check if the old entry had an expiration without cloning
+                if entry.get().expire_at.is_some() {
+                    self.expiration.cancel(entry.key());
+                }
+                self.schedule(entry.key(), &value);
+                entry.insert(value);
+            }
+            Entry::Vacant(entry) => {
+                self.schedule(entry.key(), &value);
+                entry.insert(value);
+                // Increment current length counter for new key
+                self.current_len.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        // Update high-water mark (cheap atomic load)
+        let current_len = self.current_len.load(Ordering::Relaxed);
+        self.high_water_mark
+            .fetch_max(current_len, Ordering::Relaxed);
+    }
+
+    /// Stores `value` at `key` only if the key is missing or has expired.
+    ///
+    /// Hands `value` back if a live key is in the way.
+    pub(super) fn insert_if_vacant(
+        &self,
+        key: Vec<u8>,
+        value: StoredValue,
+        now: u64,
+    ) -> Result<(), StoredValue> {
+        match self.data.entry(key) {
+            Entry::Occupied(entry) if !entry.get().is_gone_at(now) => return Err(value),
+            Entry::Occupied(mut entry) => {
+                self.expiration.cancel(entry.key());
+                self.schedule(entry.key(), &value);
+                entry.insert(value);
+            }
+            Entry::Vacant(entry) => {
+                self.schedule(entry.key(), &value);
+                entry.insert(value);
+                let current_len = self.current_len.fetch_add(1, Ordering::Relaxed) + 1;
+                self.high_water_mark
+                    .fetch_max(current_len, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Schedules the key deadline and the earliest field deadline of `value`,
+    /// about to be stored at `key`.
+    fn schedule(&self, key: &[u8], value: &StoredValue) {
+        if let Some(at) = value.expire_at {
+            self.expiration.schedule(key.to_vec(), at);
+        }
+        if let Some(at) = next_field_deadline(&value.data) {
+            self.field_expiration.schedule(key.to_vec(), at);
+        }
+    }
+
+    /// Runs `f` on the live value at `key`, or `None`, and applies the
+    /// [`Update`] it returns, all under the key's shard lock.
+    pub(super) fn update<R>(
+        &self,
+        key: Vec<u8>,
+        now: u64,
+        f: impl FnOnce(Option<&StoredValue>) -> (Update, R),
+    ) -> R {
+        let (removed, result) = match self.data.entry(key) {
+            Entry::Occupied(mut entry) => {
+                let live = !entry.get().is_gone_at(now);
+                let (update, result) = f(live.then(|| entry.get()));
+                let removed = match update {
+                    Update::Keep if live => false,
+                    Update::Set(value) => {
+                        if entry.get().expire_at.is_some() {
+                            self.expiration.cancel(entry.key());
+                        }
+                        self.schedule(entry.key(), &value);
+                        entry.insert(value);
+                        false
+                    }
+                    // Deleting, or leaving an expired key in place.
+                    Update::Keep | Update::Delete => {
+                        if entry.get().expire_at.is_some() {
+                            self.expiration.cancel(entry.key());
+                        }
+                        entry.remove();
+                        true
+                    }
+                };
+                (removed, result)
+            }
+            Entry::Vacant(entry) => {
+                let (update, result) = f(None);
+                if let Update::Set(value) = update {
+                    self.schedule(entry.key(), &value);
+                    entry.insert(value);
+                    let current_len = self.current_len.fetch_add(1, Ordering::Relaxed) + 1;
+                    self.high_water_mark
+                        .fetch_max(current_len, Ordering::Relaxed);
+                }
+                (false, result)
+            }
+        };
+        if removed {
+            // Only now is the shard lock released; compacting locks every shard.
+            self.current_len.fetch_sub(1, Ordering::Relaxed);
+            self.maybe_compact();
+        }
+        result
+    }
+
+    /// Runs `f` on the data at `key` for modification, first storing
+    /// `init()` there if the key is missing or has expired.
+    ///
+    /// A collection that `f` leaves empty is removed, and a missing key is
+    /// only created if `f` succeeds and leaves something in it.
+    pub(super) fn upsert<R, E>(
+        &self,
+        key: Vec<u8>,
+        now: u64,
+        init: impl FnOnce() -> RedisData,
+        f: impl FnOnce(&mut RedisData) -> Result<R, E>,
+    ) -> Result<R, E> {
+        let result = match self.data.entry(key) {
+            Entry::Occupied(mut entry) => {
+                let created = entry.get().is_gone_at(now);
+                if created {
+                    if entry.get().expire_at.is_some() {
+                        self.expiration.cancel(entry.key());
+                    }
+                    entry.insert(StoredValue {
+                        data: Arc::new(init()),
+                        expire_at: None,
+                    });
+                }
+                let result = f(Arc::make_mut(&mut entry.get_mut().data));
+                let failed = created && result.is_err();
+                if !failed && !entry.get().data.is_empty_collection(now) {
+                    return result;
+                }
+                if entry.get().expire_at.is_some() {
+                    self.expiration.cancel(entry.key());
+                }
+                entry.remove();
+                result
+            }
+            Entry::Vacant(entry) => {
+                let mut data = init();
+                let result = f(&mut data);
+                if result.is_err() || data.is_empty_collection(now) {
+                    return result;
+                }
+                entry.insert(StoredValue {
+                    data: Arc::new(data),
+                    expire_at: None,
+                });
+                let current_len = self.current_len.fetch_add(1, Ordering::Relaxed) + 1;
+                self.high_water_mark
+                    .fetch_max(current_len, Ordering::Relaxed);
+                return result;
+            }
+        };
+        // Only now is the shard lock released; compacting locks every shard.
+        self.current_len.fetch_sub(1, Ordering::Relaxed);
+        self.maybe_compact();
+        result
+    }
+
+    /// Runs `f` on the value at `key`, or returns `None` if the key is
+    /// missing or has expired.
+    pub(super) fn lookup<R>(
+        &self,
+        key: &[u8],
+        now: u64,
+        f: impl FnOnce(&StoredValue) -> R,
+    ) -> Option<R> {
+        {
+            let stored = self.data.get(key)?;
+            if !stored.is_gone_at(now) {
+                return Some(f(&stored));
+            }
+        }
+        // The Ref is dropped above; removing while holding it would deadlock.
+        self.remove_expired(key, now);
+        None
+    }
+
+    /// Like [`lookup`](Self::lookup), for modification. A collection that
+    /// `f` leaves empty is removed.
+    pub(super) fn lookup_mut<R>(
+        &self,
+        key: &[u8],
+        now: u64,
+        f: impl FnOnce(&mut StoredValue) -> R,
+    ) -> Option<R> {
+        let mut expired = false;
+        let mut result = None;
+        // Runs `f` under the shard lock, removing the key in the same step
+        // if `f` leaves it an empty collection.
+        let removed = self.data.remove_if_mut(key, |key, stored| {
+            if stored.is_gone_at(now) {
+                expired = true;
+                return false;
+            }
+            result = Some(f(stored));
+            let empty = stored.data.is_empty_collection(now);
+            if empty && stored.expire_at.is_some() {
+                self.expiration.cancel(key);
+            }
+            empty
+        });
+        if removed.is_some() {
+            self.current_len.fetch_sub(1, Ordering::Relaxed);
+            self.maybe_compact();
+        } else if expired {
+            self.remove_expired(key, now);
+        }
+        result
+    }
+
+    /// Removes `key` if, and only if, it is still expired.
+    ///
+    /// Another thread may have replaced the value since it was seen expired,
+    /// so the check is repeated under the shard lock, and the deadline is
+    /// cancelled before the lock is released so that a new one scheduled by
+    /// a concurrent write is kept.
+    pub(super) fn remove_expired(&self, key: &[u8], now: u64) -> bool {
+        let removed = self
+            .data
+            .remove_if(key, |key, stored| {
+                let expired = stored.is_gone_at(now);
+                if expired && stored.expire_at.is_some() {
+                    self.expiration.cancel(key);
+                }
+                expired
+            })
+            .is_some();
+        if removed {
+            self.current_len.fetch_sub(1, Ordering::Relaxed);
+            self.maybe_compact();
+        }
+        removed
+    }
+
+    /// Removes `key` and returns its value, or `None` if it was missing or
+    /// had expired. Also cancels any scheduled expiration for the key.
+    pub(super) fn take(&self, key: &[u8], now: u64) -> Option<StoredValue> {
+        let (_, stored) = self.data.remove_if(key, |key, stored| {
+            if stored.expire_at.is_some() {
+                self.expiration.cancel(key);
+            }
+            true
+        })?;
+        self.current_len.fetch_sub(1, Ordering::Relaxed);
+        self.maybe_compact();
+        (!stored.is_gone_at(now)).then_some(stored)
+    }
+
+    /// Pops up to `limit` of the earliest key deadlines and `limit` of the
+    /// earliest field deadlines that have passed, and removes the keys and
+    /// hash fields that are still expired.
+    ///
+    /// Returns how many keys or hashes had something removed and how many
+    /// deadlines were looked at.
+    pub(super) fn expire_due(&self, now: u64, limit: usize) -> (usize, usize) {
+        // The expiration lock is only held while a batch is popped, so
+        // writers scheduling new deadlines never wait on a whole cycle.
+        let (due, sampled) = self.expiration.pop_due(now, limit);
+        // A key may have been given a later deadline since it was scheduled.
+        let expired = due
+            .iter()
+            .filter(|key| self.remove_expired(key, now))
+            .count();
+        let (due, fields_sampled) = self.field_expiration.pop_due(now, limit);
+        let fields_expired = due
+            .into_iter()
+            .map(|key| self.remove_expired_fields(key, now))
+            .filter(|&removed| removed)
+            .count();
+        (expired + fields_expired, sampled + fields_sampled)
+    }
+
+    /// Removes the expired fields of the hash at `key`, deleting the hash if
+    /// none are left, and reschedules it at its next field deadline.
+    ///
+    /// Returns `true` if any field was removed.
+    fn remove_expired_fields(&self, key: Vec<u8>, now: u64) -> bool {
+        let mut removed_fields = false;
+        let mut next = None;
+        let removed_key = self.data.remove_if_mut(&key, |key, stored| {
+            let RedisData::Hash(hash) = &*stored.data else {
+                return false;
+            };
+            // An expired key is left to its own deadline.
+            if stored.is_expired_at(now) {
+                return false;
+            }
+            if hash.next_deadline().is_some_and(|at| at <= now) {
+                if let RedisData::Hash(hash) = Arc::make_mut(&mut stored.data) {
+                    removed_fields = hash.remove_expired(now) > 0;
+                }
+            }
+            let RedisData::Hash(hash) = &*stored.data else {
+                return false;
+            };
+            next = hash.next_deadline();
+            let empty = hash.is_empty();
+            if empty && stored.expire_at.is_some() {
+                self.expiration.cancel(key);
+            }
+            empty
+        });
+        if removed_key.is_some() {
+            self.current_len.fetch_sub(1, Ordering::Relaxed);
+            self.maybe_compact();
+        } else if let Some(at) = next {
+            self.field_expiration.schedule_earliest(key, at);
+        }
+        removed_fields
+    }
+
+    /// Returns the number of live keys starting with `prefix`.
+    pub(super) fn len(&self, prefix: &[u8], now: u64) -> usize {
+        self.data
+            .iter()
+            .filter(|entry| entry.key().starts_with(prefix) && !entry.is_gone_at(now))
+            .count()
+    }
+
+    /// Returns `true` if no live key starts with `prefix`.
+    pub(super) fn is_empty(&self, prefix: &[u8], now: u64) -> bool {
+        self.data
+            .iter()
+            .all(|entry| !entry.key().starts_with(prefix) || entry.is_gone_at(now))
+    }
+
+    /// Returns every live key starting with `prefix`.
+    pub(super) fn keys(&self, prefix: &[u8], now: u64) -> Vec<Vec<u8>> {
+        self.data
+            .iter()
+            .filter(|entry| entry.key().starts_with(prefix) && !entry.is_gone_at(now))
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    /// Returns about `count` live keys starting with `prefix` from `cursor`
+    /// on, and the cursor to continue from, or `0` once there are no more.
+    ///
+    /// Keys are visited in the order of [`scan_position`], so a key that
+    /// stays in the database for a whole scan is returned exactly once, no
+    /// matter what else is written meanwhile. Keys sharing a position come in
+    /// the same batch, which may then hold more than `count` keys.
+    pub(super) fn scan(
+        &self,
+        prefix: &[u8],
+        now: u64,
+        cursor: u64,
+        count: usize,
+    ) -> (u64, Vec<Vec<u8>>) {
+        let live = |entry: &RefMulti<'_, Vec<u8>, StoredValue>| {
+            entry.key().starts_with(prefix) && !entry.is_gone_at(now)
+        };
+        let mut positions: Vec<u64> = self
+            .data
+            .iter()
+            .filter(live)
+            .map(|entry| scan_position(entry.key()))
+            .filter(|&position| position >= cursor)
+            .collect();
+        let (last, next) = if positions.len() > count {
+            let (_, &mut last, rest) = positions.select_nth_unstable(count.max(1) - 1);
+            let more = rest.iter().any(|&position| position > last);
+            (last, if more { last + 1 } else { 0 })
+        } else {
+            (u64::MAX, 0)
+        };
+        let keys = self
+            .data
+            .iter()
+            .filter(live)
+            .filter(|entry| (cursor..=last).contains(&scan_position(entry.key())))
+            .map(|entry| entry.key().clone())
+            .collect();
+        (next, keys)
+    }
+
+    /// Removes every key starting with `prefix`, or every key if `prefix`
+    /// is empty.
+    pub(super) fn flush(&self, prefix: &[u8]) {
+        if prefix.is_empty() {
+            self.data.clear();
+            self.expiration.clear();
+            self.field_expiration.clear();
+            self.high_water_mark.store(0, Ordering::Relaxed);
+            self.current_len.store(0, Ordering::Relaxed);
+            return;
+        }
+        self.data.retain(|key, stored| {
+            if !key.starts_with(prefix) {
+                return true;
+            }
+            if stored.expire_at.is_some() {
+                self.expiration.cancel(key);
+            }
+            self.current_len.fetch_sub(1, Ordering::Relaxed);
+            false
+        });
+        self.maybe_compact();
+    }
+
+    pub(super) fn compact(&self) {
+        self.data.shrink_to_fit();
+        self.high_water_mark
+            .store(self.current_len.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    fn maybe_compact(&self) {
+        let hwm = self.high_water_mark.load(Ordering::Relaxed);
+        if hwm == 0 {
+            return;
+        }
+        let current_len = self.current_len.load(Ordering::Relaxed);
+        if current_len * 4 < hwm {
+            self.compact();
+        }
+    }
+}
+
+/// Returns the earliest field deadline of a hash, so whole-value writes such
+/// as `RENAME` keep its fields swept.
+fn next_field_deadline(data: &RedisData) -> Option<u64> {
+    match data {
+        RedisData::Hash(hash) => hash.next_deadline(),
+        _ => None,
+    }
+}
+
+/// Returns where `key` comes in a `SCAN`: a hash of it, from `1` up to
+/// below `2^62`, so cursors stay positive and fit in an `i64`.
+fn scan_position(key: &[u8]) -> u64 {
+    let mut hasher = FxHasher::default();
+    hasher.write(key);
+    (hasher.finish() >> 2).max(1)
+}
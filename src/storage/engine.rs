@@ -1,206 +1,821 @@
 //! The core storage engine implementation.
 
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::borrow::Cow;
 use std::collections::VecDeque;
-use std::sync::Arc;
+use std::ops::ControlFlow;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use dashmap::DashMap;
 
-use super::config::{MaxMemoryPolicy, StorageConfig};
-use super::memory::MemoryTracker;
-use super::types::{RedisData, StoredValue};
-use super::expire::ExpirationManager;
+use super::builder::{CollectionCapacity, StorageEngineBuilder};
+use super::database::{Database, Databases, Update};
+use super::expire::{SweepConfig, MAX_EFFORT, MIN_EFFORT};
+use super::hasher::KeyHasher;
+use super::types::{ExpireOption, HashValue, RedisData, StoredValue, StreamEntry};
+use crate::clock::{unix_millis, Clock};
+use crate::runtime::{DefaultExecutor, Executor, SweeperHandle};
 
 /// The core storage engine for the Redis-like store.
-/// 
-/// Uses a concurrent hash map ([`DashMap`]) for thread-safe access
-/// and supports key expiration with a background sweeper task.
+///
+/// Uses a concurrent hash map ([`DashMap`](dashmap::DashMap)) for
+/// thread-safe access and supports key expiration with a background sweeper
+/// task.
+///
+/// Keys are binary-safe byte strings. Methods that look keys up accept
+/// anything that is `AsRef<[u8]>`, so `&str`, `String` and `Vec<u8>` all work.
+///
+/// An engine holds a fixed number of numbered databases (16 by default),
+/// each with its own keyspace. Like a Redis connection, every handle has a
+/// selected database, initially 0, that its methods work on. Clones start
+/// with the same selection but change it independently; see
+/// [`select`](Self::select).
+///
+/// A handle can also be confined to the keys under a prefix; see
+/// [`namespace`](Self::namespace).
 #[derive(Clone)]
 pub struct StorageEngine {
-    data: Arc<DashMap<String, StoredValue>>,
-    expiration: ExpirationManager,
-    memory: MemoryTracker,
+    databases: Arc<Databases>,
+    db: usize,
+    namespace: Arc<[u8]>,
+    sweep: SweepConfig,
+    collection_capacity: CollectionCapacity,
+    clock: Arc<dyn Clock>,
+    created_at: SystemTime,
 }
 
+/// A reference to a [`StorageEngine`] that does not keep its data alive.
+///
+/// The background sweeper holds one of these so that dropping the last
+/// engine frees the data and ends the sweeper.
+struct WeakStorageEngine {
+    databases: Weak<Databases>,
+    db: usize,
+    namespace: Arc<[u8]>,
+    sweep: SweepConfig,
+    collection_capacity: CollectionCapacity,
+    clock: Arc<dyn Clock>,
+    created_at: SystemTime,
+}
+
+impl WeakStorageEngine {
+    /// Returns the engine if any clone of it is still alive.
+    fn upgrade(&self) -> Option<StorageEngine> {
+        Some(StorageEngine {
+            databases: self.databases.upgrade()?,
+            db: self.db,
+            namespace: Arc::clone(&self.namespace),
+            sweep: self.sweep,
+            collection_capacity: self.collection_capacity,
+            clock: Arc::clone(&self.clock),
+            created_at: self.created_at,
+        })
+    }
+}
+
+#[allow(missing_docs)]
 impl StorageEngine {
+    /// Creates a new storage engine with the default settings.
+    ///
+    /// Use [`builder`](Self::builder) to change the shard count, capacity,
+    /// sweep interval and other settings.
     pub fn new() -> Self {
-        Self::new_with_sweep_interval(100)
+        StorageEngineBuilder::default().build()
     }
 
-    pub fn new_with_sweep_interval(sweep_interval_ms: u64) -> Self {
-        let engine = Self {
-            data: Arc::new(DashMap::new()),
-            expiration: ExpirationManager::new(sweep_interval_ms),
-            memory: MemoryTracker::new(),
-        };
-        engine
+    /// Returns a builder for a storage engine with non-default settings.
+    pub fn builder() -> StorageEngineBuilder {
+        StorageEngineBuilder::default()
     }
 
-    pub fn new(sweep_interval_ms: u64) -> Self {
-        Self::new_with_sweep_interval(sweep_interval_ms)
+    pub(super) fn from_builder(builder: StorageEngineBuilder) -> Self {
+        let hasher = KeyHasher::from(builder.hasher);
+        // Only database 0 gets the initial capacity; the others are usually
+        // small or empty.
+        let databases = (0..builder.databases).map(|index| {
+            let capacity = if index == 0 {
+                builder.initial_capacity
+            } else {
+                0
+            };
+            Database::new(capacity, hasher.clone(), builder.shards)
+        });
+        Self {
+            databases: Arc::new(Databases::new(databases)),
+            db: 0,
+            namespace: Arc::from(&[][..]),
+            sweep: SweepConfig {
+                sweep_interval: builder.sweep_interval,
+                effort: builder.active_expire_effort,
+            },
+            collection_capacity: builder.collection_capacity,
+            created_at: builder.clock.system_time(),
+            clock: builder.clock,
+        }
     }
 
-    pub fn with_config(config: StorageConfig) -> Self {
-        let mut engine = Self {
-            data: Arc::new(DashMap::new()),
-            expiration: ExpirationManager::new(100),
-            memory: MemoryTracker::new(),
-        };
-        
-        if let Some(maxmemory) = config.maxmemory {
-            let rt = tokio::runtime::Handle::current();
-            rt.block_on(engine.memory.set_maxmemory(Some(maxmemory)));
-        }
-        
-        let rt = tokio::runtime::Handle::current();
-        rt.block_on(engine.memory.set_maxmemory_policy(config.maxmemory_policy));
-        
-        engine
+    /// Sets the clock used for deadlines, TTLs and stream IDs.
+    ///
+    /// Defaults to [`SystemClock`](crate::clock::SystemClock). Install a
+    /// [`TestClock`](crate::clock::TestClock) to control time in tests.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.created_at = clock.system_time();
+        self.clock = Arc::new(clock);
+        self
     }
 
-    pub async fn start_expiration_sweeper(&self) {
-        let expiration = self.expiration.clone();
-        let data = Arc::clone(&self.data);
-        
-        tokio::spawn(async move {
-            expiration.start_sweep_task(move |key| {
-                data.remove(key);
-            }).await;
-        });
+    /// Returns the current time according to this engine's clock, in
+    /// milliseconds since the Unix epoch.
+    pub(crate) fn now_ms(&self) -> u64 {
+        unix_millis(self.clock.system_time())
+    }
+
+    /// Returns the current wall-clock time according to this engine's clock.
+    pub(crate) fn system_time(&self) -> SystemTime {
+        self.clock.system_time()
+    }
+
+    /// Returns an empty hash with the configured capacity reserved.
+    pub(crate) fn new_hash(&self) -> RedisData {
+        let capacity = self.collection_capacity.hash;
+        RedisData::Hash(HashValue::with_capacity(capacity))
     }
 
-    pub fn set(&self, key: &str, value: RedisData, expire_at: Option<Instant>) {
-        let rt = tokio::runtime::Handle::current();
-        
-        let memory_enabled = rt.block_on(self.memory.is_enabled());
-        
-        if memory_enabled && rt.block_on(self.memory.should_reject_write()) {
-            return;
+    /// Returns an empty set with the configured capacity reserved.
+    pub(crate) fn new_set(&self) -> RedisData {
+        let capacity = self.collection_capacity.set;
+        RedisData::Set(FxHashSet::with_capacity_and_hasher(
+            capacity,
+            Default::default(),
+        ))
+    }
+
+    /// Returns an empty list with the configured capacity reserved.
+    pub(crate) fn new_list(&self) -> RedisData {
+        RedisData::List(VecDeque::with_capacity(self.collection_capacity.list))
+    }
+
+    /// Sets how often the background sweeper runs. Defaults to 100ms.
+    ///
+    /// Only sweepers started after this call use the new interval. Each
+    /// cycle may spend a share of the interval removing expired keys; see
+    /// [`with_active_expire_effort`](Self::with_active_expire_effort).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn with_sweep_interval(mut self, interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "sweep interval must be non-zero");
+        self.sweep.sweep_interval = interval;
+        self
+    }
+
+    /// Sets how hard the background sweeper works to remove expired keys,
+    /// from 1 (the default) to 10. Values outside that range are clamped.
+    ///
+    /// Like Redis's `active-expire-effort`, a higher effort looks at more
+    /// keys per loop, keeps looping while fewer of them had expired, and lets
+    /// each cycle use a larger share of the sweep interval. Expired keys are
+    /// freed sooner at the cost of more CPU time.
+    pub fn with_active_expire_effort(mut self, effort: u8) -> Self {
+        self.sweep.effort = effort.clamp(MIN_EFFORT, MAX_EFFORT);
+        self
+    }
+
+    /// Returns the number of databases in this engine.
+    pub fn db_count(&self) -> usize {
+        self.databases.len()
+    }
+
+    /// Returns the index of the database this handle works on.
+    pub fn selected_db(&self) -> usize {
+        self.db
+    }
+
+    /// Switches this handle to the database at `index`, like `SELECT`.
+    ///
+    /// Only this handle is affected: other clones of the engine keep their
+    /// selection. Returns `false`, leaving the selection unchanged, if
+    /// `index` is out of range.
+    pub fn select(&mut self, index: usize) -> bool {
+        if index >= self.db_count() {
+            return false;
         }
+        self.db = index;
+        true
+    }
 
-        let old_value = self.data.get(key).cloned();
-        
-        let stored = StoredValue {
-            data: value,
-            expire_at,
+    /// Returns a handle to the same engine with the database at `index`
+    /// selected, or `None` if `index` is out of range.
+    pub(crate) fn at_db(&self, index: usize) -> Option<StorageEngine> {
+        let mut engine = self.clone();
+        engine.select(index).then_some(engine)
+    }
+
+    /// Exchanges the contents of the databases at `a` and `b`, like `SWAPDB`.
+    ///
+    /// Every handle sees the swap: a handle with `a` selected now sees the
+    /// keys that were in `b`. Deadlines move with their keys. Whole databases
+    /// are swapped, even on a [namespaced](Self::namespace) handle. Returns
+    /// `false` if either index is out of range.
+    pub fn swap_db(&self, a: usize, b: usize) -> bool {
+        if a >= self.db_count() || b >= self.db_count() {
+            return false;
+        }
+        self.databases.swap(a, b);
+        true
+    }
+
+    /// Moves `key`, with its timeout, from the selected database to the
+    /// database at `db`, like `MOVE`.
+    ///
+    /// Returns `true` if the key was moved, `false` if it does not exist in
+    /// the selected database, already exists in `db`, or `db` is the
+    /// selected database or out of range.
+    pub fn move_key(&self, key: impl AsRef<[u8]>, db: usize) -> bool {
+        let key = self.full_key(key.as_ref());
+        let key = key.as_ref();
+        if db == self.db || db >= self.db_count() {
+            return false;
+        }
+        let now = self.now_ms();
+        let target = self.databases.get(db);
+        if target.lookup(key, now, |_| ()).is_some() {
+            return false;
+        }
+        let source = self.db();
+        let Some(stored) = source.take(key, now) else {
+            return false;
         };
+        match target.insert_if_vacant(key.to_vec(), stored, now) {
+            Ok(()) => true,
+            Err(stored) => {
+                // The key was created in the target since it was checked. Put
+                // the value back, unless a newer write has taken its place.
+                let _ = source.insert_if_vacant(key.to_vec(), stored, now);
+                false
+            }
+        }
+    }
 
-        if memory_enabled {
-            let memory_delta = stored.estimated_size() - old_value.as_ref().map(|v| v.estimated_size()).unwrap_or(0);
-            
-            rt.block_on(async {
-                if memory_delta > 0 {
-                    while self.memory.check_eviction_needed().await {
-                        if let Some(evicted_key) = self.memory.evict_one(&|k| self.data.get(k).cloned()).await {
-                            if let Some(evicted) = self.data.remove(&evicted_key) {
-                                self.expiration.cancel_expiration(&evicted_key);
-                                self.memory.remove_memory(&evicted_key, &evicted).await;
-                            }
-                        } else {
-                            break;
-                        }
-                    }
-                }
-            });
+    /// Returns the selected database.
+    fn db(&self) -> Arc<Database> {
+        self.databases.get(self.db)
+    }
+
+    /// Returns a handle that only sees the keys starting with `prefix`.
+    ///
+    /// Keys passed to the handle are stored under `prefix`, and keys it
+    /// returns, such as those from [`keys`](Self::keys) and
+    /// [`scan`](Self::scan), have it stripped.
+    /// [`len`](Self::len) counts and [`flush`](Self::flush) clears only the
+    /// keys under the prefix. Namespaces nest: the namespace `"b:"` of a
+    /// handle namespaced to `"a:"` stores keys under `"a:b:"`.
+    ///
+    /// The handle shares the engine's data and keeps this handle's
+    /// selected database.
+    pub fn namespace(&self, prefix: impl AsRef<[u8]>) -> StorageEngine {
+        let mut engine = self.clone();
+        engine.namespace = [&self.namespace[..], prefix.as_ref()].concat().into();
+        engine
+    }
+
+    /// Returns the key under which `key` is stored in this handle's
+    /// namespace.
+    fn full_key<'k>(&self, key: &'k [u8]) -> Cow<'k, [u8]> {
+        if self.namespace.is_empty() {
+            Cow::Borrowed(key)
+        } else {
+            Cow::Owned([&self.namespace[..], key].concat())
         }
+    }
 
-        if let Some(ref old) = old_value {
-            if old.expire_at.is_some() {
-                self.expiration.cancel_expiration(key);
+    /// Like [`full_key`](Self::full_key), for an owned key.
+    fn full_key_owned(&self, key: Vec<u8>) -> Vec<u8> {
+        if self.namespace.is_empty() {
+            key
+        } else {
+            [&self.namespace[..], &key].concat()
+        }
+    }
+
+    /// Starts the background task that periodically sweeps expired keys.
+    ///
+    /// The task runs on the [`DefaultExecutor`]: a Tokio task when the
+    /// `tokio` feature is enabled, otherwise a dedicated thread. Use
+    /// [`start_expiration_sweeper_on`](Self::start_expiration_sweeper_on) to
+    /// pick the executor.
+    ///
+    /// The returned handle stops the sweeper. It also stops on its own once
+    /// every clone of this engine has been dropped.
+    ///
+    /// # Panics
+    ///
+    /// With the `tokio` feature, panics if called outside a Tokio runtime.
+    pub fn start_expiration_sweeper(&self) -> SweeperHandle {
+        self.start_expiration_sweeper_on(&DefaultExecutor::default())
+    }
+
+    /// Starts the background expiration sweeper on the given executor.
+    ///
+    /// The sweeper runs every sweep interval and removes keys that have
+    /// passed their expiration time, in every database. It does not keep the
+    /// engine alive: once every clone has been dropped it exits on its next
+    /// tick.
+    pub fn start_expiration_sweeper_on<E: Executor + ?Sized>(&self, executor: &E) -> SweeperHandle {
+        let engine = self.downgrade();
+        let (handle, task) = SweeperHandle::wrap(move || match engine.upgrade() {
+            Some(engine) => {
+                engine.sweep_expired();
+                ControlFlow::Continue(())
             }
-            if memory_enabled {
-                rt.block_on(self.memory.remove_memory(key, old));
+            None => ControlFlow::Break(()),
+        });
+        executor.spawn_periodic(self.sweep.sweep_interval, task);
+        handle
+    }
+
+    fn downgrade(&self) -> WeakStorageEngine {
+        WeakStorageEngine {
+            databases: Arc::downgrade(&self.databases),
+            db: self.db,
+            namespace: Arc::clone(&self.namespace),
+            sweep: self.sweep,
+            collection_capacity: self.collection_capacity,
+            clock: Arc::clone(&self.clock),
+            created_at: self.created_at,
+        }
+    }
+
+    /// Runs one active expiry cycle over every database.
+    ///
+    /// Removes keys whose deadline has passed, a small batch at a time, until
+    /// few of the keys in a batch had expired, then moves on to the next
+    /// database. If the cycle's time budget runs out first, the next cycle
+    /// starts from the database after the one it stopped in, so a mass
+    /// expiry in one database cannot starve the others. See the
+    /// [`expire`](super::expire) module for the algorithm.
+    pub(crate) fn sweep_expired(&self) {
+        let started = Instant::now();
+        let budget = self.sweep.cycle_budget();
+        let keys_per_loop = self.sweep.keys_per_loop();
+        let acceptable_stale = self.sweep.acceptable_stale();
+        let count = self.databases.len();
+        let first = self.databases.sweep_cursor.load(Ordering::Relaxed);
+        for index in (first..first + count).map(|i| i % count) {
+            let db = self.databases.get(index);
+            loop {
+                let (expired, sampled) = db.expire_due(self.now_ms(), keys_per_loop);
+                if started.elapsed() >= budget {
+                    self.databases
+                        .sweep_cursor
+                        .store((index + 1) % count, Ordering::Relaxed);
+                    return;
+                }
+                if sampled == 0 || expired * 100 <= sampled * acceptable_stale {
+                    break;
+                }
             }
         }
+    }
+
+    /// Sets a key-value pair in the storage engine.
+    ///
+    /// # Arguments
+    /// * `key` - The key to store; any byte sequence is a valid key
+    /// * `value` - The data to store
+    /// * `expire_at` - Optional deadline, in milliseconds since the Unix epoch
+    pub fn set(&self, key: impl Into<Vec<u8>>, value: RedisData, expire_at: Option<u64>) {
+        self.db().insert(
+            self.full_key_owned(key.into()),
+            StoredValue {
+                data: Arc::new(value),
+                expire_at,
+            },
+        );
+    }
+
+    /// Gets a value from the storage engine by key.
+    ///
+    /// Returns the stored value if the key exists and has not expired.
+    pub fn get(&self, key: impl AsRef<[u8]>) -> Option<StoredValue> {
+        self.lookup(key.as_ref(), StoredValue::clone)
+    }
 
-        self.data.insert(key.to_string(), stored);
+    /// Runs `f` on the data at `key`, or returns `None` if the key is
+    /// missing or has expired.
+    ///
+    /// Every read of the keyspace goes through here (or through
+    /// [`write`](Self::write) and [`upsert`](Self::upsert)), so an expired
+    /// key is never visible. The shard lock is held while `f` runs.
+    pub(crate) fn read<R>(&self, key: &[u8], f: impl FnOnce(&RedisData) -> R) -> Option<R> {
+        self.lookup(key, |stored| f(&stored.data))
+    }
+
+    /// Runs `f` on the data at `key` for modification, or returns `None` if
+    /// the key is missing or has expired.
+    ///
+    /// Like Redis, the keyspace never holds an empty collection: a list,
+    /// set, hash, sorted set or stream that `f` empties is deleted, along
+    /// with its deadline.
+    pub(crate) fn write<R>(&self, key: &[u8], f: impl FnOnce(&mut RedisData) -> R) -> Option<R> {
+        self.lookup_mut(key, |stored| f(Arc::make_mut(&mut stored.data)))
+    }
+
+    /// Runs `f` on the data at `key` for modification, first storing
+    /// `init()` there if the key is missing or has expired.
+    ///
+    /// As with [`write`](Self::write), a collection left empty is deleted,
+    /// so a write that adds nothing to a missing key does not create it.
+    /// Nor does one where `f` returns an error.
+    pub(crate) fn upsert<R, E>(
+        &self,
+        key: impl Into<Vec<u8>>,
+        init: impl FnOnce() -> RedisData,
+        f: impl FnOnce(&mut RedisData) -> Result<R, E>,
+    ) -> Result<R, E> {
+        let key = self.full_key_owned(key.into());
+        self.db().upsert(key, self.now_ms(), init, f)
+    }
+
+    /// Runs `f` on the live value at `key`, or `None` if the key is missing
+    /// or has expired, then stores, keeps or deletes the key as `f` decides.
+    ///
+    /// The shard lock is held throughout, so check-and-set commands such as
+    /// `SET NX` or `GETDEL` are atomic.
+    pub(crate) fn update<R>(
+        &self,
+        key: impl Into<Vec<u8>>,
+        f: impl FnOnce(Option<&StoredValue>) -> (Update, R),
+    ) -> R {
+        let key = self.full_key_owned(key.into());
+        self.db().update(key, self.now_ms(), f)
+    }
+
+    fn lookup<R>(&self, key: &[u8], f: impl FnOnce(&StoredValue) -> R) -> Option<R> {
+        self.db().lookup(&self.full_key(key), self.now_ms(), f)
+    }
+
+    fn lookup_mut<R>(&self, key: &[u8], f: impl FnOnce(&mut StoredValue) -> R) -> Option<R> {
+        self.db().lookup_mut(&self.full_key(key), self.now_ms(), f)
+    }
+
+    /// Removes a key from the storage engine.
+    ///
+    /// Returns `true` if the key was present and live, `false` otherwise.
+    /// Also removes any scheduled expiration for the key.
+    pub fn remove(&self, key: impl AsRef<[u8]>) -> bool {
+        self.take(key).is_some()
+    }
+
+    /// Removes a key from the storage engine and returns its stored value.
+    ///
+    /// Like [`remove`](Self::remove), this also cancels any scheduled
+    /// expiration for the key. Returns `None` if the key had already expired.
+    pub fn take(&self, key: impl AsRef<[u8]>) -> Option<StoredValue> {
+        self.db().take(&self.full_key(key.as_ref()), self.now_ms())
+    }
+
+    /// Stores every pair at once: no other multi-key operation sees some of
+    /// them written and others not. Existing deadlines are cleared.
+    pub(crate) fn set_many(&self, items: impl IntoIterator<Item = (Vec<u8>, RedisData)>) {
+        let db = self.db();
+        let _batch = db.batch.write().unwrap();
+        for (key, value) in items {
+            let value = StoredValue {
+                data: Arc::new(value),
+                expire_at: None,
+            };
+            db.insert(self.full_key_owned(key), value);
+        }
+    }
 
-        if let Some(at) = expire_at {
-            self.expiration.schedule_expiration(key.to_string(), at);
+    /// Like [`set_many`](Self::set_many), but stores nothing unless every
+    /// key is missing. Returns `true` if the pairs were stored.
+    pub(crate) fn set_many_if_vacant(
+        &self,
+        items: impl IntoIterator<Item = (Vec<u8>, RedisData)>,
+    ) -> bool {
+        // The last value given for a key wins, as with `set_many`.
+        let items: FxHashMap<Vec<u8>, RedisData> = items
+            .into_iter()
+            .map(|(key, value)| (self.full_key_owned(key), value))
+            .collect();
+        let db = self.db();
+        let now = self.now_ms();
+        let _batch = db.batch.write().unwrap();
+        if items
+            .keys()
+            .any(|key| db.lookup(key, now, |_| ()).is_some())
+        {
+            return false;
         }
-        
-        if memory_enabled {
-            rt.block_on(async {
-                if let Some(new_value) = self.data.get(key) {
-                    self.memory.add_memory(key, &new_value).await;
+        let mut stored = Vec::with_capacity(items.len());
+        for (key, value) in items {
+            let data = Arc::new(value);
+            let value = StoredValue {
+                data: Arc::clone(&data),
+                expire_at: None,
+            };
+            if db.insert_if_vacant(key.clone(), value, now).is_err() {
+                // A single-key command created the key since the check above:
+                // undo the keys stored so far, unless they changed too.
+                for (key, data) in stored {
+                    db.update(key, now, |current| match current {
+                        Some(value) if Arc::ptr_eq(&value.data, &data) => (Update::Delete, ()),
+                        _ => (Update::Keep, ()),
+                    });
                 }
-            });
+                return false;
+            }
+            stored.push((key, data));
         }
+        true
     }
 
-    pub fn get(&self, key: &str) -> Option<StoredValue> {
-        let value = self.data.get(key).map(|v| v.clone());
-        
-        if value.is_some() {
-            let rt = tokio::runtime::Handle::current();
-            if rt.block_on(self.memory.is_enabled()) {
-                rt.block_on(self.memory.record_read(key));
-            }
+    /// Moves the value at `key`, with its deadline, to `new_key`, like
+    /// `RENAME`, or like `RENAMENX` unless `replace` is set.
+    ///
+    /// Returns `None` if `key` is missing, and `Some(false)` if `new_key`
+    /// exists and `replace` is not set. No other multi-key operation sees the
+    /// move half done, and the value is stored under `new_key` before it
+    /// leaves `key`, so it is never missing from both. A value written to
+    /// `new_key` meanwhile is only replaced if `replace` is set.
+    pub(crate) fn rename(&self, key: &[u8], new_key: &[u8], replace: bool) -> Option<bool> {
+        let (key, new_key) = (self.full_key(key), self.full_key(new_key));
+        let db = self.db();
+        let now = self.now_ms();
+        let _batch = db.batch.write().unwrap();
+        let stored = db.lookup(&key, now, StoredValue::clone)?;
+        if key == new_key {
+            return Some(replace);
+        }
+        let data = Arc::clone(&stored.data);
+        if replace {
+            db.insert(new_key.into_owned(), stored);
+        } else if db
+            .insert_if_vacant(new_key.into_owned(), stored, now)
+            .is_err()
+        {
+            return Some(false);
         }
-        
-        value
+        // A single-key write to `key` since the lookup came after the move,
+        // so it stays.
+        db.update(key.into_owned(), now, |current| match current {
+            Some(value) if Arc::ptr_eq(&value.data, &data) => (Update::Delete, ()),
+            _ => (Update::Keep, ()),
+        });
+        Some(true)
     }
 
-    pub fn remove(&self, key: &str) -> bool {
-        if let Some(old) = self.data.get(key) {
-            let rt = tokio::runtime::Handle::current();
-            if rt.block_on(self.memory.is_enabled()) {
-                rt.block_on(self.memory.remove_memory(key, &old));
-            }
+    /// Runs `f` on the data at each of `keys`, or gives `None` for the
+    /// missing ones, without any multi-key write happening in between.
+    pub(crate) fn read_many<R>(
+        &self,
+        keys: &[Vec<u8>],
+        mut f: impl FnMut(&RedisData) -> R,
+    ) -> Vec<Option<R>> {
+        let db = self.db();
+        let now = self.now_ms();
+        let _batch = db.batch.read().unwrap();
+        keys.iter()
+            .map(|key| db.lookup(&self.full_key(key), now, |stored| f(&stored.data)))
+            .collect()
+    }
+
+    /// Removes each of `keys` at once and returns how many were live.
+    pub(crate) fn remove_many(&self, keys: &[Vec<u8>]) -> usize {
+        let db = self.db();
+        let now = self.now_ms();
+        let _batch = db.batch.write().unwrap();
+        keys.iter()
+            .filter(|key| db.take(&self.full_key(key), now).is_some())
+            .count()
+    }
+
+    /// Compacts the storage engine by shrinking the DashMap's internal allocations.
+    ///
+    /// This reclaims memory from removed entries by shrinking each shard's
+    /// backing storage to fit only the current entries, in every database.
+    /// The high-water marks are reset to the current number of entries.
+    pub fn compact(&self) {
+        for index in 0..self.db_count() {
+            self.databases.get(index).compact();
         }
-        
-        self.expiration.cancel_expiration(key);
-        self.data.remove(key).is_some()
     }
 
-    pub fn exists(&self, key: &str) -> bool {
-        self.data.contains_key(key)
+    /// Checks if a key exists in the storage engine.
+    ///
+    /// Returns `true` if the key exists and has not expired, `false` otherwise.
+    pub fn exists(&self, key: impl AsRef<[u8]>) -> bool {
+        self.lookup(key.as_ref(), |_| ()).is_some()
     }
 
+    /// Returns the number of live keys in the selected database (and
+    /// namespace).
+    ///
+    /// Keys that have expired but not yet been swept are not counted, so this
+    /// walks the whole keyspace.
     pub fn len(&self) -> usize {
-        self.data.len()
+        self.db().len(&self.namespace, self.now_ms())
     }
 
+    /// Returns `true` if the selected database (and namespace) contains no
+    /// live keys.
     pub fn is_empty(&self) -> bool {
-        self.data.is_empty()
+        self.db().is_empty(&self.namespace, self.now_ms())
+    }
+
+    /// Returns every live key in the selected database (and namespace).
+    pub fn keys(&self) -> Vec<Vec<u8>> {
+        let prefix = self.namespace.len();
+        let mut keys = self.db().keys(&self.namespace, self.now_ms());
+        if prefix > 0 {
+            for key in &mut keys {
+                key.drain(..prefix);
+            }
+        }
+        keys
     }
 
-    pub fn keys(&self) -> Vec<String> {
-        self.data.iter().map(|kv| kv.key().clone()).collect()
+    /// Returns about `count` live keys of the selected database (and
+    /// namespace), starting at `cursor`, and the cursor to pass next time, or
+    /// `0` once every key has been returned.
+    ///
+    /// Start with a cursor of `0`. A key that is present for the whole scan
+    /// is returned exactly once; keys added or removed meanwhile may or may
+    /// not be. Each call walks the whole keyspace, like [`keys`](Self::keys),
+    /// but only copies out one batch.
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, Vec<Vec<u8>>) {
+        let prefix = self.namespace.len();
+        let (cursor, mut keys) = self
+            .db()
+            .scan(&self.namespace, self.now_ms(), cursor, count);
+        if prefix > 0 {
+            for key in &mut keys {
+                key.drain(..prefix);
+            }
+        }
+        (cursor, keys)
     }
 
+    /// Clears the selected database, or only its keys in this handle's
+    /// namespace.
+    ///
+    /// This removes the keys and their values, and cancels their scheduled
+    /// expirations. Other databases are untouched; see
+    /// [`flush_all`](Self::flush_all).
     pub fn flush(&self) {
-        self.data.clear();
-        self.expiration.clear_all();
+        self.db().flush(&self.namespace);
     }
 
-    pub fn set_expiry(&self, key: &str, duration: Duration) -> bool {
-        if let Some(mut entry) = self.data.get_mut(key) {
-            let expire_at = Instant::now() + duration;
-            entry.expire_at = Some(expire_at);
-            self.expiration.schedule_expiration(key.to_string(), expire_at);
-            return true;
+    /// Clears every database, or only their keys in this handle's
+    /// namespace.
+    pub fn flush_all(&self) {
+        for index in 0..self.db_count() {
+            self.databases.get(index).flush(&self.namespace);
         }
-        false
     }
 
-    pub fn persist(&self, key: &str) -> bool {
-        if let Some(mut entry) = self.data.get_mut(key) {
-            entry.expire_at = None;
-            self.expiration.cancel_expiration(key);
-            return true;
-        }
-        false
+    /// Sets a key's time to live, in milliseconds, if `option` allows it.
+    ///
+    /// A TTL of zero or less deletes the key, as in Redis.
+    ///
+    /// Returns the Redis reply: `1` if the timeout was set (or the key
+    /// deleted), `0` if the key does not exist or `option` prevented it.
+    pub fn set_expiry(&self, key: impl AsRef<[u8]>, ttl_ms: i64, option: ExpireOption) -> i64 {
+        let at = (self.now_ms() as i64).saturating_add(ttl_ms);
+        self.set_expiry_at(key, at, option)
     }
 
-    pub fn ttl(&self, key: &str) -> Option<Duration> {
-        self.data.get(key).and_then(|entry| {
-            entry.expire_at.map(|at| {
-                at.saturating_duration_since(Instant::now())
+    /// Sets the deadline of an existing key, in milliseconds since the Unix
+    /// epoch, if `option` allows it.
+    ///
+    /// A deadline that has already passed deletes the key, as in Redis.
+    ///
+    /// Returns the Redis reply: `1` if the timeout was set (or the key
+    /// deleted), `0` if the key does not exist or `option` prevented it.
+    pub fn set_expiry_at(&self, key: impl AsRef<[u8]>, at: i64, option: ExpireOption) -> i64 {
+        let key = self.full_key_owned(key.as_ref().to_vec());
+        let now = self.now_ms();
+        // Check and delete under the same shard lock, so a concurrent write
+        // between the two is never lost.
+        self.db().update(key, now, |stored| match stored {
+            Some(stored) if option.allows(stored.expire_at, at) => {
+                if at <= now as i64 {
+                    (Update::Delete, 1)
+                } else {
+                    let value = StoredValue {
+                        data: stored.data.clone(),
+                        expire_at: Some(at as u64),
+                    };
+                    (Update::Set(value), 1)
+                }
+            }
+            _ => (Update::Keep, 0),
+        })
+    }
+
+    /// Removes the expiration from a key, making it persistent.
+    ///
+    /// Returns the Redis reply: `1` if a timeout was removed, `0` if the key
+    /// does not exist or has no timeout.
+    pub fn persist(&self, key: impl AsRef<[u8]>) -> i64 {
+        let key = self.full_key(key.as_ref());
+        let key = key.as_ref();
+        let db = self.db();
+        // Cancel under the shard lock, so a deadline set by a concurrent
+        // write once the lock is released is kept.
+        let removed = db
+            .lookup_mut(key, self.now_ms(), |stored| {
+                let removed = stored.expire_at.take().is_some();
+                if removed {
+                    db.expiration.cancel(key);
+                }
+                removed
             })
+            .unwrap_or(false);
+        removed as i64
+    }
+
+    /// Returns the time-to-live remaining for a key.
+    ///
+    /// Returns `Some(Duration)` if the key has an expiration,
+    /// or `None` if the key does not exist or has no expiration.
+    pub fn ttl(&self, key: impl AsRef<[u8]>) -> Option<Duration> {
+        self.lookup(key.as_ref(), |stored| {
+            stored
+                .expire_at
+                .map(|at| Duration::from_millis(at.saturating_sub(self.now_ms())))
         })
+        .flatten()
     }
 
-    pub fn xadd(&self, key: &str, entry_id: Option<&[u8]>, values: Vec<(Vec<u8>, Vec<u8>)>) -> Option<Vec<u8>> {
+    /// Returns the TTL of a key in seconds, in Redis-compatible format.
+    ///
+    /// Returns:
+    /// - `-1` if the key exists but has no expiration
+    /// - `-2` if the key does not exist
+    /// - The remaining time, rounded to the nearest second
+    pub fn ttl_query(&self, key: impl AsRef<[u8]>) -> i64 {
+        match self.pttl_query(key) {
+            ms if ms < 0 => ms,
+            ms => (ms + 500) / 1000,
+        }
+    }
+
+    /// Returns the TTL of a key in milliseconds, in Redis-compatible format.
+    ///
+    /// Returns:
+    /// - `-1` if the key exists but has no expiration
+    /// - `-2` if the key does not exist
+    /// - A non-negative value representing milliseconds until expiration
+    pub fn pttl_query(&self, key: impl AsRef<[u8]>) -> i64 {
+        self.lookup(key.as_ref(), |stored| match stored.expire_at {
+            Some(at) => at.saturating_sub(self.now_ms()) as i64,
+            None => -1i64,
+        })
+        .unwrap_or(-2)
+    }
+
+    /// Returns the deadline of a key in milliseconds since the Unix epoch,
+    /// in Redis-compatible format.
+    ///
+    /// Returns:
+    /// - `-1` if the key exists but has no expiration
+    /// - `-2` if the key does not exist
+    /// - The deadline otherwise
+    pub fn expire_time_query(&self, key: impl AsRef<[u8]>) -> i64 {
+        self.lookup(key.as_ref(), |stored| match stored.expire_at {
+            Some(at) => at as i64,
+            None => -1i64,
+        })
+        .unwrap_or(-2)
+    }
+
+    /// Makes sure the sweeper visits the hash at `key` no later than `at`,
+    /// after one of its fields was given that deadline.
+    pub(crate) fn schedule_field_expiry(&self, key: &[u8], at: u64) {
+        self.db()
+            .field_expiration
+            .schedule_earliest(self.full_key(key).into_owned(), at);
+    }
+
+    /// Adds an entry to a stream.
+    ///
+    /// # Arguments
+    /// * `key` - The stream key
+    /// * `entry_id` - Optional custom entry ID. If None, auto-generates one
+    /// * `values` - Field-value pairs to add
+    ///
+    /// Returns the entry ID if successful, None if key exists but is not a stream.
+    pub fn xadd(
+        &self,
+        key: impl AsRef<[u8]>,
+        entry_id: Option<&[u8]>,
+        values: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Option<Vec<u8>> {
+        let key = key.as_ref();
         let new_id = match entry_id {
             Some(id) => id.to_vec(),
             None => self.generate_stream_id(),
@@ -208,128 +823,688 @@ impl StorageEngine {
 
         let entry = (new_id.clone(), values);
 
-        if let Some(mut stored) = self.data.get_mut(key) {
-            match &mut stored.data {
+        self.upsert(
+            key,
+            || RedisData::Stream(Vec::new()),
+            |data| match data {
                 RedisData::Stream(entries) => {
                     entries.push(entry);
-                    Some(new_id)
+                    Ok(new_id)
                 }
-                _ => None,
-            }
-        } else {
-            let entries = vec![entry];
-            self.set(key, RedisData::Stream(entries), None);
-            Some(new_id)
-        }
+                _ => Err(()),
+            },
+        )
+        .ok()
     }
 
-    pub fn xlen(&self, key: &str) -> Option<usize> {
-        self.data.get(key).map(|stored| {
-            match &stored.data {
-                RedisData::Stream(entries) => entries.len(),
-                _ => 0,
-            }
+    /// Returns the number of entries in a stream.
+    ///
+    /// Returns the length if the key exists and is a stream, None otherwise.
+    pub fn xlen(&self, key: impl AsRef<[u8]>) -> Option<usize> {
+        self.read(key.as_ref(), |data| match data {
+            RedisData::Stream(entries) => entries.len(),
+            _ => 0,
         })
     }
 
-    pub fn xtrim(&self, key: &str, maxlen: usize, approximate: bool) -> Option<usize> {
-        if let Some(mut stored) = self.data.get_mut(key) {
-            match &mut stored.data {
-                RedisData::Stream(entries) => {
-                    let original_len = entries.len();
-                    if entries.len() > maxlen {
-                        let keep = if approximate {
-                            maxlen.saturating_sub(maxlen / 10)
-                        } else {
-                            maxlen
-                        };
-                        entries.drain(0..entries.len().saturating_sub(keep));
-                    }
-                    return Some(original_len.saturating_sub(entries.len()));
+    /// Trims a stream to a maximum number of entries.
+    ///
+    /// # Arguments
+    /// * `key` - The stream key
+    /// * `maxlen` - Maximum number of entries to keep
+    /// * `approximate` - If true, uses approximate trimming (keeps maxlen - 10%)
+    ///
+    /// Returns the number of entries removed, or None if key is not a stream.
+    pub fn xtrim(&self, key: impl AsRef<[u8]>, maxlen: usize, approximate: bool) -> Option<usize> {
+        self.write(key.as_ref(), |data| match data {
+            RedisData::Stream(entries) => {
+                let original_len = entries.len();
+                if entries.len() > maxlen {
+                    let keep = if approximate {
+                        maxlen.saturating_sub(maxlen / 10)
+                    } else {
+                        maxlen
+                    };
+                    entries.drain(0..entries.len().saturating_sub(keep));
+                    entries.shrink_to_fit();
                 }
-                _ => return None,
+                Some(original_len.saturating_sub(entries.len()))
             }
-        }
-        None
+            _ => None,
+        })
+        .flatten()
     }
 
-    pub fn xdel(&self, key: &str, entry_ids: Vec<&[u8]>) -> Option<usize> {
-        if let Some(mut stored) = self.data.get_mut(key) {
-            match &mut stored.data {
-                RedisData::Stream(entries) => {
-                    let original_len = entries.len();
-                    entries.retain(|(id, _)| {
-                        !entry_ids.iter().any(|del_id| *del_id == id.as_slice())
-                    });
-                    return Some(original_len.saturating_sub(entries.len()));
-                }
-                _ => return None,
+    /// Deletes entries from a stream.
+    ///
+    /// # Arguments
+    /// * `key` - The stream key
+    /// * `entry_ids` - Entry IDs to delete
+    ///
+    /// Returns the number of entries deleted, or None if key is not a stream.
+    pub fn xdel(&self, key: impl AsRef<[u8]>, entry_ids: Vec<&[u8]>) -> Option<usize> {
+        self.write(key.as_ref(), |data| match data {
+            RedisData::Stream(entries) => {
+                let original_len = entries.len();
+                entries.retain(|(id, _)| !entry_ids.contains(&id.as_slice()));
+                entries.shrink_to_fit();
+                Some(original_len.saturating_sub(entries.len()))
             }
-        }
-        None
+            _ => None,
+        })
+        .flatten()
     }
 
-    pub fn xrange(&self, key: &str, start: &[u8], end: &[u8], count: Option<usize>) -> Option<Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>> {
-        self.data.get(key).map(|stored| {
-            match &stored.data {
-                RedisData::Stream(entries) => {
-                    let mut result: Vec<_> = entries.iter()
-                        .filter(|(id, _)| {
-                            let ge_start = start == b"-" || id.as_slice() >= start;
-                            let le_end = end == b"+" || id.as_slice() <= end;
-                            ge_start && le_end
-                        })
-                        .cloned()
-                        .collect();
-                    
-                    if let Some(c) = count {
-                        result.truncate(c);
-                    }
-                    result
+    /// Returns entries in a stream within a range.
+    ///
+    /// # Arguments
+    /// * `key` - The stream key
+    /// * `start` - Start ID (use "-" for beginning)
+    /// * `end` - End ID (use "+" for end)
+    /// * `count` - Optional maximum number of entries to return
+    ///
+    /// Returns the entries in the range, or None if key is not a stream.
+    pub fn xrange(
+        &self,
+        key: impl AsRef<[u8]>,
+        start: &[u8],
+        end: &[u8],
+        count: Option<usize>,
+    ) -> Option<Vec<StreamEntry>> {
+        self.read(key.as_ref(), |data| match data {
+            RedisData::Stream(entries) => {
+                let mut result: Vec<_> = entries
+                    .iter()
+                    .filter(|(id, _)| {
+                        let ge_start = start == b"-" || id.as_slice() >= start;
+                        let le_end = end == b"+" || id.as_slice() <= end;
+                        ge_start && le_end
+                    })
+                    .cloned()
+                    .collect();
+
+                if let Some(c) = count {
+                    result.truncate(c);
                 }
-                _ => vec![],
+                result
             }
+            _ => vec![],
         })
     }
 
-    pub fn xrevrange(&self, key: &str, start: &[u8], end: &[u8], count: Option<usize>) -> Option<Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>> {
-        self.xrange(key, start, end, count).map(|mut entries| {
+    /// Returns entries in a stream within a range, in reverse order.
+    ///
+    /// # Arguments
+    /// * `key` - The stream key
+    /// * `start` - Start ID (use "-" for beginning)
+    /// * `end` - End ID (use "+" for end)
+    /// * `count` - Optional maximum number of entries to return, newest first
+    ///
+    /// Returns the entries in reverse order, or None if key is not a stream.
+    pub fn xrevrange(
+        &self,
+        key: impl AsRef<[u8]>,
+        start: &[u8],
+        end: &[u8],
+        count: Option<usize>,
+    ) -> Option<Vec<StreamEntry>> {
+        let key = key.as_ref();
+        self.xrange(key, start, end, None).map(|mut entries| {
             entries.reverse();
+            if let Some(c) = count {
+                entries.truncate(c);
+            }
             entries
         })
     }
 
-    fn generate_stream_id(&self) -> Vec<u8> {
-        let timestamp = SystemTime::now()
+    /// Returns the Unix time, in seconds, at which this engine was created.
+    ///
+    /// There is no persistence, so this stands in for the last save time
+    /// reported by `LASTSAVE`.
+    pub fn created_at(&self) -> u64 {
+        self.created_at
             .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-        format!("{}-0", timestamp).into_bytes()
+            .map_or(0, |d| d.as_secs())
     }
 
-    pub async fn set_maxmemory(&self, maxmemory: usize) {
-        self.memory.set_maxmemory(Some(maxmemory)).await;
+    fn generate_stream_id(&self) -> Vec<u8> {
+        format!("{}-0", self.now_ms()).into_bytes()
     }
+}
 
-    pub async fn set_maxmemory_policy(&self, policy: MaxMemoryPolicy) {
-        self.memory.set_maxmemory_policy(policy).await;
+impl Default for StorageEngine {
+    fn default() -> Self {
+        Self::new()
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TestClock;
+
+    #[test]
+    fn test_xtrim_shrinks_vec_capacity() {
+        let engine = StorageEngine::new();
+
+        // Add many entries to build up Vec capacity
+        for i in 0..100 {
+            let id = format!("{}-0", i);
+            engine.xadd(
+                "stream",
+                Some(id.as_bytes()),
+                vec![(b"k".to_vec(), b"v".to_vec())],
+            );
+        }
+
+        // Verify we have 100 entries
+        assert_eq!(engine.xlen("stream"), Some(100));
+
+        // Get capacity before trim
+        let capacity_before = match &*engine.db().data.get(b"stream".as_slice()).unwrap().data {
+            RedisData::Stream(entries) => entries.capacity(),
+            _ => panic!("expected stream"),
+        };
+
+        // Trim to keep only 2 entries
+        let removed = engine.xtrim("stream", 2, false);
+        assert_eq!(removed, Some(98));
+        assert_eq!(engine.xlen("stream"), Some(2));
 
-    pub async fn get_maxmemory(&self) -> Option<usize> {
-        self.memory.get_maxmemory().await
+        // Verify capacity shrunk
+        let capacity_after = match &*engine.db().data.get(b"stream".as_slice()).unwrap().data {
+            RedisData::Stream(entries) => entries.capacity(),
+            _ => panic!("expected stream"),
+        };
+
+        assert!(
+            capacity_after < capacity_before,
+            "capacity should shrink after trim: before={}, after={}",
+            capacity_before,
+            capacity_after
+        );
     }
 
-    pub async fn get_maxmemory_policy(&self) -> MaxMemoryPolicy {
-        self.memory.get_maxmemory_policy().await
+    #[test]
+    fn test_xdel_shrinks_vec_capacity() {
+        let engine = StorageEngine::new();
+
+        // Add many entries
+        let mut ids = Vec::new();
+        for i in 0..100 {
+            let id = format!("{}-0", i);
+            engine.xadd(
+                "stream",
+                Some(id.as_bytes()),
+                vec![(b"k".to_vec(), b"v".to_vec())],
+            );
+            ids.push(id);
+        }
+
+        // Get capacity before delete
+        let capacity_before = match &*engine.db().data.get(b"stream".as_slice()).unwrap().data {
+            RedisData::Stream(entries) => entries.capacity(),
+            _ => panic!("expected stream"),
+        };
+
+        // Delete most entries (keep only the last 2)
+        let to_delete: Vec<&[u8]> = ids[..98].iter().map(|s| s.as_bytes()).collect();
+        let removed = engine.xdel("stream", to_delete);
+        assert_eq!(removed, Some(98));
+
+        // Verify capacity shrunk
+        let capacity_after = match &*engine.db().data.get(b"stream".as_slice()).unwrap().data {
+            RedisData::Stream(entries) => entries.capacity(),
+            _ => panic!("expected stream"),
+        };
+
+        assert!(
+            capacity_after < capacity_before,
+            "capacity should shrink after xdel: before={}, after={}",
+            capacity_before,
+            capacity_after
+        );
     }
 
-    pub fn current_memory_usage(&self) -> usize {
-        self.memory.current_memory()
+    #[test]
+    fn test_compact_preserves_data() {
+        let engine = StorageEngine::new();
+        engine.set("key1", RedisData::String(b"val1".to_vec()), None);
+        engine.set("key2", RedisData::String(b"val2".to_vec()), None);
+
+        engine.compact();
+
+        assert_eq!(engine.len(), 2);
+        assert!(engine.exists("key1"));
+        assert!(engine.exists("key2"));
     }
-}
 
-impl Default for StorageEngine {
-    fn default() -> Self {
-        Self::new()
+    #[test]
+    fn test_compact_resets_high_water_mark() {
+        let engine = StorageEngine::new();
+        for i in 0..100 {
+            engine.set(
+                format!("key{}", i),
+                RedisData::String(b"val".to_vec()),
+                None,
+            );
+        }
+        assert_eq!(engine.db().high_water_mark.load(Ordering::Relaxed), 100);
+
+        // Remove some keys without triggering auto-compact (50 >= 25% of 100)
+        for i in 50..100 {
+            engine.remove(format!("key{}", i));
+        }
+        assert_eq!(engine.len(), 50);
+
+        // Manual compact should reset high-water mark
+        engine.compact();
+        assert_eq!(engine.db().high_water_mark.load(Ordering::Relaxed), 50);
+    }
+
+    #[test]
+    fn test_auto_compaction_on_remove() {
+        let engine = StorageEngine::new();
+        for i in 0..100 {
+            engine.set(
+                format!("key{}", i),
+                RedisData::String(b"val".to_vec()),
+                None,
+            );
+        }
+        assert_eq!(engine.db().high_water_mark.load(Ordering::Relaxed), 100);
+
+        // Remove keys until len < 25% of high-water mark (below 25)
+        for i in 0..76 {
+            engine.remove(format!("key{}", i));
+        }
+
+        // After auto-compaction triggered, high-water mark should be reset
+        assert_eq!(engine.len(), 24);
+        assert_eq!(engine.db().high_water_mark.load(Ordering::Relaxed), 24);
+    }
+
+    #[test]
+    fn test_no_auto_compaction_above_threshold() {
+        let engine = StorageEngine::new();
+        for i in 0..100 {
+            engine.set(
+                format!("key{}", i),
+                RedisData::String(b"val".to_vec()),
+                None,
+            );
+        }
+
+        // Remove only 50 keys — 50 remaining is >= 25% of 100
+        for i in 0..50 {
+            engine.remove(format!("key{}", i));
+        }
+
+        // High-water mark should NOT have been reset
+        assert_eq!(engine.len(), 50);
+        assert_eq!(engine.db().high_water_mark.load(Ordering::Relaxed), 100);
+    }
+
+    #[test]
+    fn test_flush_resets_high_water_mark() {
+        let engine = StorageEngine::new();
+        for i in 0..50 {
+            engine.set(
+                format!("key{}", i),
+                RedisData::String(b"val".to_vec()),
+                None,
+            );
+        }
+        assert_eq!(engine.db().high_water_mark.load(Ordering::Relaxed), 50);
+
+        engine.flush();
+        assert_eq!(engine.db().high_water_mark.load(Ordering::Relaxed), 0);
+        assert_eq!(engine.len(), 0);
+    }
+
+    fn set_expired(engine: &StorageEngine, key: &str, value: RedisData) {
+        engine.set(key, value, Some(engine.now_ms() - 1000));
+    }
+
+    #[test]
+    fn test_expired_key_is_invisible() {
+        let engine = StorageEngine::new();
+        set_expired(&engine, "gone", RedisData::String(b"v".to_vec()));
+        engine.set("live", RedisData::String(b"v".to_vec()), None);
+
+        assert!(!engine.exists("gone"));
+        assert!(engine.get("gone").is_none());
+        assert_eq!(engine.ttl_query("gone"), -2);
+        assert_eq!(engine.ttl("gone"), None);
+        assert_eq!(engine.len(), 1);
+        assert_eq!(engine.keys(), vec![b"live".to_vec()]);
+        assert_eq!(engine.persist("gone"), 0);
+        assert_eq!(engine.set_expiry("gone", 10_000, ExpireOption::NONE), 0);
+    }
+
+    #[test]
+    fn test_expired_key_is_removed_on_access() {
+        let engine = StorageEngine::new();
+        set_expired(&engine, "gone", RedisData::String(b"v".to_vec()));
+        assert_eq!(engine.db().data.len(), 1);
+
+        assert!(engine.read(b"gone", |_| ()).is_none());
+        assert_eq!(engine.db().data.len(), 0);
+        assert_eq!(engine.db().current_len.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_remove_expired_key_reports_missing() {
+        let engine = StorageEngine::new();
+        set_expired(&engine, "gone", RedisData::String(b"v".to_vec()));
+        assert!(!engine.remove("gone"));
+        assert!(engine.take("gone").is_none());
+    }
+
+    #[test]
+    fn test_upsert_replaces_expired_value() {
+        let engine = StorageEngine::new();
+        set_expired(&engine, "list", RedisData::String(b"old".to_vec()));
+
+        let len = engine.upsert(
+            "list",
+            || RedisData::List(Default::default()),
+            |data| match data {
+                RedisData::List(l) => {
+                    l.push_back(b"new".to_vec());
+                    Ok(l.len())
+                }
+                _ => Err(()),
+            },
+        );
+        assert_eq!(len, Ok(1));
+        assert_eq!(engine.ttl_query("list"), -1);
+    }
+
+    #[test]
+    fn test_failed_upsert_does_not_create_key() {
+        let engine = StorageEngine::new();
+        let result = engine.upsert("key", || RedisData::Int(0), |_| Err::<(), _>(()));
+        assert_eq!(result, Err(()));
+        assert!(!engine.exists("key"));
+
+        set_expired(&engine, "old", RedisData::String(b"v".to_vec()));
+        let result = engine.upsert("old", || RedisData::Int(0), |_| Err::<(), _>(()));
+        assert_eq!(result, Err(()));
+        assert_eq!(engine.db().data.len(), 0);
+        assert_eq!(engine.db().current_len.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_expired_stream_is_invisible() {
+        let engine = StorageEngine::new();
+        set_expired(&engine, "stream", RedisData::Stream(Vec::new()));
+        assert_eq!(engine.xlen("stream"), None);
+        assert_eq!(engine.xrange("stream", b"-", b"+", None), None);
+
+        let id = engine.xadd("stream", Some(b"1-0"), vec![(b"k".to_vec(), b"v".to_vec())]);
+        assert_eq!(id, Some(b"1-0".to_vec()));
+        assert_eq!(engine.xlen("stream"), Some(1));
+    }
+
+    #[test]
+    fn test_sweep_removes_due_keys() {
+        let engine = StorageEngine::new();
+        set_expired(&engine, "gone", RedisData::String(b"v".to_vec()));
+        engine.set("live", RedisData::String(b"v".to_vec()), None);
+
+        engine.sweep_expired();
+        assert_eq!(engine.db().data.len(), 1);
+        assert!(engine.exists("live"));
+    }
+
+    #[test]
+    fn test_expiration_index_follows_overwrites() {
+        let engine = StorageEngine::new();
+        let later = Some(engine.now_ms() + 60_000);
+        engine.set("a", RedisData::String(b"v".to_vec()), later);
+        engine.set("b", RedisData::String(b"v".to_vec()), later);
+        engine.set_expiry("a", 120_000, ExpireOption::NONE);
+        assert_eq!(engine.db().expiration.len(), 2);
+
+        engine.set("a", RedisData::String(b"v".to_vec()), None);
+        engine.persist("b");
+        assert_eq!(engine.db().expiration.len(), 0);
+    }
+
+    #[test]
+    fn test_emptied_collection_is_removed() {
+        let engine = StorageEngine::new();
+        engine
+            .upsert(b"set".to_vec(), || engine.new_set(), |_| Ok::<_, ()>(()))
+            .unwrap();
+        assert_eq!(engine.db().data.len(), 0);
+        assert_eq!(engine.db().current_len.load(Ordering::Relaxed), 0);
+
+        let later = Some(engine.now_ms() + 60_000);
+        let members = FxHashSet::from_iter([b"a".to_vec()]);
+        engine.set("set", RedisData::Set(members), later);
+        engine.write(b"set", |data| {
+            if let RedisData::Set(s) = data {
+                s.clear();
+            }
+        });
+        assert_eq!(engine.db().data.len(), 0);
+        assert_eq!(engine.db().current_len.load(Ordering::Relaxed), 0);
+        assert_eq!(engine.db().expiration.len(), 0);
+    }
+
+    #[test]
+    fn test_sweep_works_through_mass_expiry_in_batches() {
+        let engine = StorageEngine::new().with_active_expire_effort(MAX_EFFORT);
+        for i in 0..1000 {
+            set_expired(
+                &engine,
+                &format!("k{}", i),
+                RedisData::String(b"v".to_vec()),
+            );
+        }
+        engine.set("live", RedisData::String(b"v".to_vec()), None);
+
+        // Every batch is fully expired, so the cycle keeps looping until the
+        // index runs dry or the budget is spent.
+        while engine.db().data.len() > 1 {
+            engine.sweep_expired();
+        }
+        assert!(engine.exists("live"));
+        assert_eq!(engine.db().expiration.len(), 0);
+    }
+
+    #[test]
+    fn test_sweep_stops_when_few_keys_are_due() {
+        let engine = StorageEngine::new();
+        let later = Some(engine.now_ms() + 60_000);
+        for i in 0..100 {
+            engine.set(format!("k{}", i), RedisData::String(b"v".to_vec()), later);
+        }
+        set_expired(&engine, "gone", RedisData::String(b"v".to_vec()));
+
+        engine.sweep_expired();
+        assert_eq!(engine.db().data.len(), 100);
+        assert_eq!(engine.db().expiration.len(), 100);
+    }
+
+    #[test]
+    fn test_active_expire_effort_is_clamped() {
+        let engine = StorageEngine::new().with_active_expire_effort(0);
+        assert_eq!(engine.sweep.effort, MIN_EFFORT);
+        let engine = engine.with_active_expire_effort(200);
+        assert_eq!(engine.sweep.effort, MAX_EFFORT);
+    }
+
+    #[test]
+    fn test_sweep_uses_engine_clock() {
+        let clock = TestClock::new();
+        let engine = StorageEngine::new().with_clock(clock.clone());
+        engine.set("key", RedisData::String(b"v".to_vec()), None);
+        engine.set_expiry("key", 5_000, ExpireOption::NONE);
+
+        engine.sweep_expired();
+        assert_eq!(engine.db().data.len(), 1);
+        clock.advance(Duration::from_secs(5));
+        engine.sweep_expired();
+        assert_eq!(engine.db().data.len(), 0);
+    }
+
+    #[test]
+    fn test_sweep_removes_expired_hash_fields() {
+        let clock = TestClock::new();
+        let engine = StorageEngine::new().with_clock(clock.clone());
+        let now = engine.now_ms();
+        let mut hash = HashValue::default();
+        hash.insert(b"a".to_vec(), b"1".to_vec(), now);
+        hash.insert(b"b".to_vec(), b"2".to_vec(), now);
+        hash.set_deadline(b"a", now + 5_000);
+        hash.set_deadline(b"b", now + 10_000);
+        engine.set("h", RedisData::Hash(hash), None);
+        assert_eq!(engine.db().field_expiration.len(), 1);
+
+        clock.advance(Duration::from_secs(5));
+        engine.sweep_expired();
+        match &*engine.db().data.get(b"h".as_slice()).unwrap().data {
+            RedisData::Hash(h) => assert_eq!(h.fields().len(), 1),
+            _ => unreachable!(),
+        }
+        assert_eq!(engine.db().field_expiration.len(), 1);
+
+        // The last field going deletes the hash.
+        clock.advance(Duration::from_secs(5));
+        engine.sweep_expired();
+        assert_eq!(engine.db().data.len(), 0);
+        assert_eq!(engine.db().field_expiration.len(), 0);
+    }
+
+    #[test]
+    fn test_builder_rounds_shards_to_power_of_two() {
+        assert_eq!(StorageEngine::builder().shards(0).shards, 2);
+        assert_eq!(StorageEngine::builder().shards(3).shards, 4);
+        assert_eq!(StorageEngine::builder().shards(64).shards, 64);
+    }
+
+    #[test]
+    fn test_builder_sets_collection_capacity() {
+        let engine = StorageEngine::builder()
+            .hash_capacity(0)
+            .set_capacity(64)
+            .list_capacity(32)
+            .build();
+        match engine.new_hash() {
+            RedisData::Hash(h) => assert_eq!(h.capacity(), 0),
+            _ => unreachable!(),
+        }
+        match engine.new_set() {
+            RedisData::Set(s) => assert!(s.capacity() >= 64),
+            _ => unreachable!(),
+        }
+        match engine.new_list() {
+            RedisData::List(l) => assert!(l.capacity() >= 32),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_sweep_covers_every_database() {
+        let mut engine = StorageEngine::builder().databases(4).build();
+        for db in 0..4 {
+            engine.select(db);
+            set_expired(&engine, "gone", RedisData::String(b"v".to_vec()));
+        }
+
+        engine.sweep_expired();
+        for db in 0..4 {
+            assert_eq!(engine.databases.get(db).data.len(), 0);
+            assert_eq!(engine.databases.get(db).expiration.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_move_key_keeps_deadline() {
+        let engine = StorageEngine::new();
+        let later = engine.now_ms() + 60_000;
+        engine.set("key", RedisData::String(b"v".to_vec()), Some(later));
+
+        assert!(engine.move_key("key", 1));
+        assert_eq!(engine.db().expiration.len(), 0);
+        let target = engine.at_db(1).unwrap();
+        assert_eq!(target.expire_time_query("key"), later as i64);
+        assert_eq!(target.db().expiration.len(), 1);
+    }
+
+    #[test]
+    fn test_namespaced_flush_cancels_expirations() {
+        let engine = StorageEngine::new();
+        let later = Some(engine.now_ms() + 60_000);
+        let ns = engine.namespace("ns:");
+        ns.set("a", RedisData::String(b"v".to_vec()), later);
+        engine.set("b", RedisData::String(b"v".to_vec()), later);
+
+        ns.flush();
+        assert_eq!(engine.db().expiration.len(), 1);
+        assert_eq!(engine.db().current_len.load(Ordering::Relaxed), 1);
+        assert_eq!(engine.keys(), vec![b"b".to_vec()]);
+    }
+
+    #[test]
+    fn test_scan_returns_each_key_once_despite_writes() {
+        let engine = StorageEngine::new();
+        for i in 0..100 {
+            engine.set(format!("key:{i}"), RedisData::String(b"v".to_vec()), None);
+        }
+
+        let mut seen = Vec::new();
+        let mut cursor = 0;
+        let mut added = 0;
+        loop {
+            let (next, keys) = engine.scan(cursor, 7);
+            seen.extend(keys);
+            // Writes between batches must not make the scan skip or repeat
+            // the keys that were there from the start.
+            engine.set(
+                format!("new:{added}"),
+                RedisData::String(b"v".to_vec()),
+                None,
+            );
+            added += 1;
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        let mut original: Vec<_> = seen.iter().filter(|key| key.starts_with(b"key:")).collect();
+        let count = original.len();
+        original.sort();
+        original.dedup();
+        assert_eq!(count, 100);
+        assert_eq!(original.len(), 100);
+    }
+
+    #[test]
+    fn test_update_keeps_expiration_index_in_sync() {
+        let engine = StorageEngine::new();
+        let later = Some(engine.now_ms() + 60_000);
+        engine.update("key", |current| {
+            assert!(current.is_none());
+            let value = StoredValue {
+                data: Arc::new(RedisData::String(b"v".to_vec())),
+                expire_at: later,
+            };
+            (Update::Set(value), ())
+        });
+        assert_eq!(engine.db().expiration.len(), 1);
+        assert_eq!(engine.db().current_len.load(Ordering::Relaxed), 1);
+
+        engine.update("key", |current| {
+            assert_eq!(current.unwrap().expire_at, later);
+            (Update::Delete, ())
+        });
+        assert_eq!(engine.db().expiration.len(), 0);
+        assert_eq!(engine.db().current_len.load(Ordering::Relaxed), 0);
+        assert!(engine.keys().is_empty());
     }
 }
@@ -1,86 +1,250 @@
 //! Key expiration management.
+//!
+//! Expired keys are removed in two ways. Every read and write checks the
+//! deadline of the key it touches and removes it if it has passed (lazy
+//! expiry). Keys that are never touched again are removed by the background
+//! sweeper, which runs an adaptive cycle modelled on Redis's active expiry:
+//!
+//! 1. Look at the next `keys_per_loop` deadlines and remove the ones that
+//!    have passed.
+//! 2. Repeat while more than `acceptable_stale` percent of the keys looked at
+//!    had expired, so a mass expiry is worked through over several loops.
+//! 3. Stop once the cycle has used its time budget, a percentage of the
+//!    sweep interval, and continue on the next tick.
+//!
+//! Redis samples volatile keys at random because its index is unordered.
+//! Ours is ordered by deadline, so the keys looked at are always the ones
+//! closest to expiring. The effort level (1 to 10, default 1) trades CPU for
+//! memory in the same way as Redis's `active-expire-effort`.
+//!
+//! Each database has its own index, so its deadlines move with it on
+//! `SWAPDB`. One sweeper serves them all, sharing each cycle's budget.
+//!
+//! Hash fields with a TTL are swept the same way. A second index per
+//! database holds each such hash under its earliest field deadline; when it
+//! comes due, the hash's expired fields are removed, the hash is deleted if
+//! none are left, and it is rescheduled at its next field deadline. That
+//! index may run early, as it is not updated when a field's TTL is removed
+//! or extended, but never late.
 
-use rustc_hash::FxHashSet;
-use std::collections::BTreeMap;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
-use tokio::time;
-
-/// Manages key expiration for the storage engine.
-/// 
-/// Uses a time-ordered map to efficiently find expired keys
-/// and a background task to periodically sweep and remove them.
-pub struct ExpirationManager {
-    expirations: Arc<Mutex<BTreeMap<Instant, FxHashSet<String>>>>,
-    sweep_interval: Duration,
+use rustc_hash::FxHashMap;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Deadlines looked at per loop at the lowest effort level.
+const KEYS_PER_LOOP: usize = 20;
+/// Percentage of expired keys per loop below which a cycle stops, at the
+/// lowest effort level.
+const ACCEPTABLE_STALE: usize = 10;
+/// Percentage of the sweep interval a cycle may use, at the lowest effort
+/// level.
+const CYCLE_TIME_PERCENT: u32 = 25;
+
+pub(crate) const MIN_EFFORT: u8 = 1;
+pub(crate) const MAX_EFFORT: u8 = 10;
+pub(crate) const DEFAULT_EFFORT: u8 = 1;
+
+/// Settings for the background sweeper's active expiry cycle.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SweepConfig {
+    pub(crate) sweep_interval: Duration,
+    pub(crate) effort: u8,
 }
 
-impl ExpirationManager {
-    pub fn new(sweep_interval_ms: u64) -> Self {
-        Self {
-            expirations: Arc::new(Mutex::new(BTreeMap::new())),
-            sweep_interval: Duration::from_millis(sweep_interval_ms),
+impl SweepConfig {
+    /// Returns the number of deadlines looked at per loop of a sweep cycle.
+    pub(crate) fn keys_per_loop(&self) -> usize {
+        KEYS_PER_LOOP + KEYS_PER_LOOP / 4 * self.extra_effort()
+    }
+
+    /// Returns the percentage of expired keys in a loop below which the
+    /// cycle stops.
+    pub(crate) fn acceptable_stale(&self) -> usize {
+        ACCEPTABLE_STALE - self.extra_effort()
+    }
+
+    /// Returns the longest a single sweep cycle may run.
+    pub(crate) fn cycle_budget(&self) -> Duration {
+        let percent = CYCLE_TIME_PERCENT + 2 * self.extra_effort() as u32;
+        self.sweep_interval * percent / 100
+    }
+
+    fn extra_effort(&self) -> usize {
+        self.effort.clamp(MIN_EFFORT, MAX_EFFORT) as usize - 1
+    }
+}
+
+/// Tracks scheduled key expirations for one database.
+///
+/// Deadlines are kept in a time-ordered set so the background sweeper can
+/// find every key whose deadline has passed, and in a key→deadline map so a
+/// key's entry can be found without scanning. Scheduling, cancelling and
+/// popping a due key are all O(log n).
+#[derive(Default)]
+pub(crate) struct ExpirationManager {
+    index: Mutex<ExpirationIndex>,
+}
+
+#[derive(Default)]
+struct ExpirationIndex {
+    by_deadline: BTreeSet<(u64, Vec<u8>)>,
+    by_key: FxHashMap<Vec<u8>, u64>,
+}
+
+impl ExpirationIndex {
+    fn insert(&mut self, key: Vec<u8>, at: u64) {
+        if let Some(previous) = self.by_key.insert(key.clone(), at) {
+            if previous == at {
+                return;
+            }
+            // Build the lookup tuple without a second allocation for the key.
+            let mut entry = (previous, key);
+            self.by_deadline.remove(&entry);
+            entry.0 = at;
+            self.by_deadline.insert(entry);
+        } else {
+            self.by_deadline.insert((at, key));
         }
     }
+}
 
-    pub fn schedule_expiration(&self, key: String, expire_at: Instant) {
-        let mut expirations = self.expirations.lock().unwrap();
-        let entry = expirations.entry(expire_at).or_insert_with(FxHashSet::default);
-        entry.insert(key);
+impl ExpirationManager {
+    /// Schedules `key` to expire at `at`, in Unix-epoch milliseconds,
+    /// replacing any earlier schedule.
+    pub(crate) fn schedule(&self, key: Vec<u8>, at: u64) {
+        self.index.lock().unwrap().insert(key, at);
     }
 
-    pub fn cancel_expiration(&self, key: &str) {
-        let mut expirations = self.expirations.lock().unwrap();
-        for (_, keys) in expirations.iter_mut() {
-            keys.remove(key);
+    /// Schedules `key` to expire at `at` unless it is already scheduled to
+    /// expire earlier.
+    pub(crate) fn schedule_earliest(&self, key: Vec<u8>, at: u64) {
+        let mut index = self.index.lock().unwrap();
+        if index
+            .by_key
+            .get(&key)
+            .is_some_and(|&previous| previous <= at)
+        {
+            return;
         }
+        index.insert(key, at);
     }
 
-    pub async fn start_sweep_task<F>(&self, mut remove_fn: F)
-    where
-        F: FnMut(&str) + Send + Sync + 'static,
-    {
-        let mut interval = time::interval(self.sweep_interval);
-        loop {
-            interval.tick().await;
-            let now = Instant::now();
-
-            let mut expirations = self.expirations.lock().unwrap();
-            let mut keys_to_remove = Vec::new();
-
-            let expired_times: Vec<Instant> = expirations
-                .iter()
-                .filter(|(t, _)| **t <= now)
-                .map(|(t, _)| *t)
-                .collect();
-
-            for t in expired_times {
-                if let Some(keys) = expirations.remove(&t) {
-                    for key in keys {
-                        keys_to_remove.push(key);
-                    }
-                }
-            }
-            drop(expirations);
+    /// Removes any scheduled expiration for `key`.
+    pub(crate) fn cancel(&self, key: &[u8]) {
+        let mut index = self.index.lock().unwrap();
+        if let Some((key, at)) = index.by_key.remove_entry(key) {
+            index.by_deadline.remove(&(at, key));
+        }
+    }
 
-            for key in keys_to_remove {
-                self.cancel_expiration(&key);
-                remove_fn(&key);
+    /// Looks at up to `limit` of the earliest deadlines and removes and
+    /// returns the keys whose deadline is at or before `now`.
+    ///
+    /// Also returns how many deadlines were looked at.
+    pub(crate) fn pop_due(&self, now: u64, limit: usize) -> (Vec<Vec<u8>>, usize) {
+        let mut index = self.index.lock().unwrap();
+        let sampled = limit.min(index.by_deadline.len());
+        let mut due = Vec::new();
+        while due.len() < limit {
+            match index.by_deadline.first() {
+                Some((at, _)) if *at <= now => {}
+                _ => break,
             }
+            let (_, key) = index.by_deadline.pop_first().unwrap();
+            index.by_key.remove(&key);
+            due.push(key);
         }
+        (due, sampled)
     }
 
-    pub fn clear_all(&self) {
-        let mut expirations = self.expirations.lock().unwrap();
-        expirations.clear();
+    /// Returns the number of keys with a scheduled expiration.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.index.lock().unwrap().by_key.len()
+    }
+
+    pub(crate) fn clear(&self) {
+        let mut index = self.index.lock().unwrap();
+        index.by_deadline.clear();
+        index.by_key.clear();
     }
 }
 
-impl Clone for ExpirationManager {
-    fn clone(&self) -> Self {
-        Self {
-            expirations: Arc::clone(&self.expirations),
-            sweep_interval: self.sweep_interval,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reschedule_replaces_deadline() {
+        let manager = ExpirationManager::default();
+        let now = 1_000_000;
+        manager.schedule(b"key".to_vec(), now);
+        manager.schedule(b"key".to_vec(), now + 60_000);
+        assert_eq!(manager.len(), 1);
+        assert!(manager.pop_due(now, 20).0.is_empty());
+    }
+
+    #[test]
+    fn test_schedule_earliest_keeps_earlier_deadline() {
+        let manager = ExpirationManager::default();
+        let now = 1_000_000;
+        manager.schedule_earliest(b"key".to_vec(), now + 60_000);
+        manager.schedule_earliest(b"key".to_vec(), now);
+        manager.schedule_earliest(b"key".to_vec(), now + 30_000);
+        assert_eq!(manager.len(), 1);
+        assert_eq!(manager.pop_due(now, 20), (vec![b"key".to_vec()], 1));
+    }
+
+    #[test]
+    fn test_cancel_removes_schedule() {
+        let manager = ExpirationManager::default();
+        let now = 1_000_000;
+        manager.schedule(b"a".to_vec(), now);
+        manager.schedule(b"b".to_vec(), now);
+        manager.cancel(b"a");
+        assert_eq!(manager.len(), 1);
+        assert_eq!(manager.pop_due(now, 20), (vec![b"b".to_vec()], 1));
+    }
+
+    #[test]
+    fn test_pop_due_returns_keys_in_deadline_order() {
+        let manager = ExpirationManager::default();
+        let now = 1_000_000;
+        manager.schedule(b"late".to_vec(), now + 60_000);
+        manager.schedule(b"second".to_vec(), now - 1_000);
+        manager.schedule(b"first".to_vec(), now - 2_000);
+        assert_eq!(
+            manager.pop_due(now, 20),
+            (vec![b"first".to_vec(), b"second".to_vec()], 3)
+        );
+        assert_eq!(manager.len(), 1);
+    }
+
+    #[test]
+    fn test_pop_due_respects_limit() {
+        let manager = ExpirationManager::default();
+        let now = 1_000_000;
+        for i in 0..5u8 {
+            manager.schedule(vec![i], now);
         }
+        assert_eq!(manager.pop_due(now, 2), (vec![vec![0], vec![1]], 2));
+        assert_eq!(manager.len(), 3);
+    }
+
+    #[test]
+    fn test_effort_scales_cycle_parameters() {
+        let mut config = SweepConfig {
+            sweep_interval: Duration::from_millis(100),
+            effort: DEFAULT_EFFORT,
+        };
+        assert_eq!(config.keys_per_loop(), 20);
+        assert_eq!(config.acceptable_stale(), 10);
+        assert_eq!(config.cycle_budget(), Duration::from_millis(25));
+
+        config.effort = MAX_EFFORT;
+        assert_eq!(config.keys_per_loop(), 65);
+        assert_eq!(config.acceptable_stale(), 1);
+        assert_eq!(config.cycle_budget(), Duration::from_millis(43));
     }
 }
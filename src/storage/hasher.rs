@@ -0,0 +1,85 @@
+//! Hash functions for the keyspace.
+
+use rustc_hash::FxHasher;
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::hash::{BuildHasher, Hasher};
+
+/// The hash function used to place keys in the keyspace.
+///
+/// Collections stored under a key (hashes, sets and so on) always use
+/// FxHash; this only picks the hasher of the top-level key map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HasherKind {
+    /// FxHash: very fast, but predictable. Keys chosen by an attacker can
+    /// make every key land in the same bucket.
+    #[default]
+    Fx,
+    /// SipHash with a random seed, as used by `std::collections::HashMap`.
+    /// Slower, but resistant to hash flooding. Use it when keys come from
+    /// untrusted input.
+    SipHash,
+}
+
+/// The [`BuildHasher`] behind a [`HasherKind`].
+#[derive(Debug, Clone)]
+pub(crate) enum KeyHasher {
+    Fx,
+    SipHash(RandomState),
+}
+
+impl From<HasherKind> for KeyHasher {
+    fn from(kind: HasherKind) -> Self {
+        match kind {
+            HasherKind::Fx => KeyHasher::Fx,
+            HasherKind::SipHash => KeyHasher::SipHash(RandomState::new()),
+        }
+    }
+}
+
+impl Default for KeyHasher {
+    fn default() -> Self {
+        HasherKind::default().into()
+    }
+}
+
+impl BuildHasher for KeyHasher {
+    type Hasher = KeyHasherState;
+
+    fn build_hasher(&self) -> KeyHasherState {
+        match self {
+            KeyHasher::Fx => KeyHasherState::Fx(FxHasher::default()),
+            KeyHasher::SipHash(state) => KeyHasherState::SipHash(state.build_hasher()),
+        }
+    }
+}
+
+pub(crate) enum KeyHasherState {
+    Fx(FxHasher),
+    SipHash(DefaultHasher),
+}
+
+impl Hasher for KeyHasherState {
+    #[inline]
+    fn finish(&self) -> u64 {
+        match self {
+            KeyHasherState::Fx(h) => h.finish(),
+            KeyHasherState::SipHash(h) => h.finish(),
+        }
+    }
+
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        match self {
+            KeyHasherState::Fx(h) => h.write(bytes),
+            KeyHasherState::SipHash(h) => h.write(bytes),
+        }
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        match self {
+            KeyHasherState::Fx(h) => h.write_usize(i),
+            KeyHasherState::SipHash(h) => h.write_usize(i),
+        }
+    }
+}
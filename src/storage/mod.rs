@@ -1,12 +1,14 @@
 //! Storage engine and related types for the Redis-like store.
 
-pub mod types;
+mod builder;
+mod database;
 pub mod engine;
-pub mod expire;
-pub mod config;
-pub mod memory;
+pub(crate) mod expire;
+mod hasher;
+pub mod types;
 
-pub use types::{RedisData, StoredValue};
+pub use builder::StorageEngineBuilder;
+pub(crate) use database::Update;
 pub use engine::StorageEngine;
-pub use expire::ExpirationManager;
-pub use config::{MaxMemoryPolicy, StorageConfig};
+pub use hasher::HasherKind;
+pub use types::{ExpireOption, HashValue, RedisData, StoredValue, StreamEntry};
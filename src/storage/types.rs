@@ -1,63 +1,324 @@
 //! Internal data types for the storage engine.
 
 use rustc_hash::{FxHashMap, FxHashSet};
+use std::borrow::Cow;
 use std::collections::{BTreeMap, VecDeque};
-use std::time::Instant;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::clock::unix_millis;
+
+/// Represents a single entry in a Redis stream.
+pub type StreamEntry = (Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>);
 
 /// Internal data types stored in the engine.
 ///
 /// These represent the actual data structures that can be stored,
 /// as opposed to the RESP protocol [`Value`](crate::Value) types.
 #[derive(Debug, Clone)]
+#[allow(missing_docs)]
 pub enum RedisData {
     String(Vec<u8>),
+    /// A string holding a 64-bit integer, as left by `INCR` and friends, so
+    /// that counters are not re-parsed on every increment.
+    Int(i64),
     List(VecDeque<Vec<u8>>),
     Set(FxHashSet<Vec<u8>>),
-    Hash(FxHashMap<Vec<u8>, Vec<u8>>),
+    Hash(HashValue),
     ZSet(BTreeMap<Vec<u8>, f64>),
+    Stream(Vec<StreamEntry>),
+}
+
+impl RedisData {
+    /// Returns the name Redis reports for this type in `TYPE` replies.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RedisData::String(_) | RedisData::Int(_) => "string",
+            RedisData::List(_) => "list",
+            RedisData::Set(_) => "set",
+            RedisData::Hash(_) => "hash",
+            RedisData::ZSet(_) => "zset",
+            RedisData::Stream(_) => "stream",
+        }
+    }
+
+    /// Returns the bytes of a string value, or `None` for the other types.
+    pub(crate) fn as_string(&self) -> Option<Cow<'_, [u8]>> {
+        match self {
+            RedisData::String(s) => Some(Cow::Borrowed(s)),
+            RedisData::Int(n) => Some(Cow::Owned(n.to_string().into_bytes())),
+            _ => None,
+        }
+    }
+
+    /// Returns the bytes of a string value for modification, expanding the
+    /// integer encoding, or `None` for the other types.
+    pub(crate) fn as_string_mut(&mut self) -> Option<&mut Vec<u8>> {
+        if let RedisData::Int(n) = self {
+            *self = RedisData::String(n.to_string().into_bytes());
+        }
+        match self {
+            RedisData::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for a collection with nothing left in it, which Redis
+    /// never keeps. A hash whose fields have all expired by `now` counts as
+    /// empty.
+    pub(crate) fn is_empty_collection(&self, now: u64) -> bool {
+        match self {
+            RedisData::String(_) | RedisData::Int(_) => false,
+            RedisData::List(l) => l.is_empty(),
+            RedisData::Set(s) => s.is_empty(),
+            RedisData::Hash(h) => h.len(now) == 0,
+            RedisData::ZSet(z) => z.is_empty(),
+            RedisData::Stream(s) => s.is_empty(),
+        }
+    }
+}
+
+/// The fields of a hash, and the deadlines of those that expire (Redis 7.4
+/// field TTLs).
+///
+/// An expired field stays in place until it is written, deleted or removed
+/// by the background sweeper, so the accessors that take `now`, in
+/// Unix-epoch milliseconds, skip it.
+#[derive(Debug, Clone, Default)]
+pub struct HashValue {
+    fields: FxHashMap<Vec<u8>, Vec<u8>>,
+    /// Deadlines in Unix-epoch milliseconds. Only fields with a TTL have one.
+    deadlines: FxHashMap<Vec<u8>, u64>,
+}
+
+impl HashValue {
+    /// Returns an empty hash with room for `capacity` fields.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            fields: FxHashMap::with_capacity_and_hasher(capacity, Default::default()),
+            deadlines: FxHashMap::default(),
+        }
+    }
+
+    /// Returns every field and value, including expired fields that have not
+    /// been removed yet.
+    pub fn fields(&self) -> &FxHashMap<Vec<u8>, Vec<u8>> {
+        &self.fields
+    }
+
+    /// Returns the deadline of `field`, in Unix-epoch milliseconds, if it
+    /// has one.
+    pub fn deadline(&self, field: &[u8]) -> Option<u64> {
+        self.deadlines.get(field).copied()
+    }
+
+    /// Returns the earliest field deadline, if any field has one.
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.deadlines.values().min().copied()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn capacity(&self) -> usize {
+        self.fields.capacity()
+    }
+
+    fn is_expired(&self, field: &[u8], now: u64) -> bool {
+        self.deadlines.get(field).is_some_and(|&at| now >= at)
+    }
+
+    /// Returns the value of `field` unless it is missing or expired.
+    pub(crate) fn get(&self, field: &[u8], now: u64) -> Option<&Vec<u8>> {
+        self.fields
+            .get(field)
+            .filter(|_| !self.is_expired(field, now))
+    }
+
+    /// Like [`get`](Self::get), for modification. The field keeps its TTL.
+    pub(crate) fn get_mut(&mut self, field: &[u8], now: u64) -> Option<&mut Vec<u8>> {
+        if self.is_expired(field, now) {
+            return None;
+        }
+        self.fields.get_mut(field)
+    }
+
+    pub(crate) fn contains(&self, field: &[u8], now: u64) -> bool {
+        self.get(field, now).is_some()
+    }
+
+    /// Returns the number of live fields.
+    pub(crate) fn len(&self, now: u64) -> usize {
+        let expired = self.deadlines.values().filter(|&&at| now >= at).count();
+        self.fields.len() - expired
+    }
+
+    /// Returns `true` if the hash has fields and every one has expired by
+    /// `now`.
+    pub(crate) fn all_expired(&self, now: u64) -> bool {
+        // Only fields with a TTL can expire, so compare counts first.
+        !self.fields.is_empty()
+            && self.fields.len() == self.deadlines.len()
+            && self.deadlines.values().all(|&at| now >= at)
+    }
+
+    /// Returns `true` if there are no fields at all, live or expired.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Iterates over the live fields and their values.
+    pub(crate) fn iter(&self, now: u64) -> impl Iterator<Item = (&Vec<u8>, &Vec<u8>)> {
+        self.fields
+            .iter()
+            .filter(move |(field, _)| !self.is_expired(field, now))
+    }
+
+    /// Sets `field` to `value`, clearing its TTL as `HSET` does, and returns
+    /// the previous value if the field was live.
+    pub(crate) fn insert(&mut self, field: Vec<u8>, value: Vec<u8>, now: u64) -> Option<Vec<u8>> {
+        let expired = self.deadlines.remove(&field).is_some_and(|at| now >= at);
+        self.fields.insert(field, value).filter(|_| !expired)
+    }
+
+    /// Removes `field` and returns its value if it was live.
+    pub(crate) fn remove(&mut self, field: &[u8], now: u64) -> Option<Vec<u8>> {
+        let expired = self.deadlines.remove(field).is_some_and(|at| now >= at);
+        self.fields.remove(field).filter(|_| !expired)
+    }
+
+    /// Sets the deadline of an existing field.
+    pub(crate) fn set_deadline(&mut self, field: &[u8], at: u64) {
+        if let Some(deadline) = self.deadlines.get_mut(field) {
+            *deadline = at;
+        } else if self.fields.contains_key(field) {
+            self.deadlines.insert(field.to_vec(), at);
+        }
+    }
+
+    /// Clears the TTL of `field` and returns whether it had one.
+    pub(crate) fn persist(&mut self, field: &[u8]) -> bool {
+        self.deadlines.remove(field).is_some()
+    }
+
+    /// Removes every expired field and returns how many there were.
+    pub(crate) fn remove_expired(&mut self, now: u64) -> usize {
+        let before = self.fields.len();
+        let fields = &mut self.fields;
+        self.deadlines.retain(|field, &mut at| {
+            let live = now < at;
+            if !live {
+                fields.remove(field);
+            }
+            live
+        });
+        before - self.fields.len()
+    }
+}
+
+impl From<FxHashMap<Vec<u8>, Vec<u8>>> for HashValue {
+    fn from(fields: FxHashMap<Vec<u8>, Vec<u8>>) -> Self {
+        Self {
+            fields,
+            deadlines: FxHashMap::default(),
+        }
+    }
+}
+
+/// The condition under which `EXPIRE` and related commands change a key's
+/// deadline (Redis 7 and later).
+///
+/// A key without a deadline counts as having an infinite one, so [`GT`]
+/// never applies to it and [`LT`] always does. [`xx`](Self::xx) combines
+/// with either comparison to also require an existing deadline.
+///
+/// [`GT`]: ExpireOption::GT
+/// [`LT`]: ExpireOption::LT
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExpireOption {
+    pub(crate) condition: ExpireCondition,
+    pub(crate) xx: bool,
+}
+
+/// The `NX`, `GT` or `LT` part of an [`ExpireOption`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ExpireCondition {
+    #[default]
+    Always,
+    Nx,
+    Gt,
+    Lt,
+}
+
+impl ExpireOption {
+    /// Always set the deadline.
+    pub const NONE: Self = Self::new(ExpireCondition::Always);
+    /// Only set the deadline if the key has none.
+    pub const NX: Self = Self::new(ExpireCondition::Nx);
+    /// Only set the deadline if the key already has one.
+    pub const XX: Self = Self::NONE.xx(true);
+    /// Only set the deadline if it is later than the current one.
+    pub const GT: Self = Self::new(ExpireCondition::Gt);
+    /// Only set the deadline if it is earlier than the current one.
+    pub const LT: Self = Self::new(ExpireCondition::Lt);
+
+    const fn new(condition: ExpireCondition) -> Self {
+        Self {
+            condition,
+            xx: false,
+        }
+    }
+
+    /// Also requires the key to already have a deadline, as `XX` does when
+    /// given together with `GT` or `LT`.
+    pub const fn xx(mut self, xx: bool) -> Self {
+        self.xx = xx;
+        self
+    }
+
+    /// Returns `true` if a key whose deadline is `current` may be given the
+    /// deadline `new`. Both are Unix-epoch milliseconds.
+    pub(crate) fn allows(self, current: Option<u64>, new: i64) -> bool {
+        if self.xx && current.is_none() {
+            return false;
+        }
+        match self.condition {
+            ExpireCondition::Always => true,
+            ExpireCondition::Nx => current.is_none(),
+            ExpireCondition::Gt => current.is_some_and(|at| new > at as i64),
+            ExpireCondition::Lt => current.is_none_or(|at| new < at as i64),
+        }
+    }
 }
 
 /// A value stored in the storage engine with optional expiration.
+///
+/// `expire_at` is the deadline in milliseconds since the Unix epoch.
 #[derive(Debug, Clone)]
+#[allow(missing_docs)]
 pub struct StoredValue {
-    pub data: RedisData,
-    pub expire_at: Option<Instant>,
+    pub data: Arc<RedisData>,
+    pub expire_at: Option<u64>,
 }
 
 impl StoredValue {
+    /// Returns `true` if the value's deadline has passed by the system clock.
+    ///
+    /// The engine itself checks against its own [`Clock`](crate::clock::Clock)
+    /// with [`is_expired_at`](Self::is_expired_at).
     pub fn is_expired(&self) -> bool {
-        match self.expire_at {
-            Some(at) => Instant::now() >= at,
-            None => false,
-        }
+        self.is_expired_at(unix_millis(SystemTime::now()))
     }
 
-    pub fn estimated_size(&self) -> usize {
-        self.data.estimated_size()
+    /// Returns `true` if the value's deadline is at or before `now`, given in
+    /// milliseconds since the Unix epoch.
+    pub fn is_expired_at(&self, now: u64) -> bool {
+        self.expire_at.is_some_and(|at| now >= at)
     }
-}
 
-impl RedisData {
-    pub fn estimated_size(&self) -> usize {
-        match self {
-            RedisData::String(data) => data.len(),
-            RedisData::List(deque) => {
-                deque.iter().map(|v| v.len()).sum::<usize>()
-                    + std::mem::size_of::<Vec<u8>>() * deque.capacity()
-            }
-            RedisData::Set(set) => {
-                set.iter().map(|v| v.len()).sum::<usize>()
-                    + std::mem::size_of::<Vec<u8>>() * set.capacity()
-            }
-            RedisData::Hash(map) => {
-                map.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>()
-                    + std::mem::size_of::<(Vec<u8>, Vec<u8>)>() * map.capacity()
-            }
-            RedisData::ZSet(map) => {
-                map.iter().map(|(k, _)| k.len()).sum::<usize>()
-                    + std::mem::size_of::<(Vec<u8>, f64)>() * map.capacity()
-                    + std::mem::size_of::<f64>()
-            }
-        }
+    /// Returns `true` if the key no longer exists at `now`: its deadline has
+    /// passed, or it is a hash whose fields have all expired, which Redis
+    /// deletes along with its last field.
+    pub(crate) fn is_gone_at(&self, now: u64) -> bool {
+        self.is_expired_at(now)
+            || matches!(&*self.data, RedisData::Hash(hash) if hash.all_expired(now))
     }
 }
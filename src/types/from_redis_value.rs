@@ -12,12 +12,15 @@ use crate::error::{RedisError, RedisResult};
 ///
 /// - `String`: Converts from Redis strings and integers
 /// - `Vec<u8>`: Converts from Redis strings (raw bytes)
-/// - `i64`, `u64`, `isize`, `usize`: Converts from Redis integers
+/// - `i64`, `u64`, `isize`, `usize`: Converts from Redis integers and strings
+/// - `f64`: Converts from Redis integers and numeric strings
 /// - `bool`: Converts from Redis booleans and integers
-/// - `()`: Converts from Redis null/okay
-/// - `Option<T>`: Converts null to None, otherwise Some(T)
+/// - `()`: Ignores the reply, whatever it is
+/// - `Option<T>`: Converts null to `None`, otherwise `Some(T)`
 /// - `Vec<T>`: Converts from Redis arrays
+/// - `(A, B)`: Converts from Redis arrays of two elements
 /// - `Value`: Returns the value as-is
+#[allow(missing_docs)]
 pub trait FromRedisValue: Sized {
     fn from_redis_value(v: Value) -> RedisResult<Self>;
 }
@@ -25,10 +28,10 @@ pub trait FromRedisValue: Sized {
 impl FromRedisValue for String {
     fn from_redis_value(v: Value) -> RedisResult<Self> {
         match v {
-            Value::String(s) => Ok(String::from_utf8(s).map_err(|_| RedisError::ParseError)?),
+            Value::String(s) => String::from_utf8(s).map_err(|_| RedisError::ParseError),
             Value::Int(n) => Ok(n.to_string()),
+            Value::Null => Ok(String::new()),
             Value::Okay => Ok("OK".to_string()),
-            Value::Bool(b) => Ok(b.to_string()),
             _ => Err(RedisError::ParseError),
         }
     }
@@ -48,9 +51,9 @@ impl FromRedisValue for i64 {
         match v {
             Value::Int(n) => Ok(n),
             Value::String(s) => String::from_utf8(s)
-                .map_err(|_| RedisError::ParseError)?
-                .parse::<i64>()
-                .map_err(|_| RedisError::ParseError),
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or(RedisError::ParseError),
             Value::Bool(b) => Ok(if b { 1 } else { 0 }),
             _ => Err(RedisError::ParseError),
         }
@@ -60,21 +63,34 @@ impl FromRedisValue for i64 {
 impl FromRedisValue for u64 {
     fn from_redis_value(v: Value) -> RedisResult<Self> {
         let n: i64 = FromRedisValue::from_redis_value(v)?;
-        Ok(n as u64)
+        u64::try_from(n).map_err(|_| RedisError::ParseError)
     }
 }
 
 impl FromRedisValue for isize {
     fn from_redis_value(v: Value) -> RedisResult<Self> {
         let n: i64 = FromRedisValue::from_redis_value(v)?;
-        Ok(n as isize)
+        isize::try_from(n).map_err(|_| RedisError::ParseError)
     }
 }
 
 impl FromRedisValue for usize {
     fn from_redis_value(v: Value) -> RedisResult<Self> {
         let n: i64 = FromRedisValue::from_redis_value(v)?;
-        Ok(n as usize)
+        usize::try_from(n).map_err(|_| RedisError::ParseError)
+    }
+}
+
+impl FromRedisValue for f64 {
+    fn from_redis_value(v: Value) -> RedisResult<Self> {
+        match v {
+            Value::Int(n) => Ok(n as f64),
+            Value::String(s) => std::str::from_utf8(&s)
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .ok_or(RedisError::ParseError),
+            _ => Err(RedisError::ParseError),
+        }
     }
 }
 
@@ -83,20 +99,26 @@ impl FromRedisValue for bool {
         match v {
             Value::Bool(b) => Ok(b),
             Value::Int(n) => Ok(n != 0),
-            Value::String(s) => Ok(!s.is_empty()),
+            Value::String(s) => {
+                let s_str = String::from_utf8(s).map_err(|_| RedisError::ParseError)?;
+                Ok(s_str == "1" || s_str.eq_ignore_ascii_case("true"))
+            }
             Value::Null => Ok(false),
+            Value::Okay => Ok(true),
             _ => Err(RedisError::ParseError),
         }
     }
 }
 
 impl FromRedisValue for () {
+    fn from_redis_value(_: Value) -> RedisResult<Self> {
+        Ok(())
+    }
+}
+
+impl FromRedisValue for Value {
     fn from_redis_value(v: Value) -> RedisResult<Self> {
-        match v {
-            Value::Null => Ok(()),
-            Value::Okay => Ok(()),
-            _ => Err(RedisError::ParseError),
-        }
+        Ok(v)
     }
 }
 
@@ -104,7 +126,7 @@ impl<T: FromRedisValue> FromRedisValue for Option<T> {
     fn from_redis_value(v: Value) -> RedisResult<Self> {
         match v {
             Value::Null => Ok(None),
-            v => Ok(Some(T::from_redis_value(v)?)),
+            v => T::from_redis_value(v).map(Some),
         }
     }
 }
@@ -119,8 +141,16 @@ impl<T: FromRedisValue> FromRedisValue for Vec<T> {
     }
 }
 
-impl FromRedisValue for Value {
+impl<A: FromRedisValue, B: FromRedisValue> FromRedisValue for (A, B) {
     fn from_redis_value(v: Value) -> RedisResult<Self> {
-        Ok(v)
+        match v {
+            Value::Array(items) if items.len() == 2 => {
+                let mut items = items.into_iter();
+                let a = A::from_redis_value(items.next().unwrap())?;
+                let b = B::from_redis_value(items.next().unwrap())?;
+                Ok((a, b))
+            }
+            _ => Err(RedisError::ParseError),
+        }
     }
 }
@@ -1,6 +1,7 @@
 //! Trait for converting Rust values into Redis command arguments.
 
 use super::Value;
+use smallvec::smallvec;
 
 /// A trait for converting values into Redis command arguments.
 ///
@@ -10,105 +11,114 @@ use super::Value;
 /// # Implementors
 ///
 /// - `String`, `&str`: Converts to Redis string
-/// - `Vec<u8>`, `&[u8]`: Converts to Redis string (raw bytes)
-/// - `i64`, `u64`, `isize`, `usize`: Converts to Redis integer
+/// - `Vec<u8>`: Converts to Redis string (raw bytes)
+/// - `i32`, `u32`, `i64`, `u64`, `isize`, `usize`: Converts to Redis integer
+/// - `f64`: Converts to Redis string (decimal representation)
 /// - `bool`: Converts to Redis boolean
-/// - `Option<T>`: Converts None to null, Some to the inner value
-/// - `Vec<T>`: Converts each element
-/// - Tuples up to 8 elements: Flattens all elements
+/// - `Option<T>`: Converts `None` to null, `Some` to the inner value
+/// - `[T]`, `[T; N]`, `Vec<T>`: One argument per element, for commands such
+///   as `DEL` that take many keys
+/// - `&T`: Delegates to `T`
+#[allow(missing_docs)]
 pub trait ToRedisArgs {
-    fn to_redis_args(&self) -> Vec<Value>;
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]>;
 }
 
 impl ToRedisArgs for String {
-    fn to_redis_args(&self) -> Vec<Value> {
-        vec![self.as_bytes().to_vec().into()]
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::String(self.as_bytes().to_vec())]
     }
 }
 
 impl ToRedisArgs for &str {
-    fn to_redis_args(&self) -> Vec<Value> {
-        vec![self.as_bytes().to_vec().into()]
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::String(self.as_bytes().to_vec())]
+    }
+}
+
+impl<T: ToRedisArgs + ?Sized> ToRedisArgs for &T {
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        (*self).to_redis_args()
     }
 }
 
 impl ToRedisArgs for Vec<u8> {
-    fn to_redis_args(&self) -> Vec<Value> {
-        vec![self.clone().into()]
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::String(self.clone())]
     }
 }
 
-impl ToRedisArgs for &[u8] {
-    fn to_redis_args(&self) -> Vec<Value> {
-        vec![self.to_vec().into()]
+impl ToRedisArgs for i32 {
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::Int(*self as i64)]
+    }
+}
+
+impl ToRedisArgs for u32 {
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::Int(*self as i64)]
     }
 }
 
 impl ToRedisArgs for i64 {
-    fn to_redis_args(&self) -> Vec<Value> {
-        vec![(*self).into()]
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::Int(*self)]
     }
 }
 
 impl ToRedisArgs for u64 {
-    fn to_redis_args(&self) -> Vec<Value> {
-        vec![(*self).into()]
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::Int(*self as i64)]
     }
 }
 
 impl ToRedisArgs for isize {
-    fn to_redis_args(&self) -> Vec<Value> {
-        vec![(*self as i64).into()]
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::Int(*self as i64)]
     }
 }
 
 impl ToRedisArgs for usize {
-    fn to_redis_args(&self) -> Vec<Value> {
-        vec![(*self as i64).into()]
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::Int(*self as i64)]
+    }
+}
+
+impl ToRedisArgs for f64 {
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::String(self.to_string().into_bytes())]
     }
 }
 
 impl ToRedisArgs for bool {
-    fn to_redis_args(&self) -> Vec<Value> {
-        vec![(*self).into()]
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        smallvec![Value::Bool(*self)]
     }
 }
 
 impl<T: ToRedisArgs> ToRedisArgs for Option<T> {
-    fn to_redis_args(&self) -> Vec<Value> {
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
         match self {
             Some(v) => v.to_redis_args(),
-            None => vec![Value::Null],
+            None => smallvec![Value::Null],
         }
     }
 }
 
-impl<T: ToRedisArgs> ToRedisArgs for Vec<T> {
-    fn to_redis_args(&self) -> Vec<Value> {
-        self.iter().flat_map(|v| v.to_redis_args()).collect()
+impl<T: ToRedisArgs> ToRedisArgs for [T] {
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        self.iter().flat_map(ToRedisArgs::to_redis_args).collect()
     }
 }
 
-macro_rules! impl_tuple {
-    ($($idx:tt $t:ident),*) => {
-        impl<$($t: ToRedisArgs),*> ToRedisArgs for ($($t,)*) {
-            fn to_redis_args(&self) -> Vec<Value> {
-                let mut args = Vec::new();
-                $(
-                    args.extend(self.$idx.to_redis_args());
-                )*
-                args
-            }
-        }
-    };
-}
-
-impl_tuple!();
-impl_tuple!(0 T1);
-impl_tuple!(0 T1, 1 T2);
-impl_tuple!(0 T1, 1 T2, 2 T3);
-impl_tuple!(0 T1, 1 T2, 2 T3, 3 T4);
-impl_tuple!(0 T1, 1 T2, 2 T3, 3 T4, 4 T5);
-impl_tuple!(0 T1, 1 T2, 2 T3, 3 T4, 4 T5, 5 T6);
-impl_tuple!(0 T1, 1 T2, 2 T3, 3 T4, 4 T5, 5 T6, 6 T7);
-impl_tuple!(0 T1, 1 T2, 2 T3, 3 T4, 4 T5, 5 T6, 6 T7, 7 T8);
+impl<T: ToRedisArgs, const N: usize> ToRedisArgs for [T; N] {
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        self[..].to_redis_args()
+    }
+}
+
+impl<T: ToRedisArgs> ToRedisArgs for Vec<T> {
+    fn to_redis_args(&self) -> smallvec::SmallVec<[Value; 1]> {
+        self[..].to_redis_args()
+    }
+}
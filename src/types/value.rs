@@ -5,6 +5,7 @@
 /// This enum mirrors the RESP (REdis Serialization Protocol) types
 /// supported by Redis.
 #[derive(Debug, Clone, PartialEq)]
+#[allow(missing_docs)]
 pub enum Value {
     Null,
     Int(i64),
@@ -40,12 +41,6 @@ impl From<i64> for Value {
     }
 }
 
-impl From<u64> for Value {
-    fn from(n: u64) -> Self {
-        Value::Int(n as i64)
-    }
-}
-
 impl From<bool> for Value {
     fn from(b: bool) -> Self {
         Value::Bool(b)
@@ -1,4 +1,6 @@
-use not_redis::{Client, RedisResult};
+use not_redis::clock::{Clock, TestClock};
+use not_redis::{blocking, Client, RedisResult, StorageEngine};
+use std::time::UNIX_EPOCH;
 
 async fn setup_client() -> Client {
     let client = Client::new();
@@ -6,8 +8,24 @@ async fn setup_client() -> Client {
     client
 }
 
+/// Returns a client whose storage runs on a [`TestClock`], and the clock.
+fn client_with_clock() -> (blocking::Client, TestClock) {
+    let clock = TestClock::new();
+    let storage = StorageEngine::new().with_clock(clock.clone());
+    (blocking::Client::from_storage(storage), clock)
+}
+
+/// Returns the time on `clock` in milliseconds since the Unix epoch.
+fn now_millis(clock: &TestClock) -> i64 {
+    clock
+        .system_time()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
 async fn cleanup(client: &mut Client) {
-    let _ = client.flushdb().await;
+    let _: Result<(), _> = client.flushdb().await;
 }
 
 mod string_tests {
@@ -16,7 +34,7 @@ mod string_tests {
     #[tokio::test]
     async fn test_set_and_get() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
         let result: String = client.get("key1").await.unwrap();
         assert_eq!(result, "value1");
         cleanup(&mut client).await;
@@ -25,8 +43,8 @@ mod string_tests {
     #[tokio::test]
     async fn test_set_overwrite() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
-        client.set("key1", "value2").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key1", "value2").await.unwrap();
         let result: String = client.get("key1").await.unwrap();
         assert_eq!(result, "value2");
         cleanup(&mut client).await;
@@ -43,10 +61,10 @@ mod string_tests {
     #[tokio::test]
     async fn test_del_single() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
         let deleted: i64 = client.del("key1").await.unwrap();
         assert_eq!(deleted, 1);
-        let exists: bool = client.exists("key1").await.unwrap();
+        let exists = client.exists::<_, i64>("key1").await.unwrap() == 1;
         assert!(!exists);
         cleanup(&mut client).await;
     }
@@ -62,8 +80,8 @@ mod string_tests {
     #[tokio::test]
     async fn test_exists_true() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
-        let exists: bool = client.exists("key1").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
+        let exists = client.exists::<_, i64>("key1").await.unwrap() == 1;
         assert!(exists);
         cleanup(&mut client).await;
     }
@@ -71,7 +89,7 @@ mod string_tests {
     #[tokio::test]
     async fn test_exists_false() {
         let mut client = setup_client().await;
-        let exists: bool = client.exists("nonexistent").await.unwrap();
+        let exists = client.exists::<_, i64>("nonexistent").await.unwrap() == 1;
         assert!(!exists);
         cleanup(&mut client).await;
     }
@@ -79,10 +97,10 @@ mod string_tests {
     #[tokio::test]
     async fn test_expire_basic() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
         let result: bool = client.expire("key1", 60).await.unwrap();
         assert!(result);
-        let ttl: i64 = client.ttl("key1").await.unwrap();
+        let ttl = client.ttl("key1").await.unwrap().raw();
         assert!(ttl > 0 && ttl <= 60);
         cleanup(&mut client).await;
     }
@@ -90,9 +108,9 @@ mod string_tests {
     #[tokio::test]
     async fn test_ttl_positive() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
         client.expire("key1", 120).await.unwrap();
-        let ttl: i64 = client.ttl("key1").await.unwrap();
+        let ttl = client.ttl("key1").await.unwrap().raw();
         assert!(ttl > 0 && ttl <= 120);
         cleanup(&mut client).await;
     }
@@ -100,7 +118,7 @@ mod string_tests {
     #[tokio::test]
     async fn test_ttl_nonexistent() {
         let mut client = setup_client().await;
-        let ttl: i64 = client.ttl("nonexistent").await.unwrap();
+        let ttl = client.ttl("nonexistent").await.unwrap().raw();
         assert_eq!(ttl, -2);
         cleanup(&mut client).await;
     }
@@ -108,11 +126,11 @@ mod string_tests {
     #[tokio::test]
     async fn test_persist_removes_expiry() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
         client.expire("key1", 60).await.unwrap();
-        let persisted: bool = client.persist("key1").await;
+        let persisted: bool = client.persist("key1").await.unwrap();
         assert!(persisted);
-        let ttl: i64 = client.ttl("key1").await.unwrap();
+        let ttl = client.ttl("key1").await.unwrap().raw();
         assert_eq!(ttl, -1); // -1 means no expiry
         cleanup(&mut client).await;
     }
@@ -121,7 +139,7 @@ mod string_tests {
     async fn test_type_conversion_string() {
         let mut client = setup_client().await;
         let test_str = "hello world";
-        client.set("key1", test_str).await.unwrap();
+        let _: () = client.set("key1", test_str).await.unwrap();
         let result: String = client.get("key1").await.unwrap();
         assert_eq!(result, test_str);
         cleanup(&mut client).await;
@@ -131,7 +149,7 @@ mod string_tests {
     async fn test_type_conversion_i64() {
         let mut client = setup_client().await;
         let test_num: i64 = 42;
-        client.set("key1", test_num).await.unwrap();
+        let _: () = client.set("key1", test_num).await.unwrap();
         let result: i64 = client.get("key1").await.unwrap();
         assert_eq!(result, 42);
         cleanup(&mut client).await;
@@ -140,7 +158,7 @@ mod string_tests {
     #[tokio::test]
     async fn test_type_conversion_bool() {
         let mut client = setup_client().await;
-        client.set("key1", true).await.unwrap();
+        let _: () = client.set("key1", true).await.unwrap();
         let result: bool = client.get("key1").await.unwrap();
         assert!(result);
         cleanup(&mut client).await;
@@ -163,7 +181,7 @@ mod hash_tests {
     #[tokio::test]
     async fn test_hset_existing_field() {
         let mut client = setup_client().await;
-        client.hset("myhash", "field1", "value1").await.unwrap();
+        let _: () = client.hset("myhash", "field1", "value1").await.unwrap();
         let result: i64 = client.hset("myhash", "field1", "value2").await.unwrap();
         assert_eq!(result, 0);
         let val: String = client.hget("myhash", "field1").await.unwrap();
@@ -176,7 +194,7 @@ mod hash_tests {
         let mut client = setup_client().await;
         let result: i64 = client.hset("newkey", "field1", "value1").await.unwrap();
         assert_eq!(result, 1);
-        let exists: bool = client.exists("newkey").await.unwrap();
+        let exists = client.exists::<_, i64>("newkey").await.unwrap() == 1;
         assert!(exists);
         cleanup(&mut client).await;
     }
@@ -184,8 +202,8 @@ mod hash_tests {
     #[tokio::test]
     async fn test_hget_existing() {
         let mut client = setup_client().await;
-        client.hset("myhash", "field1", "value1").await.unwrap();
-        client.hset("myhash", "field2", "value2").await.unwrap();
+        let _: () = client.hset("myhash", "field1", "value1").await.unwrap();
+        let _: () = client.hset("myhash", "field2", "value2").await.unwrap();
         let val: String = client.hget("myhash", "field1").await.unwrap();
         assert_eq!(val, "value1");
         cleanup(&mut client).await;
@@ -194,7 +212,7 @@ mod hash_tests {
     #[tokio::test]
     async fn test_hget_nonexistent() {
         let mut client = setup_client().await;
-        client.hset("myhash", "field1", "value1").await.unwrap();
+        let _: () = client.hset("myhash", "field1", "value1").await.unwrap();
         let val: String = client.hget("myhash", "nonexistent").await.unwrap();
         assert_eq!(val, "");
         cleanup(&mut client).await;
@@ -203,9 +221,9 @@ mod hash_tests {
     #[tokio::test]
     async fn test_hgetall_multiple() {
         let mut client = setup_client().await;
-        client.hset("myhash", "field1", "value1").await.unwrap();
-        client.hset("myhash", "field2", "value2").await.unwrap();
-        client.hset("myhash", "field3", "value3").await.unwrap();
+        let _: () = client.hset("myhash", "field1", "value1").await.unwrap();
+        let _: () = client.hset("myhash", "field2", "value2").await.unwrap();
+        let _: () = client.hset("myhash", "field3", "value3").await.unwrap();
         let result: Vec<String> = client.hgetall("myhash").await.unwrap();
         assert_eq!(result.len(), 6);
         cleanup(&mut client).await;
@@ -214,7 +232,7 @@ mod hash_tests {
     #[tokio::test]
     async fn test_hgetall_empty() {
         let mut client = setup_client().await;
-        client.set("stringkey", "value").await.unwrap();
+        let _: () = client.set("stringkey", "value").await.unwrap();
         let result: Vec<String> = client.hgetall("nonexistent").await.unwrap();
         assert_eq!(result.len(), 0);
         cleanup(&mut client).await;
@@ -223,7 +241,7 @@ mod hash_tests {
     #[tokio::test]
     async fn test_hdel_existing() {
         let mut client = setup_client().await;
-        client.hset("myhash", "field1", "value1").await.unwrap();
+        let _: () = client.hset("myhash", "field1", "value1").await.unwrap();
         let result: i64 = client.hdel("myhash", "field1").await.unwrap();
         assert_eq!(result, 1);
         let val: String = client.hget("myhash", "field1").await.unwrap();
@@ -234,7 +252,7 @@ mod hash_tests {
     #[tokio::test]
     async fn test_hdel_nonexistent() {
         let mut client = setup_client().await;
-        client.hset("myhash", "field1", "value1").await.unwrap();
+        let _: () = client.hset("myhash", "field1", "value1").await.unwrap();
         let result: i64 = client.hdel("myhash", "nonexistent").await.unwrap();
         assert_eq!(result, 0);
         cleanup(&mut client).await;
@@ -243,11 +261,315 @@ mod hash_tests {
     #[tokio::test]
     async fn test_wrong_type_on_string_key() {
         let mut client = setup_client().await;
-        client.set("mykey", "stringvalue").await.unwrap();
-        let result = client.hset("mykey", "field", "value").await;
+        let _: () = client.set("mykey", "stringvalue").await.unwrap();
+        let result: RedisResult<i64> = client.hset("mykey", "field", "value").await;
         assert!(result.is_err());
         cleanup(&mut client).await;
     }
+
+    #[tokio::test]
+    async fn test_hset_multiple_and_hdel_many() {
+        let mut client = setup_client().await;
+        let _: () = client.hset("myhash", "a", "old").await.unwrap();
+        let added: i64 = client
+            .hset_multiple("myhash", &[("a", "1"), ("b", "2"), ("c", "3")])
+            .await
+            .unwrap();
+        assert_eq!(added, 2);
+        assert_eq!(client.hlen::<_, i64>("myhash").await.unwrap(), 3);
+        let removed: i64 = client.hdel("myhash", ["a", "b", "missing"]).await.unwrap();
+        assert_eq!(removed, 2);
+        assert_eq!(client.hlen::<_, i64>("myhash").await.unwrap(), 1);
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_hmget_hkeys_hvals() {
+        let mut client = setup_client().await;
+        let _: () = client
+            .hset_multiple("myhash", &[("a", "1"), ("b", "2")])
+            .await
+            .unwrap();
+        let values: Vec<Option<String>> = client.hmget("myhash", ["a", "x", "b"]).await.unwrap();
+        assert_eq!(values, vec![Some("1".into()), None, Some("2".into())]);
+        let values: Vec<Option<String>> = client.hmget("missing", ["a", "b"]).await.unwrap();
+        assert_eq!(values, vec![None, None]);
+
+        let mut keys: Vec<String> = client.hkeys("myhash").await.unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["a", "b"]);
+        let mut vals: Vec<String> = client.hvals("myhash").await.unwrap();
+        vals.sort();
+        assert_eq!(vals, vec!["1", "2"]);
+        let keys: Vec<String> = client.hkeys("missing").await.unwrap();
+        assert!(keys.is_empty());
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_hexists_hstrlen_hlen() {
+        let mut client = setup_client().await;
+        let _: () = client.hset("myhash", "name", "Alice").await.unwrap();
+        assert!(client
+            .hexists::<_, _, bool>("myhash", "name")
+            .await
+            .unwrap());
+        assert!(!client.hexists::<_, _, bool>("myhash", "age").await.unwrap());
+        assert!(!client
+            .hexists::<_, _, bool>("missing", "name")
+            .await
+            .unwrap());
+        assert_eq!(
+            client.hstrlen::<_, _, i64>("myhash", "name").await.unwrap(),
+            5
+        );
+        assert_eq!(
+            client.hstrlen::<_, _, i64>("myhash", "age").await.unwrap(),
+            0
+        );
+        assert_eq!(client.hlen::<_, i64>("missing").await.unwrap(), 0);
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_hset_nx() {
+        let mut client = setup_client().await;
+        assert!(client
+            .hset_nx::<_, _, _, bool>("myhash", "f", "first")
+            .await
+            .unwrap());
+        assert!(!client
+            .hset_nx::<_, _, _, bool>("myhash", "f", "second")
+            .await
+            .unwrap());
+        let val: String = client.hget("myhash", "f").await.unwrap();
+        assert_eq!(val, "first");
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_hincr() {
+        let mut client = setup_client().await;
+        assert_eq!(
+            client
+                .hincr::<_, _, _, i64>("myhash", "n", 5)
+                .await
+                .unwrap(),
+            5
+        );
+        assert_eq!(
+            client
+                .hincr::<_, _, _, i64>("myhash", "n", -7)
+                .await
+                .unwrap(),
+            -2
+        );
+        let val: String = client.hget("myhash", "n").await.unwrap();
+        assert_eq!(val, "-2");
+
+        let _: () = client.hset("myhash", "big", i64::MAX).await.unwrap();
+        assert!(client
+            .hincr::<_, _, _, ()>("myhash", "big", 1)
+            .await
+            .is_err());
+        let _: () = client.hset("myhash", "text", "abc").await.unwrap();
+        assert!(client
+            .hincr::<_, _, _, ()>("myhash", "text", 1)
+            .await
+            .is_err());
+        let _: () = client.hset("myhash", "padded", " 1").await.unwrap();
+        assert!(client
+            .hincr::<_, _, _, ()>("myhash", "padded", 1)
+            .await
+            .is_err());
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_hincr_by_float() {
+        use not_redis::cmd;
+
+        let mut client = setup_client().await;
+        let _: () = client.hset("myhash", "f", "10.50").await.unwrap();
+        assert_eq!(
+            client
+                .hincr_by_float::<_, _, f64>("myhash", "f", 0.1)
+                .await
+                .unwrap(),
+            10.6
+        );
+        let val: String = client.hget("myhash", "f").await.unwrap();
+        assert_eq!(val, "10.6");
+        assert_eq!(
+            client
+                .hincr_by_float::<_, _, f64>("myhash", "g", 2.0)
+                .await
+                .unwrap(),
+            2.0
+        );
+        let _: () = client.hset("myhash", "text", "abc").await.unwrap();
+        assert!(client
+            .hincr_by_float::<_, _, f64>("myhash", "text", 1.0)
+            .await
+            .is_err());
+
+        let _: () = client.hset("myhash", "sum", "0.1").await.unwrap();
+        let reply: String = cmd("HINCRBYFLOAT")
+            .arg("myhash")
+            .arg("sum")
+            .arg("0.2")
+            .query_async(&mut client)
+            .await
+            .unwrap();
+        assert_eq!(reply, "0.3");
+        let val: String = client.hget("myhash", "sum").await.unwrap();
+        assert_eq!(val, "0.3");
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_hrandfield() {
+        let mut client = setup_client().await;
+        let _: () = client
+            .hset_multiple("myhash", &[("a", "1"), ("b", "2"), ("c", "3")])
+            .await
+            .unwrap();
+        let field: String = client.hrandfield("myhash").await.unwrap();
+        assert!(["a", "b", "c"].contains(&field.as_str()));
+        let field: Option<String> = client.hrandfield("missing").await.unwrap();
+        assert_eq!(field, None);
+
+        let mut fields: Vec<String> = client.hrandfield_count("myhash", 10).await.unwrap();
+        fields.sort();
+        assert_eq!(fields, vec!["a", "b", "c"]);
+        let fields: Vec<String> = client.hrandfield_count("myhash", 2).await.unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_ne!(fields[0], fields[1]);
+        let fields: Vec<String> = client.hrandfield_count("myhash", -7).await.unwrap();
+        assert_eq!(fields.len(), 7);
+        let fields: Vec<String> = client.hrandfield_count("missing", -3).await.unwrap();
+        assert!(fields.is_empty());
+
+        let pairs: Vec<String> = client.hrandfield_withvalues("myhash", -4).await.unwrap();
+        assert_eq!(pairs.len(), 8);
+        for pair in pairs.chunks(2) {
+            let value: String = client.hget("myhash", &pair[0]).await.unwrap();
+            assert_eq!(value, pair[1]);
+        }
+        cleanup(&mut client).await;
+    }
+
+    #[test]
+    fn test_hrandfield_count_out_of_range() {
+        use not_redis::{blocking, cmd, Commands, RedisError};
+
+        let mut client = blocking::Client::new();
+        let _: () = client.hset("h", "a", "1").unwrap();
+        let result: Result<Vec<String>, _> =
+            cmd("HRANDFIELD").arg("h").arg(-i64::MAX).query(&mut client);
+        assert!(
+            matches!(result, Err(RedisError::InvalidArgument(ref m)) if m.contains("out of range"))
+        );
+        let result: Result<Vec<String>, _> = client.hrandfield_count("h", isize::MIN + 1);
+        assert!(
+            matches!(result, Err(RedisError::InvalidArgument(ref m)) if m.contains("out of range"))
+        );
+        // Repeated fields are bounded well before the reply length.
+        for count in [-4611686018427387903i64, -(1 << 24) - 1] {
+            let result: Result<Vec<String>, _> =
+                cmd("HRANDFIELD").arg("h").arg(count).query(&mut client);
+            assert!(
+                matches!(result, Err(RedisError::InvalidArgument(ref m)) if m.contains("out of range"))
+            );
+        }
+        let result: Result<Vec<String>, _> = client.hrandfield_withvalues("h", isize::MAX);
+        assert!(
+            matches!(result, Err(RedisError::InvalidArgument(ref m)) if m.contains("out of range"))
+        );
+    }
+
+    #[test]
+    fn test_hash_commands_through_cmd() {
+        use not_redis::{blocking, cmd, Commands, RedisError, Value};
+
+        let mut client = blocking::Client::new();
+        let added: i64 = cmd("HSET")
+            .arg("h")
+            .arg(["a", "1", "b", "2"])
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(added, 2);
+        let result: Result<i64, _> = cmd("HSET").arg("h").arg(["a", "1", "b"]).query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        let removed: i64 = cmd("HDEL")
+            .arg("h")
+            .arg(["a", "b", "c"])
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(removed, 2);
+
+        let _: () = client.hset("h", "n", "1").unwrap();
+        let value: String = cmd("HINCRBYFLOAT")
+            .arg("h")
+            .arg("n")
+            .arg("1.5e1")
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(value, "16");
+        let reply: Value = cmd("HRANDFIELD")
+            .arg("h")
+            .arg(1)
+            .arg("withvalues")
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(
+            reply,
+            Value::Array(vec![
+                Value::String(b"n".to_vec()),
+                Value::String(b"16".to_vec())
+            ])
+        );
+        let result: Result<Value, _> = cmd("HRANDFIELD")
+            .arg("h")
+            .arg(1)
+            .arg("WITHSCORES")
+            .query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        let result: Result<Value, _> = cmd("HRANDFIELD")
+            .arg("h")
+            .arg(i64::MIN)
+            .arg("WITHVALUES")
+            .query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_hash_commands_wrong_type() {
+        use not_redis::{blocking, Commands, RedisError};
+
+        let mut client = blocking::Client::new();
+        let _: () = client.set("s", "v").unwrap();
+        assert!(matches!(
+            client.hlen::<_, i64>("s"),
+            Err(RedisError::WrongType)
+        ));
+        assert!(matches!(
+            client.hexists::<_, _, ()>("s", "f"),
+            Err(RedisError::WrongType)
+        ));
+        assert!(matches!(
+            client.hincr::<_, _, _, ()>("s", "f", 1),
+            Err(RedisError::WrongType)
+        ));
+        assert!(matches!(
+            client.hset_nx::<_, _, _, ()>("s", "f", "v"),
+            Err(RedisError::WrongType)
+        ));
+        let result: Result<Vec<Option<String>>, _> = client.hmget("s", ["f"]);
+        assert!(matches!(result, Err(RedisError::WrongType)));
+        let result: Result<Option<String>, _> = client.hrandfield("s");
+        assert!(matches!(result, Err(RedisError::WrongType)));
+    }
 }
 
 mod list_tests {
@@ -264,9 +586,9 @@ mod list_tests {
     #[tokio::test]
     async fn test_lpush_multiple() {
         let mut client = setup_client().await;
-        client.lpush("mylist", "a").await.unwrap();
-        client.lpush("mylist", "b").await.unwrap();
-        client.lpush("mylist", "c").await.unwrap();
+        let _: () = client.lpush("mylist", "a").await.unwrap();
+        let _: () = client.lpush("mylist", "b").await.unwrap();
+        let _: () = client.lpush("mylist", "c").await.unwrap();
         let len: i64 = client.llen("mylist").await.unwrap();
         assert_eq!(len, 3);
         cleanup(&mut client).await;
@@ -283,8 +605,8 @@ mod list_tests {
     #[tokio::test]
     async fn test_llen_basic() {
         let mut client = setup_client().await;
-        client.lpush("mylist", "a").await.unwrap();
-        client.lpush("mylist", "b").await.unwrap();
+        let _: () = client.lpush("mylist", "a").await.unwrap();
+        let _: () = client.lpush("mylist", "b").await.unwrap();
         let len: i64 = client.llen("mylist").await.unwrap();
         assert_eq!(len, 2);
         cleanup(&mut client).await;
@@ -301,8 +623,8 @@ mod list_tests {
     #[tokio::test]
     async fn test_wrong_type_on_string_key() {
         let mut client = setup_client().await;
-        client.set("mykey", "stringvalue").await.unwrap();
-        let result = client.llen("mykey").await;
+        let _: () = client.set("mykey", "stringvalue").await.unwrap();
+        let result: RedisResult<i64> = client.llen("mykey").await;
         assert!(result.is_err());
         cleanup(&mut client).await;
     }
@@ -322,7 +644,7 @@ mod set_tests {
     #[tokio::test]
     async fn test_sadd_duplicate() {
         let mut client = setup_client().await;
-        client.sadd("myset", "member1").await.unwrap();
+        let _: () = client.sadd("myset", "member1").await.unwrap();
         let result: i64 = client.sadd("myset", "member1").await.unwrap();
         assert_eq!(result, 0);
         cleanup(&mut client).await;
@@ -331,9 +653,9 @@ mod set_tests {
     #[tokio::test]
     async fn test_smembers_basic() {
         let mut client = setup_client().await;
-        client.sadd("myset", "member1").await.unwrap();
-        client.sadd("myset", "member2").await.unwrap();
-        client.sadd("myset", "member3").await.unwrap();
+        let _: () = client.sadd("myset", "member1").await.unwrap();
+        let _: () = client.sadd("myset", "member2").await.unwrap();
+        let _: () = client.sadd("myset", "member3").await.unwrap();
         let members: Vec<String> = client.smembers("myset").await.unwrap();
         assert_eq!(members.len(), 3);
         cleanup(&mut client).await;
@@ -350,8 +672,8 @@ mod set_tests {
     #[tokio::test]
     async fn test_wrong_type_on_string_key() {
         let mut client = setup_client().await;
-        client.set("mykey", "stringvalue").await.unwrap();
-        let result = client.sadd("mykey", "member").await;
+        let _: () = client.set("mykey", "stringvalue").await.unwrap();
+        let result: RedisResult<i64> = client.sadd("mykey", "member").await;
         assert!(result.is_err());
         cleanup(&mut client).await;
     }
@@ -387,9 +709,9 @@ mod utility_tests {
     #[tokio::test]
     async fn test_dbsize_after_ops() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
-        client.set("key2", "value2").await.unwrap();
-        client.hset("hash1", "field1", "val1").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key2", "value2").await.unwrap();
+        let _: () = client.hset("hash1", "field1", "val1").await.unwrap();
         let size: i64 = client.dbsize().await.unwrap();
         assert_eq!(size, 3);
         cleanup(&mut client).await;
@@ -398,8 +720,8 @@ mod utility_tests {
     #[tokio::test]
     async fn test_flushdb() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
-        client.set("key2", "value2").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key2", "value2").await.unwrap();
         let _: String = client.flushdb().await.unwrap();
         let size: i64 = client.dbsize().await.unwrap();
         assert_eq!(size, 0);
@@ -409,13 +731,16 @@ mod utility_tests {
 
 mod expiration_tests {
     use super::*;
+    use not_redis::{RedisData, StorageEngine};
+    use rustc_hash::{FxHashMap, FxHashSet};
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_expire_sets_ttl() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
         client.expire("key1", 30).await.unwrap();
-        let ttl: i64 = client.ttl("key1").await.unwrap();
+        let ttl = client.ttl("key1").await.unwrap().raw();
         assert!(ttl > 0 && ttl <= 30);
         cleanup(&mut client).await;
     }
@@ -423,11 +748,11 @@ mod expiration_tests {
     #[tokio::test]
     async fn test_persist_preserves_key() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
         client.expire("key1", 10).await.unwrap();
-        let persisted = client.persist("key1").await;
+        let persisted: bool = client.persist("key1").await.unwrap();
         assert!(persisted);
-        let ttl: i64 = client.ttl("key1").await.unwrap();
+        let ttl = client.ttl("key1").await.unwrap().raw();
         assert_eq!(ttl, -1); // -1 means no expiry
         cleanup(&mut client).await;
     }
@@ -435,24 +760,120 @@ mod expiration_tests {
     #[tokio::test]
     async fn test_ttl_mixed_expiry() {
         let mut client = setup_client().await;
-        client.set("key1", "value1").await.unwrap();
-        client.set("key2", "value2").await.unwrap();
+        let _: () = client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key2", "value2").await.unwrap();
         client.expire("key1", 60).await.unwrap();
-        let ttl1: i64 = client.ttl("key1").await.unwrap();
-        let ttl2: i64 = client.ttl("key2").await.unwrap();
+        let ttl1 = client.ttl("key1").await.unwrap().raw();
+        let ttl2 = client.ttl("key2").await.unwrap().raw();
         assert!(ttl1 > 0 && ttl1 <= 60);
         assert_eq!(ttl2, -1); // key2 has no expiry, so -1
         cleanup(&mut client).await;
     }
+
+    #[tokio::test]
+    async fn test_sweeper_removes_expired_keys() {
+        let mut client = setup_client().await;
+        let _: () = client.set("key1", "value1").await.unwrap();
+        let _: () = client.set("key2", "value2").await.unwrap();
+        client.expire("key1", 1).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(1300)).await;
+        // Either the sweeper or the expiry-aware DBSIZE must leave only key2.
+        let size: i64 = client.dbsize().await.unwrap();
+        assert_eq!(size, 1);
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_stops_sweeper() {
+        let client = Client::from_storage(
+            StorageEngine::new().with_sweep_interval(Duration::from_millis(10)),
+        );
+        let handle = client.start().await;
+        handle.clone().shutdown().await;
+        assert!(handle.is_finished());
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_exits_when_client_is_dropped() {
+        let client = Client::from_storage(
+            StorageEngine::new().with_sweep_interval(Duration::from_millis(10)),
+        );
+        let handle = client.start().await;
+        drop(client);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !handle.is_finished() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("sweeper did not exit");
+    }
+
+    fn client_with_expired_keys() -> Client {
+        let storage = StorageEngine::new();
+        // Deadlines are Unix-epoch milliseconds; any small value is long past.
+        let past = Some(1);
+        let mut hash = FxHashMap::default();
+        hash.insert(b"field".to_vec(), b"value".to_vec());
+        storage.set("hash", RedisData::Hash(hash.into()), past);
+        let set: FxHashSet<Vec<u8>> = [b"member".to_vec()].into_iter().collect();
+        storage.set("set", RedisData::Set(set), past);
+        storage.set("string", RedisData::String(b"value".to_vec()), past);
+        Client::from_storage(storage)
+    }
+
+    #[tokio::test]
+    async fn test_reads_skip_expired_keys() {
+        let mut client = client_with_expired_keys();
+        let value: Option<String> = client.hget("hash", "field").await.unwrap();
+        assert_eq!(value, None);
+        let all: Vec<String> = client.hgetall("hash").await.unwrap();
+        assert!(all.is_empty());
+        let members: Vec<String> = client.smembers("set").await.unwrap();
+        assert!(members.is_empty());
+        assert_eq!(client.exists::<_, i64>("string").await.unwrap(), 0);
+        assert_eq!(client.ttl("string").await.unwrap().raw(), -2);
+        assert_eq!(client.dbsize::<i64>().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_writes_treat_expired_keys_as_missing() {
+        let mut client = client_with_expired_keys();
+        // Each key held a different type; the expired value must not cause WRONGTYPE.
+        assert_eq!(client.lpush::<_, _, i64>("hash", "item").await.unwrap(), 1);
+        assert_eq!(
+            client.sadd::<_, _, i64>("string", "member").await.unwrap(),
+            1
+        );
+        assert_eq!(
+            client
+                .hset::<_, _, _, i64>("set", "field", "value")
+                .await
+                .unwrap(),
+            1
+        );
+        // The new values do not inherit the old deadline.
+        assert_eq!(client.ttl("hash").await.unwrap().raw(), -1);
+        assert_eq!(client.dbsize::<i64>().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_del_expired_key_returns_zero() {
+        let mut client = client_with_expired_keys();
+        assert_eq!(client.del::<_, i64>("string").await.unwrap(), 0);
+        assert_eq!(client.hdel::<_, _, i64>("hash", "field").await.unwrap(), 0);
+        assert!(!client.persist("set").await.unwrap());
+    }
 }
 
 mod edge_case_tests {
     use super::*;
+    use not_redis::cmd;
 
     #[tokio::test]
     async fn test_empty_string_key() {
         let mut client = setup_client().await;
-        client.set("", "value1").await.unwrap();
+        let _: () = client.set("", "value1").await.unwrap();
         let result: String = client.get("").await.unwrap();
         assert_eq!(result, "value1");
         let deleted: i64 = client.del("").await.unwrap();
@@ -460,10 +881,41 @@ mod edge_case_tests {
         cleanup(&mut client).await;
     }
 
+    #[tokio::test]
+    async fn test_binary_keys_do_not_collide() {
+        let mut client = setup_client().await;
+        // Both keys are invalid UTF-8 and would map to the same lossy string.
+        let key_a: Vec<u8> = vec![b'k', 0xff];
+        let key_b: Vec<u8> = vec![b'k', 0xfe];
+        let _: () = client.set(key_a.clone(), "a").await.unwrap();
+        let _: () = client.set(key_b.clone(), "b").await.unwrap();
+        let a: String = client.get(key_a.clone()).await.unwrap();
+        let b: String = client.get(key_b).await.unwrap();
+        assert_eq!(a, "a");
+        assert_eq!(b, "b");
+        let size: i64 = client.dbsize().await.unwrap();
+        assert_eq!(size, 2);
+        let deleted: i64 = client.del(key_a.clone()).await.unwrap();
+        assert_eq!(deleted, 1);
+        let exists = client.exists::<_, i64>(key_a).await.unwrap() == 1;
+        assert!(!exists);
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_keys_returns_raw_bytes() {
+        let mut client = setup_client().await;
+        let key: Vec<u8> = vec![b'i', b'd', b':', 0x00, 0xff];
+        let _: () = client.hset(key.clone(), "field", "value").await.unwrap();
+        let keys: Vec<Vec<u8>> = cmd("KEYS").arg("id:*").query(&mut client).unwrap();
+        assert_eq!(keys, vec![key]);
+        cleanup(&mut client).await;
+    }
+
     #[tokio::test]
     async fn test_empty_string_value() {
         let mut client = setup_client().await;
-        client.set("key1", "").await.unwrap();
+        let _: () = client.set("key1", "").await.unwrap();
         let result: String = client.get("key1").await.unwrap();
         assert_eq!(result, "");
         cleanup(&mut client).await;
@@ -473,7 +925,7 @@ mod edge_case_tests {
     async fn test_unicode_strings() {
         let mut client = setup_client().await;
         let unicode_str = "你好世界 🌍 Привет мир";
-        client.set("key1", unicode_str).await.unwrap();
+        let _: () = client.set("key1", unicode_str).await.unwrap();
         let result: String = client.get("key1").await.unwrap();
         assert_eq!(result, unicode_str);
         cleanup(&mut client).await;
@@ -483,7 +935,7 @@ mod edge_case_tests {
     async fn test_binary_data() {
         let mut client = setup_client().await;
         let binary: Vec<u8> = vec![0x00, 0xFF, 0x42, 0x13, 0x37];
-        client.set("key1", binary.clone()).await.unwrap();
+        let _: () = client.set("key1", binary.clone()).await.unwrap();
         let result: Vec<u8> = client.get("key1").await.unwrap();
         assert_eq!(result, binary);
         cleanup(&mut client).await;
@@ -493,7 +945,7 @@ mod edge_case_tests {
     async fn test_special_characters() {
         let mut client = setup_client().await;
         let special = "key:value\nwith\ttabs\"and\"quotes\\backslashes";
-        client.set("key1", special).await.unwrap();
+        let _: () = client.set("key1", special).await.unwrap();
         let result: String = client.get("key1").await.unwrap();
         assert_eq!(result, special);
         cleanup(&mut client).await;
@@ -503,7 +955,7 @@ mod edge_case_tests {
     async fn test_whitespace_values() {
         let mut client = setup_client().await;
         let whitespace = "   spaces   \n\tnewlines\t ";
-        client.set("key1", whitespace).await.unwrap();
+        let _: () = client.set("key1", whitespace).await.unwrap();
         let result: String = client.get("key1").await.unwrap();
         assert_eq!(result, whitespace);
         cleanup(&mut client).await;
@@ -513,7 +965,7 @@ mod edge_case_tests {
     async fn test_large_value() {
         let mut client = setup_client().await;
         let large = "x".repeat(10000);
-        client.set("key1", large).await.unwrap();
+        let _: () = client.set("key1", large).await.unwrap();
         let result: String = client.get("key1").await.unwrap();
         assert_eq!(result.len(), 10000);
         cleanup(&mut client).await;
@@ -522,7 +974,7 @@ mod edge_case_tests {
     #[tokio::test]
     async fn test_numeric_strings() {
         let mut client = setup_client().await;
-        client.set("key1", "12345").await.unwrap();
+        let _: () = client.set("key1", "12345").await.unwrap();
         let result: String = client.get("key1").await.unwrap();
         assert_eq!(result, "12345");
         cleanup(&mut client).await;
@@ -535,7 +987,7 @@ mod error_tests {
     #[tokio::test]
     async fn test_get_wrong_type() {
         let mut client = setup_client().await;
-        client.lpush("mylist", "item").await.unwrap();
+        let _: () = client.lpush("mylist", "item").await.unwrap();
         let result: RedisResult<String> = client.get("mylist").await;
         assert!(result.is_err());
         cleanup(&mut client).await;
@@ -544,7 +996,7 @@ mod error_tests {
     #[tokio::test]
     async fn test_hget_wrong_type() {
         let mut client = setup_client().await;
-        client.set("mykey", "stringvalue").await.unwrap();
+        let _: () = client.set("mykey", "stringvalue").await.unwrap();
         let result: RedisResult<String> = client.hget("mykey", "field").await;
         assert!(result.is_err());
         cleanup(&mut client).await;
@@ -553,7 +1005,7 @@ mod error_tests {
     #[tokio::test]
     async fn test_llen_wrong_type() {
         let mut client = setup_client().await;
-        client.set("mykey", "value").await.unwrap();
+        let _: () = client.set("mykey", "value").await.unwrap();
         let result: RedisResult<i64> = client.llen("mykey").await;
         assert!(result.is_err());
         cleanup(&mut client).await;
@@ -562,7 +1014,7 @@ mod error_tests {
     #[tokio::test]
     async fn test_sadd_wrong_type() {
         let mut client = setup_client().await;
-        client.set("mykey", "value").await.unwrap();
+        let _: () = client.set("mykey", "value").await.unwrap();
         let result: RedisResult<i64> = client.sadd("mykey", "member").await;
         assert!(result.is_err());
         cleanup(&mut client).await;
@@ -571,9 +1023,2824 @@ mod error_tests {
     #[tokio::test]
     async fn test_wrong_type_hset_on_list() {
         let mut client = setup_client().await;
-        client.lpush("mylist", "item").await.unwrap();
+        let _: () = client.lpush("mylist", "item").await.unwrap();
         let result: RedisResult<i64> = client.hset("mylist", "field", "value").await;
         assert!(result.is_err());
         cleanup(&mut client).await;
     }
 }
+
+mod commands_trait_tests {
+    use super::*;
+    use not_redis::{Commands, CopyOptions, RedisError};
+
+    fn add_visit<C: Commands>(con: &mut C, page: &str) -> RedisResult<i64> {
+        con.sadd("visited", page)
+    }
+
+    #[tokio::test]
+    async fn test_generic_commands_caller() {
+        let mut client = setup_client().await;
+        assert_eq!(add_visit(&mut client, "/home").unwrap(), 1);
+        assert_eq!(add_visit(&mut client, "/home").unwrap(), 0);
+        let count: i64 = client.scard("visited").await.unwrap();
+        assert_eq!(count, 1);
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_set_commands() {
+        let mut client = setup_client().await;
+        let _: i64 = Commands::sadd(&mut client, "myset", "a").unwrap();
+        let _: i64 = Commands::sadd(&mut client, "myset", "b").unwrap();
+        let is_member: bool = client.sismember("myset", "a").await.unwrap();
+        assert!(is_member);
+        let removed: i64 = client.srem("myset", "a").await.unwrap();
+        assert_eq!(removed, 1);
+        let is_member: bool = client.sismember("myset", "a").await.unwrap();
+        assert!(!is_member);
+        let count: i64 = client.scard("myset").await.unwrap();
+        assert_eq!(count, 1);
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_sorted_set_commands() {
+        let mut client = setup_client().await;
+        let _: i64 = client.zadd("board", 3.0, "carol").await.unwrap();
+        let _: i64 = client.zadd("board", 1.0, "alice").await.unwrap();
+        let _: i64 = client.zadd("board", 2.0, "bob").await.unwrap();
+        let members: Vec<String> = client.zrange("board", 0, -1).await.unwrap();
+        assert_eq!(members, vec!["alice", "bob", "carol"]);
+        let score: f64 = client.zscore("board", "bob").await.unwrap();
+        assert_eq!(score, 2.0);
+        let missing: Option<f64> = client.zscore("board", "dave").await.unwrap();
+        assert_eq!(missing, None);
+        let count: i64 = client.zcount("board", "(1", "3").await.unwrap();
+        assert_eq!(count, 2);
+        let in_range: Vec<String> = client.zrangebyscore("board", "-inf", "2").await.unwrap();
+        assert_eq!(in_range, vec!["alice", "bob"]);
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_key_commands() {
+        let mut client = setup_client().await;
+        let _: () = client.set("user:1", "alice").await.unwrap();
+        let _: () = client.set("user:2", "bob").await.unwrap();
+        let _: () = client.lpush("queue", "job").await.unwrap();
+
+        let mut keys: Vec<String> = client.keys("user:*").await.unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["user:1", "user:2"]);
+
+        let kind: String = client.key_type("queue").await.unwrap();
+        assert_eq!(kind, "list");
+        let kind: String = client.key_type("missing").await.unwrap();
+        assert_eq!(kind, "none");
+
+        let _: () = client.rename("user:1", "user:3").await.unwrap();
+        let renamed: bool = client.rename_nx("user:3", "user:2").await.unwrap();
+        assert!(!renamed);
+        let value: String = client.get("user:3").await.unwrap();
+        assert_eq!(value, "alice");
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_copy() {
+        let mut client = setup_client().await;
+        let _: () = client.set("src", "one").await.unwrap();
+        let _: () = client.set("dst", "two").await.unwrap();
+
+        let copied: bool = client
+            .copy("src", "dst", CopyOptions::default())
+            .await
+            .unwrap();
+        assert!(!copied);
+        let copied: bool = client
+            .copy("src", "dst", CopyOptions::default().replace(true))
+            .await
+            .unwrap();
+        assert!(copied);
+        let value: String = client.get("dst").await.unwrap();
+        assert_eq!(value, "one");
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_command() {
+        let mut client = setup_client().await;
+        let result: RedisResult<i64> = not_redis::cmd("PFCOUNT").arg("key").query(&mut client);
+        assert!(matches!(result, Err(RedisError::CommandNotFound(_))));
+        cleanup(&mut client).await;
+    }
+}
+
+mod cmd_tests {
+    use super::*;
+    use not_redis::{cmd, RedisError};
+
+    #[tokio::test]
+    async fn test_cmd_set_and_get() {
+        let mut client = setup_client().await;
+        let _: () = cmd("SET")
+            .arg("key")
+            .arg("value")
+            .query(&mut client)
+            .unwrap();
+        let value: String = cmd("GET").arg("key").query(&mut client).unwrap();
+        assert_eq!(value, "value");
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_cmd_query_async() {
+        let mut client = setup_client().await;
+        let added: i64 = cmd("SADD")
+            .arg("tags")
+            .arg("a")
+            .arg("b")
+            .arg("a")
+            .query_async(&mut client)
+            .await
+            .unwrap();
+        assert_eq!(added, 2);
+        let count: i64 = cmd("SCARD").arg("tags").query(&mut client).unwrap();
+        assert_eq!(count, 2);
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_cmd_name_is_case_insensitive() {
+        let mut client = setup_client().await;
+        let pong: String = cmd("ping").query(&mut client).unwrap();
+        assert_eq!(pong, "PONG");
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_cmd_integer_args() {
+        let mut client = setup_client().await;
+        let _: i64 = cmd("ZADD")
+            .arg("z")
+            .arg(1)
+            .arg("one")
+            .arg(2)
+            .arg("two")
+            .query(&mut client)
+            .unwrap();
+        let members: Vec<String> = cmd("ZRANGE")
+            .arg("z")
+            .arg(0)
+            .arg(-1)
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(members, vec!["one", "two"]);
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_cmd_none_args_are_skipped() {
+        let mut client = setup_client().await;
+        let reply: String = cmd("PING").arg(None::<&str>).query(&mut client).unwrap();
+        assert_eq!(reply, "PONG");
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_cmd_wrong_arity() {
+        let mut client = setup_client().await;
+        let result: RedisResult<String> = cmd("GET").query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        let result: RedisResult<String> = cmd("GET").arg("a").arg("b").query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        cleanup(&mut client).await;
+    }
+
+    #[tokio::test]
+    async fn test_cmd_unknown_command() {
+        let mut client = setup_client().await;
+        let result: RedisResult<()> = cmd("NOSUCHCOMMAND").arg("x").query(&mut client);
+        assert!(matches!(
+            result,
+            Err(RedisError::CommandNotFound(name)) if name == "NOSUCHCOMMAND"
+        ));
+        cleanup(&mut client).await;
+    }
+}
+
+mod blocking_tests {
+    use not_redis::{blocking, Commands, StorageEngine};
+
+    #[test]
+    fn test_blocking_set_and_get() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("key1", "value1").unwrap();
+        let result: String = client.get("key1").unwrap();
+        assert_eq!(result, "value1");
+        let deleted: i64 = client.del("key1").unwrap();
+        assert_eq!(deleted, 1);
+    }
+
+    #[test]
+    fn test_blocking_collections() {
+        let mut client = blocking::Client::new();
+        let _: () = client.hset("user:1", "name", "alice").unwrap();
+        let name: String = client.hget("user:1", "name").unwrap();
+        assert_eq!(name, "alice");
+        let _: () = client.rpush("queue", "a").unwrap();
+        let _: () = client.rpush("queue", "b").unwrap();
+        assert_eq!(client.llen::<_, i64>("queue").unwrap(), 2);
+        let _: i64 = client.zadd("board", 1.0, "alice").unwrap();
+        let count: i64 = client.zcard("board").unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_blocking_across_threads() {
+        let storage = StorageEngine::new();
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let storage = storage.clone();
+                std::thread::spawn(move || {
+                    let mut client = blocking::Client::from_storage(storage);
+                    for j in 0..25 {
+                        let _: () = client.set(format!("key:{}:{}", i, j), j as i64).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let mut client = blocking::Client::from_storage(storage);
+        assert_eq!(client.dbsize::<i64>().unwrap(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_shares_storage_with_async_client() {
+        let storage = StorageEngine::new();
+        let mut async_client = not_redis::Client::from_storage(storage.clone());
+        async_client.start().await;
+        let mut client = blocking::Client::from_storage(storage);
+
+        let _: () = async_client.set("shared", "from async").await.unwrap();
+        let value: String = client.get("shared").unwrap();
+        assert_eq!(value, "from async");
+
+        let _: () = client.set("shared", "from blocking").unwrap();
+        let value: String = async_client.get("shared").await.unwrap();
+        assert_eq!(value, "from blocking");
+    }
+}
+
+mod runtime_tests {
+    use not_redis::runtime::{Executor, PeriodicTask, ThreadExecutor};
+    use not_redis::{blocking, Commands, StorageEngine};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    /// Runs the task inline, a fixed number of times, counting the calls.
+    struct CountingExecutor {
+        runs: usize,
+        spawned: Arc<AtomicUsize>,
+    }
+
+    impl Executor for CountingExecutor {
+        fn spawn_periodic(&self, _interval: Duration, mut task: PeriodicTask) {
+            self.spawned.fetch_add(1, Ordering::SeqCst);
+            for _ in 0..self.runs {
+                if task().is_break() {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_thread_executor_sweeps_without_runtime() {
+        let mut client = blocking::Client::new();
+        client.start();
+        let _: () = client.set("key1", "value1").unwrap();
+        let _: () = client.set("key2", "value2").unwrap();
+        client.expire("key1", 1).unwrap();
+        std::thread::sleep(Duration::from_millis(1300));
+        assert_eq!(client.dbsize::<i64>().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_custom_executor() {
+        let storage = StorageEngine::new();
+        let mut client = blocking::Client::from_storage(storage.clone());
+        let _: () = client.set("key1", "value1").unwrap();
+        client.expire("key1", 0).unwrap();
+
+        let spawned = Arc::new(AtomicUsize::new(0));
+        storage.start_expiration_sweeper_on(&CountingExecutor {
+            runs: 1,
+            spawned: Arc::clone(&spawned),
+        });
+        assert_eq!(spawned.load(Ordering::SeqCst), 1);
+        assert_eq!(client.dbsize::<i64>().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_custom_executor_sees_finished_task() {
+        let storage = StorageEngine::new();
+        let handle = storage.start_expiration_sweeper_on(&CountingExecutor {
+            runs: 1,
+            spawned: Arc::new(AtomicUsize::new(0)),
+        });
+        // The executor dropped the task after running it.
+        assert!(handle.is_finished());
+    }
+
+    #[test]
+    fn test_shutdown_blocking_stops_sweeper() {
+        let storage = StorageEngine::new().with_sweep_interval(Duration::from_millis(10));
+        let handle = storage.start_expiration_sweeper_on(&ThreadExecutor);
+        assert!(!handle.is_finished());
+        handle.clone().shutdown_blocking();
+        assert!(handle.is_finished());
+    }
+
+    #[test]
+    fn test_sweeper_exits_when_storage_is_dropped() {
+        let client = blocking::Client::from_storage(
+            StorageEngine::new().with_sweep_interval(Duration::from_millis(10)),
+        );
+        let handle = client.start();
+        drop(client);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !handle.is_finished() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(handle.is_finished());
+    }
+
+    #[test]
+    fn test_explicit_thread_executor() {
+        let storage = StorageEngine::new();
+        storage.start_expiration_sweeper_on(&ThreadExecutor);
+        let mut client = blocking::Client::from_storage(storage);
+        let _: () = client.set("key1", "value1").unwrap();
+        client.expire("key1", 1).unwrap();
+        std::thread::sleep(Duration::from_millis(1300));
+        assert_eq!(client.dbsize::<i64>().unwrap(), 0);
+    }
+}
+
+mod clock_tests {
+    use super::{client_with_clock, now_millis};
+    use not_redis::clock::Clock;
+    use not_redis::{cmd, Commands, ExpireOption, IntegerReplyOrNoOp, Value};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_ttl_follows_clock() {
+        let (mut client, clock) = client_with_clock();
+        let _: () = client.set("key", "value").unwrap();
+        client.expire("key", 10).unwrap();
+        assert_eq!(client.ttl("key").unwrap().raw(), 10);
+
+        // TTL rounds to the nearest second, as in Redis.
+        clock.advance(Duration::from_millis(4500));
+        assert_eq!(client.ttl("key").unwrap().raw(), 6);
+        assert_eq!(client.pttl("key").unwrap().raw(), 5500);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(client.ttl("key").unwrap().raw(), 5);
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_key_expires_exactly_at_deadline() {
+        let (mut client, clock) = client_with_clock();
+        let _: () = client.set("key", "value").unwrap();
+        client.expire("key", 10).unwrap();
+
+        clock.advance(Duration::from_secs(10) - Duration::from_nanos(1));
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 1);
+        clock.advance(Duration::from_nanos(1));
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+        assert_eq!(client.ttl("key").unwrap().raw(), -2);
+        assert_eq!(client.dbsize::<i64>().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_pexpire_at_round_trips() {
+        let (mut client, clock) = client_with_clock();
+        let at = now_millis(&clock) + 10_500;
+        let _: () = client.set("key", "value").unwrap();
+        assert!(client.pexpire_at("key", at).unwrap());
+        assert_eq!(client.pexpire_time("key").unwrap().raw(), at as isize);
+        // EXPIRETIME rounds to the nearest second.
+        assert_eq!(
+            client.expire_time("key").unwrap().raw(),
+            ((at + 500) / 1000) as isize
+        );
+        assert_eq!(client.pttl("key").unwrap().raw(), 10_500);
+
+        clock.advance(Duration::from_millis(10_500));
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_expire_at_seconds() {
+        let (mut client, clock) = client_with_clock();
+        let at = now_millis(&clock) / 1000 + 60;
+        let _: () = client.set("key", "value").unwrap();
+        assert!(client.expire_at("key", at).unwrap());
+        assert_eq!(client.expire_time("key").unwrap().raw(), at as isize);
+        assert_eq!(
+            client.pexpire_time("key").unwrap().raw(),
+            (at * 1000) as isize
+        );
+    }
+
+    #[test]
+    fn test_expire_at_in_the_past_deletes_key() {
+        let (mut client, clock) = client_with_clock();
+        let _: () = client.set("key", "value").unwrap();
+        let past = now_millis(&clock) / 1000 - 1;
+        assert!(client.expire_at("key", past).unwrap());
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+        assert!(!client.expire_at("key", past).unwrap());
+        assert!(!client
+            .pexpire_at("missing", now_millis(&clock) + 1000)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_expire_time_without_deadline() {
+        let (mut client, _clock) = client_with_clock();
+        let _: () = client.set("key", "value").unwrap();
+        assert_eq!(
+            client.expire_time("key").unwrap(),
+            IntegerReplyOrNoOp::ExistsButNotRelevant
+        );
+        assert_eq!(
+            client.pexpire_time("missing").unwrap(),
+            IntegerReplyOrNoOp::NotExists
+        );
+    }
+
+    #[test]
+    fn test_expire_at_through_cmd() {
+        let (mut client, clock) = client_with_clock();
+        let at = now_millis(&clock) + 5_000;
+        let _: () = client.set("key", "value").unwrap();
+        let set: bool = cmd("PEXPIREAT")
+            .arg("key")
+            .arg(at)
+            .query(&mut client)
+            .unwrap();
+        assert!(set);
+        let time: i64 = cmd("pexpiretime").arg("key").query(&mut client).unwrap();
+        assert_eq!(time, at);
+    }
+
+    #[test]
+    fn test_expire_options() {
+        let (mut client, _clock) = client_with_clock();
+        let _: () = client.set("key", "value").unwrap();
+
+        assert!(!client.expire_options("key", 100, ExpireOption::XX).unwrap());
+        // A key without a TTL has an infinite one: GT never applies, LT always does.
+        assert!(!client.expire_options("key", 100, ExpireOption::GT).unwrap());
+        assert!(client.expire_options("key", 100, ExpireOption::LT).unwrap());
+        assert!(!client.expire_options("key", 50, ExpireOption::NX).unwrap());
+
+        assert!(!client.expire_options("key", 50, ExpireOption::GT).unwrap());
+        assert!(client.expire_options("key", 200, ExpireOption::GT).unwrap());
+        assert!(!client.expire_options("key", 300, ExpireOption::LT).unwrap());
+        assert!(client
+            .pexpire_options("key", 150_000, ExpireOption::LT)
+            .unwrap());
+        assert!(client.expire_options("key", 20, ExpireOption::XX).unwrap());
+        assert_eq!(client.ttl("key").unwrap().raw(), 20);
+
+        assert!(!client
+            .expire_options("missing", 20, ExpireOption::NONE)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_expire_xx_combines_with_lt() {
+        let (mut client, _clock) = client_with_clock();
+        let xx_lt = ExpireOption::LT.xx(true);
+        let _: () = client.set("key", "value").unwrap();
+
+        // Unlike LT alone, XX LT needs an existing TTL.
+        assert!(!client.expire_options("key", 100, xx_lt).unwrap());
+        assert_eq!(client.ttl("key").unwrap().raw(), -1);
+
+        client.expire("key", 200).unwrap();
+        assert!(client.expire_options("key", 100, xx_lt).unwrap());
+        assert_eq!(client.ttl("key").unwrap().raw(), 100);
+
+        let set: bool = cmd("EXPIRE")
+            .arg("key")
+            .arg(50)
+            .arg("XX")
+            .arg("LT")
+            .query(&mut client)
+            .unwrap();
+        assert!(set);
+        assert_eq!(client.ttl("key").unwrap().raw(), 50);
+        let _: () = client.set("plain", "value").unwrap();
+        let set: bool = cmd("EXPIRE")
+            .arg("plain")
+            .arg(50)
+            .arg("LT")
+            .arg("XX")
+            .query(&mut client)
+            .unwrap();
+        assert!(!set);
+    }
+
+    #[test]
+    fn test_non_positive_ttl_deletes_key() {
+        let (mut client, _clock) = client_with_clock();
+        let _: () = client.set("a", "value").unwrap();
+        let _: () = client.set("b", "value").unwrap();
+        let _: () = client.set("c", "value").unwrap();
+        assert!(client.expire("a", -10).unwrap());
+        assert!(client.pexpire("b", 0).unwrap());
+        assert_eq!(client.exists::<_, i64>("a").unwrap(), 0);
+        assert_eq!(client.exists::<_, i64>("b").unwrap(), 0);
+        // The condition is checked before the key is deleted.
+        assert!(!client.expire_options("c", -1, ExpireOption::XX).unwrap());
+        assert_eq!(client.exists::<_, i64>("c").unwrap(), 1);
+        assert!(!client.expire("missing", -1).unwrap());
+    }
+
+    #[test]
+    fn test_pexpire_has_millisecond_precision() {
+        let (mut client, clock) = client_with_clock();
+        let _: () = client.set("key", "value").unwrap();
+        assert!(client.pexpire("key", 1500).unwrap());
+        assert_eq!(client.pttl("key").unwrap().raw(), 1500);
+        clock.advance(Duration::from_millis(1499));
+        assert_eq!(client.pttl("key").unwrap().raw(), 1);
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(client.pttl("key").unwrap(), IntegerReplyOrNoOp::NotExists);
+    }
+
+    #[test]
+    fn test_persist_reply() {
+        let (mut client, _clock) = client_with_clock();
+        let _: () = client.set("key", "value").unwrap();
+        assert!(!client.persist("key").unwrap());
+        client.expire("key", 10).unwrap();
+        assert!(client.persist("key").unwrap());
+        assert_eq!(
+            client.pttl("key").unwrap(),
+            IntegerReplyOrNoOp::ExistsButNotRelevant
+        );
+    }
+
+    #[test]
+    fn test_expire_options_through_cmd() {
+        let (mut client, _clock) = client_with_clock();
+        let _: () = client.set("key", "value").unwrap();
+        let set: bool = cmd("EXPIRE")
+            .arg("key")
+            .arg(10)
+            .arg("nx")
+            .query(&mut client)
+            .unwrap();
+        assert!(set);
+        let set: bool = cmd("EXPIRE")
+            .arg("key")
+            .arg(20)
+            .arg("XX")
+            .arg("GT")
+            .query(&mut client)
+            .unwrap();
+        assert!(set);
+        let err = cmd("EXPIRE")
+            .arg("key")
+            .arg(10)
+            .arg("NX")
+            .arg("GT")
+            .query::<bool>(&mut client)
+            .unwrap_err();
+        assert!(err.to_string().contains("not compatible"));
+        assert!(cmd("EXPIRE")
+            .arg("key")
+            .arg(10)
+            .arg("GT")
+            .arg("LT")
+            .query::<bool>(&mut client)
+            .is_err());
+        assert!(cmd("EXPIRE")
+            .arg("key")
+            .arg(10)
+            .arg("BOGUS")
+            .query::<bool>(&mut client)
+            .is_err());
+        let err = cmd("EXPIRE")
+            .arg("key")
+            .arg(i64::MAX)
+            .query::<bool>(&mut client)
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid expire time"));
+    }
+
+    #[test]
+    fn test_stream_ids_follow_clock() {
+        let (mut client, clock) = client_with_clock();
+        let millis = clock
+            .system_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        let id: String = client.xadd("stream", None, vec![("f", "v")]).unwrap();
+        assert_eq!(id, format!("{}-0", millis));
+
+        clock.advance(Duration::from_secs(1));
+        let id: String = client.xadd("stream", None, vec![("f", "v")]).unwrap();
+        assert_eq!(id, format!("{}-0", millis + 1000));
+    }
+
+    #[test]
+    fn test_time_follows_clock() {
+        let (mut client, clock) = client_with_clock();
+        let secs = clock
+            .system_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        clock.advance(Duration::from_secs(60));
+        let time: Vec<Value> = cmd("TIME").query(&mut client).unwrap();
+        assert_eq!(time[0], Value::String((secs + 60).to_string().into_bytes()));
+    }
+}
+
+mod builder_tests {
+    use not_redis::clock::TestClock;
+    use not_redis::{blocking, Client, Commands, HasherKind, StorageEngine};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_client_builder() {
+        let mut client = Client::builder()
+            .shards(64)
+            .initial_capacity(1024)
+            .sweep_interval(Duration::from_millis(10))
+            .active_expire_effort(5)
+            .hash_capacity(8)
+            .build();
+        client.start().await;
+        let _: () = client.set("key", "value").await.unwrap();
+        let _: () = client.hset("hash", "field", "value").await.unwrap();
+        let value: String = client.get("key").await.unwrap();
+        assert_eq!(value, "value");
+        let field: String = client.hget("hash", "field").await.unwrap();
+        assert_eq!(field, "value");
+    }
+
+    #[test]
+    fn test_sip_hasher() {
+        let storage = StorageEngine::builder().hasher(HasherKind::SipHash).build();
+        let mut client = blocking::Client::from_storage(storage);
+        for i in 0..100 {
+            let _: () = client.set(format!("key:{}", i), i).unwrap();
+        }
+        assert_eq!(client.dbsize::<i64>().unwrap(), 100);
+        let value: i64 = client.get("key:42").unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn test_builder_clock() {
+        let clock = TestClock::new();
+        let storage = StorageEngine::builder().clock(clock.clone()).build();
+        let mut client = blocking::Client::from_storage(storage);
+        let _: () = client.set("key", "value").unwrap();
+        client.expire("key", 1).unwrap();
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+    }
+}
+
+mod database_tests {
+    use not_redis::clock::TestClock;
+    use not_redis::{blocking, cmd, Client, Commands, CopyOptions, RedisError, StorageEngine};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_select_isolates_keyspaces() {
+        let mut client = Client::new();
+        let _: () = client.set("key", "zero").await.unwrap();
+        let _: () = client.select(1).await.unwrap();
+        assert_eq!(client.exists::<_, i64>("key").await.unwrap(), 0);
+        let _: () = client.set("key", "one").await.unwrap();
+        assert_eq!(client.dbsize::<i64>().await.unwrap(), 1);
+
+        let _: () = client.select(0).await.unwrap();
+        let value: String = client.get("key").await.unwrap();
+        assert_eq!(value, "zero");
+    }
+
+    #[test]
+    fn test_select_out_of_range() {
+        let mut client =
+            blocking::Client::from_storage(StorageEngine::builder().databases(2).build());
+        assert!(client.select::<()>(1).is_ok());
+        assert!(matches!(
+            client.select::<()>(2),
+            Err(RedisError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            client.select::<()>(-1),
+            Err(RedisError::InvalidArgument(_))
+        ));
+        let _: () = client.set("key", "one").unwrap();
+        let value: String = client.get("key").unwrap();
+        assert_eq!(value, "one");
+    }
+
+    #[test]
+    fn test_selection_is_per_client() {
+        let storage = StorageEngine::new();
+        let mut a = blocking::Client::from_storage(storage.clone());
+        let mut b = blocking::Client::from_storage(storage);
+        let _: () = a.select(3).unwrap();
+        let _: () = a.set("key", "three").unwrap();
+        assert_eq!(b.exists::<_, i64>("key").unwrap(), 0);
+        let _: () = b.select(3).unwrap();
+        assert_eq!(b.exists::<_, i64>("key").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_select_through_cmd() {
+        let mut client = blocking::Client::new();
+        let _: () = cmd("SELECT").arg(5).query(&mut client).unwrap();
+        let _: () = client.set("key", "five").unwrap();
+        let _: () = Commands::select(&mut client, 0).unwrap();
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_swapdb_is_seen_by_every_client() {
+        let storage = StorageEngine::new();
+        let mut a = blocking::Client::from_storage(storage.clone());
+        let mut b = blocking::Client::from_storage(storage);
+        let _: () = a.set("key", "zero").unwrap();
+        let _: () = b.select(1).unwrap();
+        let _: () = b.set("other", "one").unwrap();
+
+        let _: () = a.swapdb(0, 1).unwrap();
+        assert_eq!(a.exists::<_, i64>("other").unwrap(), 1);
+        assert_eq!(a.exists::<_, i64>("key").unwrap(), 0);
+        let value: String = b.get("key").unwrap();
+        assert_eq!(value, "zero");
+
+        assert!(a.swapdb::<()>(0, 16).is_err());
+        let result: Result<(), _> = cmd("SWAPDB").arg("x").arg(1).query(&mut a);
+        assert!(
+            matches!(result, Err(RedisError::InvalidArgument(msg)) if msg == "invalid first DB index")
+        );
+    }
+
+    #[test]
+    fn test_swapdb_moves_deadlines() {
+        let clock = TestClock::new();
+        let storage = StorageEngine::new().with_clock(clock.clone());
+        let mut client = blocking::Client::from_storage(storage);
+        let _: () = client.set("session", "data").unwrap();
+        client.expire("session", 10).unwrap();
+        let _: () = client.swapdb(0, 1).unwrap();
+
+        let _: () = client.select(1).unwrap();
+        assert_eq!(client.ttl("session").unwrap().raw(), 10);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(client.exists::<_, i64>("session").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_move_key() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("key", "value").unwrap();
+        client.expire("key", 100).unwrap();
+
+        assert!(client.move_key::<_, bool>("key", 1).unwrap());
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+        assert!(!client.move_key::<_, bool>("key", 1).unwrap());
+
+        let _: () = client.select(1).unwrap();
+        assert_eq!(client.ttl("key").unwrap().raw(), 100);
+        let _: () = client.select(0).unwrap();
+        let _: () = client.set("key", "new").unwrap();
+        assert!(!client.move_key::<_, bool>("key", 1).unwrap());
+        let value: String = client.get("key").unwrap();
+        assert_eq!(value, "new");
+
+        assert!(matches!(
+            client.move_key::<_, ()>("key", 0),
+            Err(RedisError::InvalidArgument(msg)) if msg == "source and destination objects are the same"
+        ));
+        assert!(client.move_key::<_, ()>("key", 16).is_err());
+    }
+
+    #[test]
+    fn test_flushdb_and_flushall() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("a", 1).unwrap();
+        let _: () = client.select(1).unwrap();
+        let _: () = client.set("b", 2).unwrap();
+        let _: () = client.select(2).unwrap();
+        let _: () = client.set("c", 3).unwrap();
+
+        let _: () = client.flushdb().unwrap();
+        assert_eq!(client.dbsize::<i64>().unwrap(), 0);
+        let _: () = client.select(1).unwrap();
+        assert_eq!(client.dbsize::<i64>().unwrap(), 1);
+
+        let _: () = client.flushall().unwrap();
+        assert_eq!(client.dbsize::<i64>().unwrap(), 0);
+        let _: () = client.select(0).unwrap();
+        assert_eq!(client.dbsize::<i64>().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_flush_modes() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("a", 1).unwrap();
+        for args in [&["garbage"][..], &["ASYNC", "SYNC"]] {
+            for name in ["FLUSHDB", "FLUSHALL"] {
+                let result: Result<(), _> = cmd(name).arg(args).query(&mut client);
+                assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+            }
+        }
+        assert_eq!(client.dbsize::<i64>().unwrap(), 1);
+
+        let _: () = cmd("FLUSHDB").arg("async").query(&mut client).unwrap();
+        assert_eq!(client.dbsize::<i64>().unwrap(), 0);
+        let _: () = client.set("a", 1).unwrap();
+        let _: () = cmd("FLUSHALL").arg("SYNC").query(&mut client).unwrap();
+        assert_eq!(client.dbsize::<i64>().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_copy_to_another_db() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("src", "value").unwrap();
+        let copied: bool = client
+            .copy("src", "src", CopyOptions::default().db(1))
+            .unwrap();
+        assert!(copied);
+        let result: Result<bool, _> = client.copy("src", "src", CopyOptions::default());
+        assert!(result.is_err());
+        let result: Result<bool, _> = client.copy("src", "dst", CopyOptions::default().db(16));
+        assert!(result.is_err());
+
+        let _: () = client.select(1).unwrap();
+        let value: String = client.get("src").unwrap();
+        assert_eq!(value, "value");
+    }
+}
+
+mod namespace_tests {
+    use not_redis::{blocking, cmd, Client, Commands, ScanOptions, StorageEngine, Value};
+
+    #[tokio::test]
+    async fn test_namespace_prefixes_keys() {
+        let mut root = Client::new();
+        let mut billing = root.namespace("billing:");
+        let _: () = billing.set("invoice:1", "paid").await.unwrap();
+
+        let value: String = root.get("billing:invoice:1").await.unwrap();
+        assert_eq!(value, "paid");
+        assert_eq!(root.exists::<_, i64>("invoice:1").await.unwrap(), 0);
+        let value: String = billing.get("invoice:1").await.unwrap();
+        assert_eq!(value, "paid");
+    }
+
+    #[test]
+    fn test_keys_are_stripped() {
+        let root = blocking::Client::new();
+        let mut billing = root.namespace("billing:");
+        let mut auth = root.namespace("auth:");
+        let _: () = billing.set("invoice:1", 1).unwrap();
+        let _: () = billing.set("invoice:2", 2).unwrap();
+        let _: () = auth.set("invoice:3", 3).unwrap();
+
+        let mut keys: Vec<String> = billing.keys("*").unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["invoice:1", "invoice:2"]);
+        let keys: Vec<String> = billing.keys("invoice:?").unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(billing.dbsize::<i64>().unwrap(), 2);
+        assert_eq!(auth.dbsize::<i64>().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_scan_walks_only_the_namespace() {
+        let mut root = blocking::Client::new();
+        let mut billing = root.namespace("billing:");
+        for i in 0..25 {
+            let _: () = billing.set(format!("invoice:{i}"), i).unwrap();
+            let _: () = root.set(format!("other:{i}"), i).unwrap();
+        }
+        let _: () = billing.rpush("queue", "job").unwrap();
+
+        let scan_all = |client: &mut blocking::Client, options: ScanOptions| {
+            let mut all = Vec::new();
+            let mut cursor = 0;
+            loop {
+                let (next, keys): (u64, Vec<String>) =
+                    client.scan(cursor, options.clone()).unwrap();
+                all.extend(keys);
+                if next == 0 {
+                    break;
+                }
+                cursor = next;
+            }
+            all.sort();
+            all
+        };
+
+        let keys = scan_all(&mut billing, ScanOptions::default().count(4));
+        assert_eq!(keys.len(), 26);
+        assert!(keys
+            .iter()
+            .all(|key| key.starts_with("invoice:") || key == "queue"));
+        let keys = scan_all(&mut billing, ScanOptions::default().pattern("invoice:1?"));
+        assert_eq!(keys.len(), 10);
+        let keys = scan_all(&mut billing, ScanOptions::default().key_type("list"));
+        assert_eq!(keys, vec!["queue"]);
+        assert_eq!(scan_all(&mut root, ScanOptions::default()).len(), 51);
+    }
+
+    #[test]
+    fn test_scan_rejects_bad_arguments() {
+        let mut client = blocking::Client::new();
+        assert!(cmd("SCAN").arg("abc").query::<Value>(&mut client).is_err());
+        assert!(cmd("SCAN").arg(-1).query::<Value>(&mut client).is_err());
+        let zero_count = cmd("SCAN")
+            .arg(0)
+            .arg("COUNT")
+            .arg(0)
+            .query::<Value>(&mut client);
+        assert!(zero_count.is_err());
+        let dangling = cmd("SCAN").arg(0).arg("MATCH").query::<Value>(&mut client);
+        assert!(dangling.is_err());
+    }
+
+    #[test]
+    fn test_flushdb_clears_only_namespace() {
+        let mut root = blocking::Client::new();
+        let mut billing = root.namespace("billing:");
+        let _: () = root.set("global", 1).unwrap();
+        let _: () = billing.set("invoice:1", 1).unwrap();
+        billing.expire("invoice:1", 60).unwrap();
+
+        let _: () = billing.flushdb().unwrap();
+        assert_eq!(billing.dbsize::<i64>().unwrap(), 0);
+        assert_eq!(root.dbsize::<i64>().unwrap(), 1);
+        assert_eq!(root.exists::<_, i64>("global").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_namespaces_nest() {
+        let mut root = blocking::Client::new();
+        let mut invoices = root.namespace("billing:").namespace("invoices:");
+        let _: () = invoices.set("1", "paid").unwrap();
+        assert_eq!(root.exists::<_, i64>("billing:invoices:1").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_namespace_applies_to_every_command() {
+        let mut root = blocking::Client::new();
+        let mut ns = root.namespace("ns:");
+        let _: i64 = Commands::sadd(&mut ns, "tags", "rust").unwrap();
+        let _: () = cmd("RENAME")
+            .arg("tags")
+            .arg("labels")
+            .query(&mut ns)
+            .unwrap();
+        ns.expire("labels", 60).unwrap();
+        let moved: bool = ns.move_key("labels", 1).unwrap();
+        assert!(moved);
+
+        let _: () = root.select(1).unwrap();
+        let members: Vec<String> = root.smembers("ns:labels").unwrap();
+        assert_eq!(members, vec!["rust"]);
+        assert!(root.ttl("ns:labels").unwrap().raw() > 0);
+    }
+
+    #[test]
+    fn test_namespace_keeps_selected_db() {
+        let storage = StorageEngine::new();
+        let mut root = blocking::Client::from_storage(storage);
+        let _: () = root.select(2).unwrap();
+        let mut ns = root.namespace("ns:");
+        let _: () = ns.set("key", "value").unwrap();
+        assert_eq!(root.exists::<_, i64>("ns:key").unwrap(), 1);
+    }
+}
+
+mod set_options_tests {
+    use super::{client_with_clock, now_millis};
+    use not_redis::{
+        blocking, cmd, Client, Commands, ExistenceCheck, Expiry, RedisError, SetExpiry, SetOptions,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_set_nx_and_xx() {
+        let mut client = blocking::Client::new();
+        let nx = SetOptions::default().conditional_set(ExistenceCheck::NX);
+        let xx = SetOptions::default().conditional_set(ExistenceCheck::XX);
+
+        let set: bool = client.set_options("key", "first", xx).unwrap();
+        assert!(!set);
+        let set: bool = client.set_options("key", "first", nx).unwrap();
+        assert!(set);
+        let set: bool = client.set_options("key", "second", nx).unwrap();
+        assert!(!set);
+        let set: bool = client.set_options("key", "third", xx).unwrap();
+        assert!(set);
+        let value: String = client.get("key").unwrap();
+        assert_eq!(value, "third");
+    }
+
+    #[test]
+    fn test_set_get_returns_previous_value() {
+        let mut client = blocking::Client::new();
+        let get = SetOptions::default().get(true);
+        let previous: Option<String> = client.set_options("key", "first", get).unwrap();
+        assert_eq!(previous, None);
+        let previous: Option<String> = client.set_options("key", "second", get).unwrap();
+        assert_eq!(previous.as_deref(), Some("first"));
+
+        // With NX the old value is returned even though nothing is written.
+        let options = get.conditional_set(ExistenceCheck::NX);
+        let previous: Option<String> = client.set_options("key", "third", options).unwrap();
+        assert_eq!(previous.as_deref(), Some("second"));
+        let value: String = client.get("key").unwrap();
+        assert_eq!(value, "second");
+
+        let _: () = client.sadd("set", "member").unwrap();
+        let result: Result<Option<String>, _> = client.set_options("set", "value", get);
+        assert!(matches!(result, Err(RedisError::WrongType)));
+        let key_type: String = client.key_type("set").unwrap();
+        assert_eq!(key_type, "set");
+    }
+
+    #[test]
+    fn test_set_expirations() {
+        let (mut client, clock) = client_with_clock();
+        let now = now_millis(&clock) as u64;
+        let cases = [
+            ("ex", SetExpiry::EX(10)),
+            ("px", SetExpiry::PX(10_000)),
+            ("exat", SetExpiry::EXAT(now / 1000 + 10)),
+            ("pxat", SetExpiry::PXAT(now + 10_000)),
+        ];
+        for (key, expiry) in cases {
+            let options = SetOptions::default().with_expiration(expiry);
+            let _: () = client.set_options(key, "value", options).unwrap();
+            let ttl = client.pttl(key).unwrap().raw();
+            assert!((9_000..=10_000).contains(&ttl), "{key}: {ttl}");
+        }
+
+        clock.advance(Duration::from_secs(10));
+        for (key, _) in cases {
+            assert_eq!(client.exists::<_, i64>(key).unwrap(), 0, "{key}");
+        }
+    }
+
+    #[test]
+    fn test_set_clears_or_keeps_ttl() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set_ex("key", "first", 100).unwrap();
+        let keep = SetOptions::default().with_expiration(SetExpiry::KEEPTTL);
+        let _: () = client.set_options("key", "second", keep).unwrap();
+        assert!(client.ttl("key").unwrap().raw() > 0);
+        let value: String = client.get("key").unwrap();
+        assert_eq!(value, "second");
+
+        let _: () = client.set("key", "third").unwrap();
+        assert_eq!(client.ttl("key").unwrap().raw(), -1);
+    }
+
+    #[test]
+    fn test_set_with_past_deadline_deletes_key() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("key", "value").unwrap();
+        let options = SetOptions::default().with_expiration(SetExpiry::EXAT(1));
+        let _: () = client.set_options("key", "new", options).unwrap();
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_set_rejects_invalid_expire_time() {
+        let mut client = blocking::Client::new();
+        let options = SetOptions::default().with_expiration(SetExpiry::EX(0));
+        let result: Result<(), _> = client.set_options("key", "value", options);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        assert!(client.set_ex::<_, _, ()>("key", "value", 0).is_err());
+        assert!(client.pset_ex::<_, _, ()>("key", "value", 0).is_err());
+        let result: Result<(), _> = cmd("SETEX")
+            .arg("key")
+            .arg(-1)
+            .arg("value")
+            .query(&mut client);
+        assert!(result.is_err());
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_setnx_setex_psetex() {
+        let mut client = blocking::Client::new();
+        assert!(client.set_nx::<_, _, bool>("key", "first").unwrap());
+        assert!(!client.set_nx::<_, _, bool>("key", "second").unwrap());
+
+        let _: () = client.set_ex("ex", "value", 10).unwrap();
+        assert_eq!(client.ttl("ex").unwrap().raw(), 10);
+        let _: () = client.pset_ex("px", "value", 1500).unwrap();
+        let ttl = client.pttl("px").unwrap().raw();
+        assert!((1..=1500).contains(&ttl));
+    }
+
+    #[test]
+    fn test_getset_and_getdel() {
+        let mut client = blocking::Client::new();
+        let old: Option<String> = client.getset("key", "first").unwrap();
+        assert_eq!(old, None);
+        let old: String = client.getset("key", "second").unwrap();
+        assert_eq!(old, "first");
+
+        let value: String = client.get_del("key").unwrap();
+        assert_eq!(value, "second");
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+        let value: Option<String> = client.get_del("key").unwrap();
+        assert_eq!(value, None);
+
+        let _: () = client.sadd("set", "member").unwrap();
+        let result: Result<Option<String>, _> = client.get_del("set");
+        assert!(matches!(result, Err(RedisError::WrongType)));
+        assert_eq!(client.exists::<_, i64>("set").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_getex() {
+        let (mut client, clock) = client_with_clock();
+        let _: () = client.set("key", "value").unwrap();
+        let value: String = client.get_ex("key", Expiry::EX(10)).unwrap();
+        assert_eq!(value, "value");
+        assert_eq!(client.ttl("key").unwrap().raw(), 10);
+
+        let _: String = client.get_ex("key", Expiry::PERSIST).unwrap();
+        assert_eq!(client.ttl("key").unwrap().raw(), -1);
+
+        let _: String = client.get_ex("key", Expiry::PX(500)).unwrap();
+        clock.advance(Duration::from_millis(500));
+        let value: Option<String> = client.get_ex("key", Expiry::EX(10)).unwrap();
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn test_set_options_through_cmd() {
+        let mut client = blocking::Client::new();
+        let reply: Option<String> = cmd("SET")
+            .arg("key")
+            .arg("value")
+            .arg("nx")
+            .arg("px")
+            .arg(5000)
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(reply.as_deref(), Some("OK"));
+        assert!(client.ttl("key").unwrap().raw() > 0);
+
+        for args in [
+            vec!["NX", "XX"],
+            vec!["EX", "10", "PX", "100"],
+            vec!["EX", "10", "KEEPTTL"],
+            vec!["EX"],
+            vec!["BOGUS"],
+        ] {
+            let mut set = cmd("SET");
+            set.arg("key").arg("value");
+            for arg in &args {
+                set.arg(*arg);
+            }
+            let result: Result<(), _> = set.query(&mut client);
+            assert!(
+                matches!(result, Err(RedisError::InvalidArgument(_))),
+                "{args:?}"
+            );
+        }
+
+        let value: String = cmd("GETEX")
+            .arg("key")
+            .arg("PERSIST")
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(value, "value");
+        assert_eq!(client.ttl("key").unwrap().raw(), -1);
+    }
+
+    #[tokio::test]
+    async fn test_async_set_options() {
+        let mut client = Client::new();
+        let options = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .get(true);
+        let old: Option<String> = client.set_options("key", "value", options).await.unwrap();
+        assert_eq!(old, None);
+        assert!(!client.set_nx::<_, _, bool>("key", "other").await.unwrap());
+        let old: String = client.getset("key", "new").await.unwrap();
+        assert_eq!(old, "value");
+        let value: String = client.get_del("key").await.unwrap();
+        assert_eq!(value, "new");
+    }
+}
+
+mod counter_tests {
+    use not_redis::{blocking, cmd, Client, Commands, RedisError, StorageEngine};
+    use std::thread;
+
+    #[test]
+    fn test_incr_and_decr() {
+        let mut client = blocking::Client::new();
+        assert_eq!(client.incr::<_, _, i64>("counter", 1).unwrap(), 1);
+        assert_eq!(client.incr::<_, _, i64>("counter", 10).unwrap(), 11);
+        assert_eq!(client.decr::<_, _, i64>("counter", 20).unwrap(), -9);
+        let value: String = client.get("counter").unwrap();
+        assert_eq!(value, "-9");
+        let key_type: String = client.key_type("counter").unwrap();
+        assert_eq!(key_type, "string");
+
+        let reply: i64 = cmd("INCR").arg("counter").query(&mut client).unwrap();
+        assert_eq!(reply, -8);
+        let reply: i64 = cmd("DECR").arg("counter").query(&mut client).unwrap();
+        assert_eq!(reply, -9);
+    }
+
+    #[test]
+    fn test_incr_parses_existing_string() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("counter", "41").unwrap();
+        assert_eq!(client.incr::<_, _, i64>("counter", 1).unwrap(), 42);
+
+        for value in ["abc", "1.5", " 1", "+1", "01", "", "9223372036854775808"] {
+            let _: () = client.set("bad", value).unwrap();
+            let result: Result<i64, _> = client.incr("bad", 1);
+            assert!(
+                matches!(result, Err(RedisError::InvalidArgument(ref m)) if m.contains("not an integer")),
+                "{value:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_incr_overflow() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("counter", i64::MAX.to_string()).unwrap();
+        assert!(client.incr::<_, _, ()>("counter", 1).is_err());
+        let value: String = client.get("counter").unwrap();
+        assert_eq!(value, i64::MAX.to_string());
+
+        assert_eq!(
+            client.decr::<_, _, i64>("min", i64::MAX).unwrap(),
+            -i64::MAX
+        );
+        assert!(client.decr::<_, _, ()>("min", 2).is_err());
+        let result: Result<i64, _> = cmd("DECRBY").arg("other").arg(i64::MIN).query(&mut client);
+        assert!(result.is_err());
+        assert_eq!(client.exists::<_, i64>("other").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_incr_wrong_type() {
+        let mut client = blocking::Client::new();
+        let _: () = client.sadd("set", "member").unwrap();
+        assert!(matches!(
+            client.incr::<_, _, i64>("set", 1),
+            Err(RedisError::WrongType)
+        ));
+        assert!(matches!(
+            client.incr_by_float::<_, f64>("set", 1.0),
+            Err(RedisError::WrongType)
+        ));
+    }
+
+    #[test]
+    fn test_incr_keeps_ttl() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set_ex("counter", "1", 100).unwrap();
+        let _: () = client.incr("counter", 1).unwrap();
+        let _: () = client.incr_by_float("counter", 0.5).unwrap();
+        assert!(client.ttl("counter").unwrap().raw() > 0);
+    }
+
+    #[test]
+    fn test_incr_by_float() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("n", "10.5").unwrap();
+        assert_eq!(client.incr_by_float::<_, f64>("n", 0.1).unwrap(), 10.6);
+        let value: String = client.get("n").unwrap();
+        assert_eq!(value, "10.6");
+
+        let _: () = client.set("i", "3").unwrap();
+        assert_eq!(client.incr_by_float::<_, f64>("i", 2.0).unwrap(), 5.0);
+        let value: String = client.get("i").unwrap();
+        assert_eq!(value, "5");
+        // The float result can still be incremented as an integer.
+        assert_eq!(client.incr::<_, _, i64>("i", 1).unwrap(), 6);
+
+        let value: String = cmd("INCRBYFLOAT")
+            .arg("big")
+            .arg("5.0e3")
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(value, "5000");
+
+        let _: () = client.set("text", "abc").unwrap();
+        assert!(client.incr_by_float::<_, f64>("text", 1.0).is_err());
+        let result: Result<String, _> = cmd("INCRBYFLOAT").arg("n").arg("inf").query(&mut client);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_incr_by_float_error_does_not_create_key() {
+        let mut client = blocking::Client::new();
+        let result: Result<String, _> = cmd("INCRBYFLOAT").arg("f").arg("inf").query(&mut client);
+        assert!(
+            matches!(result, Err(RedisError::InvalidArgument(ref m)) if m.contains("NaN or Infinity"))
+        );
+        assert!(client
+            .incr_by_float::<_, f64>("f", f64::NEG_INFINITY)
+            .is_err());
+        assert_eq!(client.exists::<_, i64>("f").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_incr_by_float_formats_like_redis() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("k", "0.1").unwrap();
+        let reply: String = cmd("INCRBYFLOAT")
+            .arg("k")
+            .arg("0.2")
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(reply, "0.3");
+        let value: String = client.get("k").unwrap();
+        assert_eq!(value, "0.3");
+
+        let _: () = client.set("small", "0").unwrap();
+        let _: () = client.incr_by_float("small", 1.5e-7).unwrap();
+        let value: String = client.get("small").unwrap();
+        assert_eq!(value, "0.00000015");
+    }
+
+    #[test]
+    fn test_concurrent_incr_is_atomic() {
+        let storage = StorageEngine::new();
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let mut client = blocking::Client::from_storage(storage.clone());
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        let _: () = client.incr("counter", 1).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let mut client = blocking::Client::from_storage(storage);
+        let value: i64 = client.get("counter").unwrap();
+        assert_eq!(value, 8000);
+    }
+
+    #[tokio::test]
+    async fn test_async_counters() {
+        let mut client = Client::new();
+        assert_eq!(client.incr::<_, _, i64>("counter", 5).await.unwrap(), 5);
+        assert_eq!(client.decr::<_, _, i64>("counter", 2).await.unwrap(), 3);
+        assert_eq!(
+            client
+                .incr_by_float::<_, f64>("counter", 0.25)
+                .await
+                .unwrap(),
+            3.25
+        );
+        let value: f64 = Commands::incr_by_float(&mut client, "counter", 0.75).unwrap();
+        assert_eq!(value, 4.0);
+        let value: i64 = Commands::incr(&mut client, "counter", 1).unwrap();
+        assert_eq!(value, 5);
+    }
+}
+
+mod string_range_tests {
+    use not_redis::{blocking, cmd, Client, Commands, RedisError, Value};
+
+    #[test]
+    fn test_append_and_strlen() {
+        let mut client = blocking::Client::new();
+        assert_eq!(client.strlen::<_, i64>("key").unwrap(), 0);
+        assert_eq!(client.append::<_, _, i64>("key", "Hello").unwrap(), 5);
+        assert_eq!(client.append::<_, _, i64>("key", " World").unwrap(), 11);
+        assert_eq!(client.strlen::<_, i64>("key").unwrap(), 11);
+        let value: String = client.get("key").unwrap();
+        assert_eq!(value, "Hello World");
+
+        let _: () = client.incr("counter", 42).unwrap();
+        assert_eq!(client.strlen::<_, i64>("counter").unwrap(), 2);
+        assert_eq!(client.append::<_, _, i64>("counter", "0").unwrap(), 3);
+        assert_eq!(client.incr::<_, _, i64>("counter", 1).unwrap(), 421);
+    }
+
+    #[test]
+    fn test_getrange() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("key", "This is a string").unwrap();
+        let cases = [
+            (0, 3, "This"),
+            (-3, -1, "ing"),
+            (0, -1, "This is a string"),
+            (10, 100, "string"),
+            (5, 3, ""),
+            (-100, 3, "This"),
+            (-1, -5, ""),
+        ];
+        for (start, end, expected) in cases {
+            let value: String = client.getrange("key", start, end).unwrap();
+            assert_eq!(value, expected, "{start}..{end}");
+        }
+        let value: String = client.getrange("missing", 0, -1).unwrap();
+        assert_eq!(value, "");
+    }
+
+    #[test]
+    fn test_setrange() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("key", "Hello World").unwrap();
+        assert_eq!(client.setrange::<_, _, i64>("key", 6, "Redis").unwrap(), 11);
+        let value: String = client.get("key").unwrap();
+        assert_eq!(value, "Hello Redis");
+
+        assert_eq!(client.setrange::<_, _, i64>("padded", 3, "ab").unwrap(), 5);
+        let value: Vec<u8> = client.get("padded").unwrap();
+        assert_eq!(value, b"\0\0\0ab");
+
+        // An empty value changes nothing and does not create the key.
+        assert_eq!(client.setrange::<_, _, i64>("key", 100, "").unwrap(), 11);
+        assert_eq!(client.setrange::<_, _, i64>("missing", 5, "").unwrap(), 0);
+        assert_eq!(client.exists::<_, i64>("missing").unwrap(), 0);
+
+        let result: Result<i64, _> = cmd("SETRANGE")
+            .arg("key")
+            .arg(-1)
+            .arg("x")
+            .query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        assert!(client
+            .setrange::<_, _, ()>("key", 512 * 1024 * 1024, "x")
+            .is_err());
+    }
+
+    #[test]
+    fn test_range_commands_wrong_type() {
+        let mut client = blocking::Client::new();
+        let _: () = client.sadd("set", "member").unwrap();
+        assert!(matches!(
+            client.append::<_, _, i64>("set", "x"),
+            Err(RedisError::WrongType)
+        ));
+        assert!(matches!(
+            client.strlen::<_, ()>("set"),
+            Err(RedisError::WrongType)
+        ));
+        assert!(matches!(
+            client.setrange::<_, _, ()>("set", 0, "x"),
+            Err(RedisError::WrongType)
+        ));
+        let result: Result<String, _> = client.getrange("set", 0, -1);
+        assert!(matches!(result, Err(RedisError::WrongType)));
+        let result: Result<String, _> = client.lcs("set", "missing");
+        assert!(matches!(result, Err(RedisError::WrongType)));
+    }
+
+    #[test]
+    fn test_lcs() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("key1", "ohmytext").unwrap();
+        let _: () = client.set("key2", "mynewtext").unwrap();
+        let value: String = client.lcs("key1", "key2").unwrap();
+        assert_eq!(value, "mytext");
+        assert_eq!(client.lcs_len::<_, i64>("key1", "key2").unwrap(), 6);
+        assert_eq!(client.lcs_len::<_, i64>("key1", "missing").unwrap(), 0);
+
+        let reply: Value = client.lcs_idx("key1", "key2", 0, false).unwrap();
+        let range = |a: i64, b: i64| Value::Array(vec![Value::Int(a), Value::Int(b)]);
+        let expected = Value::Array(vec![
+            Value::String(b"matches".to_vec()),
+            Value::Array(vec![
+                Value::Array(vec![range(4, 7), range(5, 8)]),
+                Value::Array(vec![range(2, 3), range(0, 1)]),
+            ]),
+            Value::String(b"len".to_vec()),
+            Value::Int(6),
+        ]);
+        assert_eq!(reply, expected);
+
+        let reply: Value = cmd("LCS")
+            .arg("key1")
+            .arg("key2")
+            .arg("IDX")
+            .arg("MINMATCHLEN")
+            .arg(4)
+            .arg("WITHMATCHLEN")
+            .query(&mut client)
+            .unwrap();
+        let expected = Value::Array(vec![
+            Value::String(b"matches".to_vec()),
+            Value::Array(vec![Value::Array(vec![
+                range(4, 7),
+                range(5, 8),
+                Value::Int(4),
+            ])]),
+            Value::String(b"len".to_vec()),
+            Value::Int(6),
+        ]);
+        assert_eq!(reply, expected);
+
+        let result: Result<Value, _> = cmd("LCS")
+            .arg("key1")
+            .arg("key2")
+            .arg("LEN")
+            .arg("IDX")
+            .query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_lcs_rejects_oversized_table() {
+        let mut client = blocking::Client::new();
+        // (12_001 * 12_001) four-byte cells come to more than 512 MB.
+        let _: () = client.set("key1", "a".repeat(12_000)).unwrap();
+        let _: () = client.set("key2", "b".repeat(12_000)).unwrap();
+        let result: Result<i64, _> = client.lcs_len("key1", "key2");
+        let Err(RedisError::InvalidArgument(message)) = result else {
+            panic!("expected an error, got {result:?}");
+        };
+        assert!(message.contains("exceeds proto-max-bulk-len"));
+    }
+
+    #[tokio::test]
+    async fn test_async_string_ranges() {
+        let mut client = Client::new();
+        assert_eq!(client.append::<_, _, i64>("key", "abc").await.unwrap(), 3);
+        assert_eq!(
+            client.setrange::<_, _, i64>("key", 1, "X").await.unwrap(),
+            3
+        );
+        let value: String = client.getrange("key", 0, -1).await.unwrap();
+        assert_eq!(value, "aXc");
+        assert_eq!(client.strlen::<_, i64>("key").await.unwrap(), 3);
+    }
+}
+
+mod multi_key_tests {
+    use not_redis::{blocking, cmd, Client, Commands, RedisError, StorageEngine};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_variadic_del_and_exists() {
+        let mut client = blocking::Client::new();
+        let _: () = client.mset(&[("a", "1"), ("b", "2"), ("c", "3")]).unwrap();
+        assert_eq!(client.exists::<_, i64>(["a", "b", "missing"]).unwrap(), 2);
+        // A key given twice counts twice, as in Redis.
+        assert_eq!(client.exists::<_, i64>(&["a", "a"][..]).unwrap(), 2);
+        assert_eq!(client.touch::<_, i64>(vec!["a", "c"]).unwrap(), 2);
+
+        assert_eq!(client.del::<_, i64>(["a", "missing"]).unwrap(), 1);
+        let keys = vec!["b".to_string(), "c".to_string()];
+        assert_eq!(client.unlink::<_, i64>(&keys).unwrap(), 2);
+        assert_eq!(client.exists::<_, i64>(["a", "b", "c"]).unwrap(), 0);
+
+        let n: i64 = cmd("DEL").arg("x").arg("y").query(&mut client).unwrap();
+        assert_eq!(n, 0);
+        let result: Result<i64, _> = cmd("EXISTS").query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_mset_and_mget() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set_ex("a", "old", 100).unwrap();
+        let _: () = client.mset(&[("a", "1"), ("b", "2")]).unwrap();
+        assert_eq!(client.ttl("a").unwrap().raw(), -1);
+        let _: () = client.sadd("set", "member").unwrap();
+
+        let values: Vec<Option<String>> = client.mget(["a", "missing", "b", "set"]).unwrap();
+        assert_eq!(
+            values,
+            vec![Some("1".to_string()), None, Some("2".to_string()), None]
+        );
+        let values: Vec<Option<i64>> = client.mget(["a"]).unwrap();
+        assert_eq!(values, vec![Some(1)]);
+
+        let result: Result<(), _> = cmd("MSET").arg("a").arg("1").arg("b").query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_mset_nx() {
+        let mut client = blocking::Client::new();
+        assert!(client
+            .mset_nx::<_, _, bool>(&[("a", "1"), ("b", "2")])
+            .unwrap());
+        assert!(!client
+            .mset_nx::<_, _, bool>(&[("b", "3"), ("c", "4")])
+            .unwrap());
+        assert_eq!(client.exists::<_, i64>("c").unwrap(), 0);
+        let value: String = client.get("b").unwrap();
+        assert_eq!(value, "2");
+
+        // The last value for a repeated key wins.
+        assert!(client
+            .mset_nx::<_, _, bool>(&[("d", "1"), ("d", "2")])
+            .unwrap());
+        let value: String = client.get("d").unwrap();
+        assert_eq!(value, "2");
+
+        let set: i64 = cmd("MSETNX").arg("a").arg("x").query(&mut client).unwrap();
+        assert_eq!(set, 0);
+    }
+
+    #[test]
+    fn test_mset_is_atomic_for_mget() {
+        let storage = StorageEngine::new();
+        let done = Arc::new(AtomicBool::new(false));
+        let keys: Vec<String> = (0..16).map(|i| format!("key:{i}")).collect();
+
+        let writer = {
+            let mut client = blocking::Client::from_storage(storage.clone());
+            let keys = keys.clone();
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                for round in 0..2000 {
+                    let items: Vec<_> = keys.iter().map(|k| (k.as_str(), round)).collect();
+                    let _: () = client.mset(&items).unwrap();
+                }
+                done.store(true, Ordering::Relaxed);
+            })
+        };
+
+        let mut client = blocking::Client::from_storage(storage);
+        while !done.load(Ordering::Relaxed) {
+            let values: Vec<Option<i64>> = client.mget(&keys).unwrap();
+            assert!(
+                values.windows(2).all(|pair| pair[0] == pair[1]),
+                "{values:?}"
+            );
+        }
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_mset_nx_sets_once() {
+        let storage = StorageEngine::new();
+        let handles: Vec<_> = (0..8)
+            .map(|id| {
+                let mut client = blocking::Client::from_storage(storage.clone());
+                thread::spawn(move || client.mset_nx(&[("x", id), ("y", id)]).unwrap())
+            })
+            .collect();
+        let winners = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .filter(|&set| set)
+            .count();
+        assert_eq!(winners, 1);
+
+        let mut client = blocking::Client::from_storage(storage);
+        let values: Vec<Option<i64>> = client.mget(["x", "y"]).unwrap();
+        assert_eq!(values[0], values[1]);
+    }
+
+    #[test]
+    fn test_concurrent_renamenx_and_copy_claim_once() {
+        for command in ["RENAMENX", "COPY"] {
+            let storage = StorageEngine::new();
+            let handles: Vec<_> = (0..8)
+                .map(|id| {
+                    let mut client = blocking::Client::from_storage(storage.clone());
+                    thread::spawn(move || {
+                        let own = format!("candidate:{id}");
+                        let _: () = client.set(&own, id).unwrap();
+                        let claimed: bool = cmd(command)
+                            .arg(own)
+                            .arg("lock")
+                            .query(&mut client)
+                            .unwrap();
+                        claimed.then_some(id)
+                    })
+                })
+                .collect();
+            let winners: Vec<i64> = handles
+                .into_iter()
+                .filter_map(|handle| handle.join().unwrap())
+                .collect();
+            assert_eq!(winners.len(), 1, "{command}");
+
+            let mut client = blocking::Client::from_storage(storage);
+            let holder: i64 = client.get("lock").unwrap();
+            assert_eq!(holder, winners[0], "{command}");
+        }
+    }
+
+    #[test]
+    fn test_rename_to_itself() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set_ex("key", "value", 100).unwrap();
+        let _: () = client.rename("key", "key").unwrap();
+        assert!(!client.rename_nx::<_, _, bool>("key", "key").unwrap());
+        let value: String = client.get("key").unwrap();
+        assert_eq!(value, "value");
+        assert!(client.ttl("key").unwrap().raw() > 0);
+
+        let _: () = client.rename("key", "other").unwrap();
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+        assert!(client.ttl("other").unwrap().raw() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_async_multi_key() {
+        let mut client = Client::new();
+        let _: () = client.mset(&[("a", 1), ("b", 2)]).await.unwrap();
+        assert!(!client.mset_nx::<_, _, bool>(&[("a", 3)]).await.unwrap());
+        let values: Vec<Option<i64>> = client.mget(["a", "b", "c"]).await.unwrap();
+        assert_eq!(values, vec![Some(1), Some(2), None]);
+        assert_eq!(client.exists::<_, i64>(["a", "b"]).await.unwrap(), 2);
+        let removed: i64 = Commands::del(&mut client, ["a", "b"]).unwrap();
+        assert_eq!(removed, 2);
+    }
+}
+
+mod bitmap_tests {
+    use not_redis::{
+        blocking, cmd, BitFieldOffset, BitFieldOps, BitFieldOverflow, BitFieldType, BitUnit,
+        Client, Commands, RedisError, Value,
+    };
+
+    #[test]
+    fn test_setbit_and_getbit() {
+        let mut client = blocking::Client::new();
+        assert!(!client.setbit("key", 7, true).unwrap());
+        assert!(client.setbit("key", 7, true).unwrap());
+        assert!(client.getbit("key", 7).unwrap());
+        assert!(!client.getbit("key", 6).unwrap());
+        assert!(!client.getbit("key", 1000).unwrap());
+        assert!(!client.getbit("missing", 0).unwrap());
+
+        // Setting a bit far out pads the string with zero bytes.
+        client.setbit("key", 23, true).unwrap();
+        let value: Vec<u8> = client.get("key").unwrap();
+        assert_eq!(value, vec![0x01, 0x00, 0x01]);
+        assert!(client.setbit("key", 7, false).unwrap());
+        let value: Vec<u8> = client.get("key").unwrap();
+        assert_eq!(value, vec![0x00, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_setbit_keeps_ttl() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set_ex("key", "a", 100).unwrap();
+        client.setbit("key", 0, true).unwrap();
+        assert!(client.ttl("key").unwrap().raw() > 0);
+    }
+
+    #[test]
+    fn test_bitcount() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("key", "foobar").unwrap();
+        assert_eq!(client.bitcount("key").unwrap(), 26);
+        assert_eq!(client.bitcount_range("key", 0, 0).unwrap(), 4);
+        assert_eq!(client.bitcount_range("key", 1, 1).unwrap(), 6);
+        assert_eq!(client.bitcount_range("key", -2, -1).unwrap(), 7);
+        assert_eq!(client.bitcount_range("key", 3, 1).unwrap(), 0);
+        assert_eq!(
+            client
+                .bitcount_range_unit("key", 5, 30, BitUnit::Bit)
+                .unwrap(),
+            17
+        );
+        assert_eq!(client.bitcount("missing").unwrap(), 0);
+        let _: () = client.set("empty", "").unwrap();
+        assert_eq!(client.bitcount("empty").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_bitcount_syntax() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("key", "foobar").unwrap();
+        let n: usize = cmd("BITCOUNT")
+            .arg("key")
+            .arg(1)
+            .arg(1)
+            .arg("bit")
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(n, 1);
+        let result: Result<usize, _> = cmd("BITCOUNT").arg("key").arg(1).query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        let result: Result<usize, _> = cmd("BITCOUNT")
+            .arg("key")
+            .arg(0)
+            .arg(1)
+            .arg("WORD")
+            .query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_bitpos() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("key", vec![0xffu8, 0xf0, 0x00]).unwrap();
+        assert_eq!(client.bitpos("key", false).unwrap(), 12);
+        assert_eq!(client.bitpos("key", true).unwrap(), 0);
+        assert_eq!(
+            client
+                .bitpos_range("key", true, 2, Some(-1), BitUnit::Byte)
+                .unwrap(),
+            -1
+        );
+        assert_eq!(
+            client
+                .bitpos_range("key", true, 7, Some(15), BitUnit::Bit)
+                .unwrap(),
+            7
+        );
+        assert_eq!(
+            client
+                .bitpos_range("key", false, 1, None, BitUnit::Byte)
+                .unwrap(),
+            12
+        );
+
+        // A string of ones counts as padded with zeros unless an end is given.
+        let _: () = client.set("ones", vec![0xffu8, 0xff]).unwrap();
+        assert_eq!(client.bitpos("ones", false).unwrap(), 16);
+        assert_eq!(
+            client
+                .bitpos_range("ones", false, 0, Some(-1), BitUnit::Byte)
+                .unwrap(),
+            -1
+        );
+
+        assert_eq!(client.bitpos("missing", true).unwrap(), -1);
+        assert_eq!(client.bitpos("missing", false).unwrap(), 0);
+
+        let result: Result<i64, _> = cmd("BITPOS").arg("key").arg(2).query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_bitop() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("a", vec![0xf0u8, 0x0f]).unwrap();
+        let _: () = client.set("b", vec![0xffu8]).unwrap();
+
+        assert_eq!(client.bit_and::<_, _, i64>("dest", ["a", "b"]).unwrap(), 2);
+        let value: Vec<u8> = client.get("dest").unwrap();
+        assert_eq!(value, vec![0xf0, 0x00]);
+
+        assert_eq!(client.bit_or::<_, _, i64>("dest", ["a", "b"]).unwrap(), 2);
+        let value: Vec<u8> = client.get("dest").unwrap();
+        assert_eq!(value, vec![0xff, 0x0f]);
+
+        assert_eq!(
+            client
+                .bit_xor::<_, _, i64>("dest", ["a", "b", "missing"])
+                .unwrap(),
+            2
+        );
+        let value: Vec<u8> = client.get("dest").unwrap();
+        assert_eq!(value, vec![0x0f, 0x0f]);
+
+        assert_eq!(client.bit_not::<_, _, i64>("dest", "a").unwrap(), 2);
+        let value: Vec<u8> = client.get("dest").unwrap();
+        assert_eq!(value, vec![0x0f, 0xf0]);
+    }
+
+    #[test]
+    fn test_bitop_empty_result_deletes_dest() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("dest", "x").unwrap();
+        assert_eq!(
+            client
+                .bit_or::<_, _, i64>("dest", ["missing1", "missing2"])
+                .unwrap(),
+            0
+        );
+        assert_eq!(client.exists::<_, i64>("dest").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_bitop_not_takes_one_source() {
+        let mut client = blocking::Client::new();
+        let result: Result<usize, _> = cmd("BITOP")
+            .arg("NOT")
+            .arg("dest")
+            .arg("a")
+            .arg("b")
+            .query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        let result: Result<usize, _> = cmd("BITOP")
+            .arg("NAND")
+            .arg("dest")
+            .arg("a")
+            .query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_bitmap_errors() {
+        let mut client = blocking::Client::new();
+        let _: () = client.sadd("set", "a").unwrap();
+        assert!(matches!(
+            client.setbit("set", 0, true),
+            Err(RedisError::WrongType)
+        ));
+        assert!(matches!(
+            client.getbit("set", 0),
+            Err(RedisError::WrongType)
+        ));
+        assert!(matches!(client.bitcount("set"), Err(RedisError::WrongType)));
+        assert!(matches!(
+            client.bit_and::<_, _, i64>("dest", ["set"]),
+            Err(RedisError::WrongType)
+        ));
+
+        assert!(matches!(
+            client.setbit("key", 1 << 32, true),
+            Err(RedisError::InvalidArgument(_))
+        ));
+        let result: Result<i64, _> = cmd("SETBIT").arg("key").arg(0).arg(2).query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_bitmap_on_integer_value() {
+        let mut client = blocking::Client::new();
+        let _: () = client.incr("n", 1).unwrap();
+        // "1" is 0x31.
+        assert_eq!(client.bitcount("n").unwrap(), 3);
+        client.setbit("n", 6, true).unwrap();
+        let value: String = client.get("n").unwrap();
+        assert_eq!(value, "3");
+    }
+
+    #[test]
+    fn test_bitfield_get_set_incrby() {
+        let mut client = blocking::Client::new();
+        let ops = BitFieldOps::default()
+            .set(BitFieldType::unsigned(8), BitFieldOffset::Bit(0), 255)
+            .get(BitFieldType::signed(8), BitFieldOffset::Bit(0))
+            .incr_by(BitFieldType::signed(5), BitFieldOffset::Bit(100), 1)
+            .get(BitFieldType::unsigned(4), BitFieldOffset::Bit(0));
+        let replies = client.bitfield("key", &ops).unwrap();
+        assert_eq!(replies, vec![Some(0), Some(-1), Some(1), Some(15)]);
+        // The string grows to cover bit 104.
+        assert_eq!(client.strlen::<_, i64>("key").unwrap(), 14);
+
+        let ops = BitFieldOps::default()
+            .set(BitFieldType::unsigned(8), BitFieldOffset::Index(1), 0x61)
+            .get(BitFieldType::unsigned(16), BitFieldOffset::Bit(0));
+        let replies = client.bitfield("key", &ops).unwrap();
+        assert_eq!(replies, vec![Some(0), Some(0xff61)]);
+        let value: Vec<u8> = client.getrange("key", 0, 1).unwrap();
+        assert_eq!(value, vec![0xff, 0x61]);
+    }
+
+    #[test]
+    fn test_bitfield_wide_fields() {
+        let mut client = blocking::Client::new();
+        let ops = BitFieldOps::default()
+            .set(BitFieldType::signed(64), BitFieldOffset::Bit(3), i64::MIN)
+            .get(BitFieldType::signed(64), BitFieldOffset::Bit(3))
+            .incr_by(BitFieldType::signed(64), BitFieldOffset::Bit(3), -1)
+            .set(
+                BitFieldType::unsigned(63),
+                BitFieldOffset::Index(2),
+                i64::MAX,
+            )
+            .get(BitFieldType::unsigned(63), BitFieldOffset::Bit(126));
+        let replies = client.bitfield("key", &ops).unwrap();
+        assert_eq!(
+            replies,
+            vec![
+                Some(0),
+                Some(i64::MIN),
+                Some(i64::MAX),
+                Some(0),
+                Some(i64::MAX)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bitfield_overflow() {
+        let mut client = blocking::Client::new();
+        let u2 = BitFieldType::unsigned(2);
+        let i8 = BitFieldType::signed(8);
+        let ops = BitFieldOps::default()
+            .incr_by(u2, BitFieldOffset::Bit(0), 5)
+            .overflow(BitFieldOverflow::Sat)
+            .incr_by(u2, BitFieldOffset::Bit(0), 5)
+            .incr_by(i8, BitFieldOffset::Bit(8), -200)
+            .overflow(BitFieldOverflow::Fail)
+            .incr_by(u2, BitFieldOffset::Bit(0), 1)
+            .set(i8, BitFieldOffset::Bit(8), 128)
+            .overflow(BitFieldOverflow::Wrap)
+            .incr_by(i8, BitFieldOffset::Bit(8), -1)
+            .set(u2, BitFieldOffset::Bit(0), -1);
+        let replies = client.bitfield("key", &ops).unwrap();
+        assert_eq!(
+            replies,
+            vec![Some(1), Some(3), Some(-128), None, None, Some(127), Some(3)]
+        );
+        let value: Vec<u8> = client.get("key").unwrap();
+        assert_eq!(value, vec![0xc0, 0x7f]);
+    }
+
+    #[test]
+    fn test_bitfield_read_only() {
+        let mut client = blocking::Client::new();
+        let gets = BitFieldOps::default().get(BitFieldType::unsigned(8), BitFieldOffset::Bit(0));
+        assert_eq!(client.bitfield_ro("key", &gets).unwrap(), vec![Some(0)]);
+        assert_eq!(client.bitfield("key", &gets).unwrap(), vec![Some(0)]);
+        // Reads never create the key.
+        assert_eq!(client.exists::<_, i64>("key").unwrap(), 0);
+
+        let _: () = client.set("key", "a").unwrap();
+        assert_eq!(client.bitfield_ro("key", &gets).unwrap(), vec![Some(0x61)]);
+        let write = gets.incr_by(BitFieldType::unsigned(8), BitFieldOffset::Bit(0), 1);
+        assert!(matches!(
+            client.bitfield_ro("key", &write),
+            Err(RedisError::InvalidArgument(_))
+        ));
+        let value: String = client.get("key").unwrap();
+        assert_eq!(value, "a");
+    }
+
+    #[test]
+    fn test_bitfield_command_parsing() {
+        let mut client = blocking::Client::new();
+        let reply: Value = cmd("BITFIELD")
+            .arg("key")
+            .arg("set")
+            .arg("i8")
+            .arg("#1")
+            .arg(-2)
+            .arg("overflow")
+            .arg("fail")
+            .arg("incrby")
+            .arg("I8")
+            .arg(8)
+            .arg(-127)
+            .arg("get")
+            .arg("u16")
+            .arg(0)
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(
+            reply,
+            Value::Array(vec![Value::Int(0), Value::Null, Value::Int(0x00fe)])
+        );
+        let reply: Value = cmd("BITFIELD").arg("key").query(&mut client).unwrap();
+        assert_eq!(reply, Value::Array(vec![]));
+
+        let invalid: &[&[&str]] = &[
+            &["GET", "u64", "0"],
+            &["GET", "i65", "0"],
+            &["GET", "i0", "0"],
+            &["GET", "x8", "0"],
+            &["GET", "i8", "-1"],
+            &["GET", "i8", "#-1"],
+            &["GET", "i8", "4294967289"],
+            &["GET", "i8"],
+            &["SET", "i8", "0", "x"],
+            &["OVERFLOW", "MAYBE"],
+            &["DECRBY", "i8", "0", "1"],
+        ];
+        for args in invalid {
+            let result: Result<Value, _> = cmd("BITFIELD").arg("key").arg(*args).query(&mut client);
+            assert!(
+                matches!(result, Err(RedisError::InvalidArgument(_))),
+                "{args:?}"
+            );
+        }
+        let result: Result<Value, _> = cmd("BITFIELD_RO")
+            .arg("key")
+            .arg(["SET", "i8", "0", "1"])
+            .query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        // Nothing was applied by the failing calls.
+        let value: Vec<u8> = client.get("key").unwrap();
+        assert_eq!(value, vec![0x00, 0xfe]);
+    }
+
+    #[test]
+    fn test_bitfield_wrong_type() {
+        let mut client = blocking::Client::new();
+        let _: () = client.sadd("set", "a").unwrap();
+        let ops = BitFieldOps::default().get(BitFieldType::unsigned(8), BitFieldOffset::Bit(0));
+        assert!(matches!(
+            client.bitfield("set", &ops),
+            Err(RedisError::WrongType)
+        ));
+        let ops = ops.set(BitFieldType::unsigned(8), BitFieldOffset::Bit(0), 1);
+        assert!(matches!(
+            client.bitfield("set", &ops),
+            Err(RedisError::WrongType)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_async_bitmap() {
+        let mut client = Client::new();
+        assert!(!client.setbit("key", 3, true).await.unwrap());
+        assert!(client.getbit("key", 3).await.unwrap());
+        assert_eq!(client.bitcount("key").await.unwrap(), 1);
+        assert_eq!(client.bitpos("key", true).await.unwrap(), 3);
+        assert_eq!(client.bit_not::<_, _, i64>("dest", "key").await.unwrap(), 1);
+        assert_eq!(client.bitcount("dest").await.unwrap(), 7);
+        let ops =
+            BitFieldOps::default().incr_by(BitFieldType::signed(4), BitFieldOffset::Bit(0), 3);
+        assert_eq!(client.bitfield("key", &ops).await.unwrap(), vec![Some(4)]);
+    }
+}
+
+mod hash_field_expiration_tests {
+    use super::{client_with_clock, now_millis};
+    use not_redis::{
+        blocking, cmd, Client, Commands, ExpireOption, Expiry, FieldExistenceCheck,
+        HashFieldExpirationOptions, RedisError, SetExpiry, Value,
+    };
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_hexpire_and_httl() {
+        let mut client = Client::new();
+        let _: () = client.hset("h", "a", "1").await.unwrap();
+        let _: () = client.hset("h", "b", "2").await.unwrap();
+
+        let replies: Vec<i64> = client
+            .hexpire("h", 100, ExpireOption::NONE, ["a", "missing"])
+            .await
+            .unwrap();
+        assert_eq!(replies, vec![1, -2]);
+        assert_eq!(
+            client
+                .httl::<_, _, Vec<i64>>("h", ["a", "b", "missing"])
+                .await
+                .unwrap(),
+            vec![100, -1, -2]
+        );
+        assert_eq!(
+            client
+                .hpersist::<_, _, Vec<i64>>("h", ["a", "b", "missing"])
+                .await
+                .unwrap(),
+            vec![1, -1, -2]
+        );
+        assert_eq!(
+            client.httl::<_, _, Vec<i64>>("h", "a").await.unwrap(),
+            vec![-1]
+        );
+        assert_eq!(
+            client
+                .httl::<_, _, Vec<i64>>("nokey", ["a", "b"])
+                .await
+                .unwrap(),
+            vec![-2, -2]
+        );
+    }
+
+    #[test]
+    fn test_field_expires_exactly_at_deadline() {
+        let (mut client, clock) = client_with_clock();
+        let _: () = client.hset("h", "a", "1").unwrap();
+        let _: () = client.hset("h", "b", "2").unwrap();
+        let _: () = client
+            .hpexpire("h", 10_000, ExpireOption::NONE, "a")
+            .unwrap();
+
+        // HTTL rounds to the nearest second, as TTL does.
+        clock.advance(Duration::from_millis(4500));
+        assert_eq!(client.httl::<_, _, Vec<i64>>("h", "a").unwrap(), vec![6]);
+        assert_eq!(
+            client.hpttl::<_, _, Vec<i64>>("h", "a").unwrap(),
+            vec![5500]
+        );
+
+        clock.advance(Duration::from_millis(5500) - Duration::from_nanos(1));
+        assert!(client.hexists::<_, _, bool>("h", "a").unwrap());
+        clock.advance(Duration::from_nanos(1));
+        let value: Option<String> = client.hget("h", "a").unwrap();
+        assert_eq!(value, None);
+        assert!(!client.hexists::<_, _, bool>("h", "a").unwrap());
+        assert_eq!(client.hlen::<_, i64>("h").unwrap(), 1);
+        let all: Vec<String> = client.hgetall("h").unwrap();
+        assert_eq!(all, vec!["b", "2"]);
+        assert_eq!(client.httl::<_, _, Vec<i64>>("h", "a").unwrap(), vec![-2]);
+        // An expired field counts as new when it is set again.
+        assert_eq!(client.hset::<_, _, _, i64>("h", "a", "3").unwrap(), 1);
+        assert_eq!(client.httl::<_, _, Vec<i64>>("h", "a").unwrap(), vec![-1]);
+    }
+
+    #[test]
+    fn test_hash_is_deleted_when_its_last_field_expires() {
+        let (mut client, clock) = client_with_clock();
+        let _: () = client.hset("h", "a", "1").unwrap();
+        let _: () = client.set("other", "v").unwrap();
+        let _: () = client.hpexpire("h", 100, ExpireOption::NONE, "a").unwrap();
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(client.exists::<_, i64>("h").unwrap(), 0);
+        let kind: String = cmd("TYPE").arg("h").query(&mut client).unwrap();
+        assert_eq!(kind, "none");
+        assert_eq!(client.dbsize::<i64>().unwrap(), 1);
+        let keys: Vec<String> = cmd("KEYS").arg("*").query(&mut client).unwrap();
+        assert_eq!(keys, vec!["other"]);
+        let result: Result<Value, _> = cmd("RENAME").arg(["h", "h2"]).query(&mut client);
+        assert!(result.is_err());
+        assert_eq!(client.lpush::<_, _, i64>("h2", "x").unwrap(), 1);
+        // A write to the key starts a new hash.
+        assert_eq!(client.hset::<_, _, _, i64>("h", "b", "2").unwrap(), 1);
+        assert_eq!(client.hlen::<_, i64>("h").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_hexpire_conditions() {
+        let (mut client, clock) = client_with_clock();
+        let _: () = client.hset("h", "f", "v").unwrap();
+
+        let set = |client: &mut blocking::Client, secs, option| {
+            let ttls: Vec<i64> = client.hexpire("h", secs, option, "f").unwrap();
+            ttls[0]
+        };
+        assert_eq!(set(&mut client, 100, ExpireOption::XX), 0);
+        assert_eq!(set(&mut client, 100, ExpireOption::GT), 0);
+        assert_eq!(set(&mut client, 100, ExpireOption::NX), 1);
+        assert_eq!(set(&mut client, 200, ExpireOption::NX), 0);
+        assert_eq!(set(&mut client, 50, ExpireOption::GT), 0);
+        assert_eq!(set(&mut client, 200, ExpireOption::GT), 1);
+        assert_eq!(set(&mut client, 300, ExpireOption::LT), 0);
+        assert_eq!(set(&mut client, 150, ExpireOption::LT), 1);
+        assert_eq!(client.httl::<_, _, Vec<i64>>("h", "f").unwrap(), vec![150]);
+
+        let at = now_millis(&clock) + 60_000;
+        assert_eq!(
+            client
+                .hpexpire_at::<_, _, Vec<i64>>("h", at, ExpireOption::NONE, "f")
+                .unwrap(),
+            vec![1]
+        );
+        assert_eq!(
+            client.hpexpire_time::<_, _, Vec<i64>>("h", "f").unwrap(),
+            vec![at]
+        );
+        assert_eq!(
+            client.hexpire_time::<_, _, Vec<i64>>("h", "f").unwrap(),
+            vec![(at + 500) / 1000]
+        );
+
+        // A deadline that has passed deletes the field.
+        assert_eq!(set(&mut client, 0, ExpireOption::NONE), 2);
+        assert!(!client.hexists::<_, _, bool>("h", "f").unwrap());
+        let result: Result<Vec<i64>, _> = client.hexpire("h", -1, ExpireOption::NONE, "f");
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_field_ttl_survives_increments_but_not_hset() {
+        let (mut client, _clock) = client_with_clock();
+        let _: () = client.hset("h", "n", "1").unwrap();
+        let _: () = client.hset("h", "f", "v").unwrap();
+        let _: () = client
+            .hexpire("h", 100, ExpireOption::NONE, ["n", "f"])
+            .unwrap();
+
+        assert_eq!(client.hincr::<_, _, _, i64>("h", "n", 1).unwrap(), 2);
+        let _: () = client.hset("h", "f", "w").unwrap();
+        assert_eq!(
+            client.httl::<_, _, Vec<i64>>("h", ["n", "f"]).unwrap(),
+            vec![100, -1]
+        );
+    }
+
+    #[test]
+    fn test_hget_ex() {
+        let (mut client, clock) = client_with_clock();
+        let _: () = client
+            .hset_multiple("h", &[("a", "1"), ("b", "2")])
+            .unwrap();
+
+        let values: Vec<Option<String>> = client
+            .hget_ex("h", ["a", "missing"], Expiry::EX(10))
+            .unwrap();
+        assert_eq!(values, vec![Some("1".to_string()), None]);
+        assert_eq!(
+            client.httl::<_, _, Vec<i64>>("h", ["a", "b"]).unwrap(),
+            vec![10, -1]
+        );
+
+        let values: Vec<Option<String>> = client.hget_ex("h", "a", Expiry::PERSIST).unwrap();
+        assert_eq!(values, vec![Some("1".to_string())]);
+        assert_eq!(client.httl::<_, _, Vec<i64>>("h", "a").unwrap(), vec![-1]);
+
+        let past = (now_millis(&clock) - 1) as u64;
+        let values: Vec<Option<String>> = client.hget_ex("h", "b", Expiry::PXAT(past)).unwrap();
+        assert_eq!(values, vec![Some("2".to_string())]);
+        assert!(!client.hexists::<_, _, bool>("h", "b").unwrap());
+
+        let values: Vec<Option<String>> =
+            client.hget_ex("nokey", ["a", "b"], Expiry::EX(10)).unwrap();
+        assert_eq!(values, vec![None, None]);
+        let result: Result<Vec<Option<String>>, _> = client.hget_ex("h", "a", Expiry::EX(0));
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_hset_ex() {
+        let (mut client, _clock) = client_with_clock();
+        let fnx = HashFieldExpirationOptions::default()
+            .set_existence_check(FieldExistenceCheck::FNX)
+            .set_expiration(SetExpiry::EX(10));
+        let fxx = HashFieldExpirationOptions::default()
+            .set_existence_check(FieldExistenceCheck::FXX)
+            .set_expiration(SetExpiry::KEEPTTL);
+
+        assert!(!client
+            .hset_ex::<_, _, _, bool>("h", &fxx, &[("a", "1")])
+            .unwrap());
+        assert_eq!(client.exists::<_, i64>("h").unwrap(), 0);
+        assert!(client
+            .hset_ex::<_, _, _, bool>("h", &fnx, &[("a", "1")])
+            .unwrap());
+        assert!(!client
+            .hset_ex::<_, _, _, bool>("h", &fnx, &[("a", "2"), ("b", "2")])
+            .unwrap());
+        assert!(!client
+            .hset_ex::<_, _, _, bool>("h", &fxx, &[("a", "2"), ("b", "2")])
+            .unwrap());
+
+        assert!(client
+            .hset_ex::<_, _, _, bool>("h", &fxx, &[("a", "2")])
+            .unwrap());
+        let value: String = client.hget("h", "a").unwrap();
+        assert_eq!(value, "2");
+        assert_eq!(client.httl::<_, _, Vec<i64>>("h", "a").unwrap(), vec![10]);
+
+        // Without an expiration the fields' TTL is cleared.
+        let plain = HashFieldExpirationOptions::default();
+        assert!(client
+            .hset_ex::<_, _, _, bool>("h", &plain, &[("a", "3"), ("b", "3")])
+            .unwrap());
+        assert_eq!(
+            client.httl::<_, _, Vec<i64>>("h", ["a", "b"]).unwrap(),
+            vec![-1, -1]
+        );
+    }
+
+    #[test]
+    fn test_hash_field_expiration_cmd_parsing() {
+        let mut client = blocking::Client::new();
+        let set: i64 = cmd("HSETEX")
+            .arg("h")
+            .arg("fnx")
+            .arg("px")
+            .arg(10_000)
+            .arg("fields")
+            .arg(2)
+            .arg(["a", "1", "b", "2"])
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(set, 1);
+        let ttls: Vec<i64> = cmd("HPTTL")
+            .arg("h")
+            .arg("FIELDS")
+            .arg(2)
+            .arg(["a", "b"])
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(ttls.len(), 2);
+        assert!(ttls.iter().all(|&ttl| ttl > 9_000 && ttl <= 10_000));
+        let reply: Value = cmd("HGETEX")
+            .arg("h")
+            .arg("PERSIST")
+            .arg("FIELDS")
+            .arg(1)
+            .arg("a")
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(reply, Value::Array(vec![Value::String(b"1".to_vec())]));
+
+        for args in [
+            &["h", "10", "a"][..],
+            &["h", "10", "FIELDS", "0", "a"],
+            &["h", "10", "FIELDS", "2", "a"],
+            &["h", "10", "NX", "XX", "FIELDS", "1", "a"],
+        ] {
+            let result: Result<Value, _> = cmd("HEXPIRE").arg(args).query(&mut client);
+            assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        }
+        let result: Result<Value, _> = cmd("HTTL")
+            .arg(["h", "NX", "FIELDS", "1", "a"])
+            .query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+        let result: Result<Value, _> = cmd("HSETEX")
+            .arg(["h", "FIELDS", "2", "a", "1"])
+            .query(&mut client);
+        assert!(matches!(result, Err(RedisError::InvalidArgument(_))));
+
+        let _: () = client.set("s", "v").unwrap();
+        let result: Result<Vec<i64>, _> = client.httl("s", "a");
+        assert!(matches!(result, Err(RedisError::WrongType)));
+    }
+}
+
+mod empty_collection_tests {
+    use not_redis::{blocking, cmd, Commands, ExpireOption};
+
+    fn assert_deleted(client: &mut blocking::Client, key: &str) {
+        assert_eq!(client.exists::<_, i64>(key).unwrap(), 0);
+        let key_type: String = Commands::key_type(client, key).unwrap();
+        assert_eq!(key_type, "none");
+    }
+
+    #[test]
+    fn test_hdel_of_last_field_deletes_key() {
+        let mut client = blocking::Client::new();
+        let _: () = client
+            .hset_multiple("h", &[("a", "1"), ("b", "2")])
+            .unwrap();
+        client.expire("h", 100).unwrap();
+        let _: () = client.hdel("h", ["a", "b"]).unwrap();
+        assert_deleted(&mut client, "h");
+        assert_eq!(client.dbsize::<i64>().unwrap(), 0);
+
+        // The old deadline does not carry over to a new hash.
+        let _: () = client.hset("h", "a", "1").unwrap();
+        assert_eq!(client.ttl("h").unwrap().raw(), -1);
+    }
+
+    #[test]
+    fn test_expiring_last_field_deletes_key() {
+        let mut client = blocking::Client::new();
+        let _: () = client.hset("h", "a", "1").unwrap();
+        assert_eq!(
+            client
+                .hexpire::<_, _, Vec<i64>>("h", 0, ExpireOption::NONE, "a")
+                .unwrap(),
+            vec![2]
+        );
+        assert_deleted(&mut client, "h");
+    }
+
+    #[test]
+    fn test_removing_last_member_deletes_set_and_zset() {
+        let mut client = blocking::Client::new();
+        let _: () = client.sadd("s", "a").unwrap();
+        let removed: i64 = cmd("SREM").arg("s").arg("a").query(&mut client).unwrap();
+        assert_eq!(removed, 1);
+        assert_deleted(&mut client, "s");
+
+        let _: () = client.sadd("s", "a").unwrap();
+        let popped: Vec<String> = cmd("SPOP").arg("s").arg(5).query(&mut client).unwrap();
+        assert_eq!(popped, vec!["a"]);
+        assert_deleted(&mut client, "s");
+
+        let _: i64 = cmd("ZADD")
+            .arg("z")
+            .arg(1)
+            .arg("a")
+            .query(&mut client)
+            .unwrap();
+        let removed: i64 = cmd("ZREM").arg("z").arg("a").query(&mut client).unwrap();
+        assert_eq!(removed, 1);
+        assert_deleted(&mut client, "z");
+        assert_eq!(client.dbsize::<i64>().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_emptying_stream_deletes_key() {
+        let mut client = blocking::Client::new();
+        let _: () = client.xadd("x", Some("1-0"), vec![("k", "v")]).unwrap();
+        let _: () = client.xadd("x", Some("2-0"), vec![("k", "v")]).unwrap();
+        assert_eq!(client.xdel::<_, i64>("x", vec!["1-0"]).unwrap(), 1);
+        assert_eq!(client.exists::<_, i64>("x").unwrap(), 1);
+        assert_eq!(client.xtrim::<_, i64>("x", 0, false).unwrap(), 1);
+        assert_deleted(&mut client, "x");
+    }
+}
+
+mod list_command_tests {
+    use not_redis::{blocking, cmd, Client, Commands, LposOptions, RedisError};
+
+    fn letters() -> blocking::Client {
+        let mut client = blocking::Client::new();
+        let _: () = client.rpush("l", ["a", "b", "c", "b", "a"]).unwrap();
+        client
+    }
+
+    #[test]
+    fn test_variadic_push_order() {
+        let mut client = blocking::Client::new();
+        assert_eq!(client.lpush::<_, _, i64>("l", ["a", "b", "c"]).unwrap(), 3);
+        assert_eq!(client.rpush::<_, _, i64>("l", ["d", "e"]).unwrap(), 5);
+        let all: Vec<String> = client.lrange("l", 0, -1).unwrap();
+        assert_eq!(all, vec!["c", "b", "a", "d", "e"]);
+    }
+
+    #[test]
+    fn test_pushx_requires_existing_list() {
+        let mut client = blocking::Client::new();
+        assert_eq!(
+            client.lpush_exists::<_, _, i64>("l", ["a", "b"]).unwrap(),
+            0
+        );
+        assert_eq!(client.rpush_exists::<_, _, i64>("l", "a").unwrap(), 0);
+        assert_eq!(client.exists::<_, i64>("l").unwrap(), 0);
+
+        let _: () = client.rpush("l", "x").unwrap();
+        assert_eq!(
+            client.lpush_exists::<_, _, i64>("l", ["a", "b"]).unwrap(),
+            3
+        );
+        assert_eq!(client.rpush_exists::<_, _, i64>("l", "z").unwrap(), 4);
+        let all: Vec<String> = client.lrange("l", 0, -1).unwrap();
+        assert_eq!(all, vec!["b", "a", "x", "z"]);
+    }
+
+    #[test]
+    fn test_pop_with_and_without_count() {
+        let mut client = letters();
+        let first: String = client.lpop("l").unwrap();
+        assert_eq!(first, "a");
+        let last: Option<String> = client.rpop("l").unwrap();
+        assert_eq!(last.as_deref(), Some("a"));
+        let head: Vec<String> = client.lpop_count("l", 2).unwrap();
+        assert_eq!(head, vec!["b", "c"]);
+        let none: Vec<String> = client.rpop_count("l", 0).unwrap();
+        assert!(none.is_empty());
+        let rest: Vec<String> = client.rpop_count("l", 10).unwrap();
+        assert_eq!(rest, vec!["b"]);
+        assert_eq!(client.exists::<_, i64>("l").unwrap(), 0);
+
+        let missing: Option<String> = client.lpop("l").unwrap();
+        assert_eq!(missing, None);
+        let missing: Option<Vec<String>> = client.lpop_count("l", 2).unwrap();
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn test_lrange_and_lindex_negative_indexes() {
+        let mut client = letters();
+        let tail: Vec<String> = client.lrange("l", -2, -1).unwrap();
+        assert_eq!(tail, vec!["b", "a"]);
+        let clamped: Vec<String> = client.lrange("l", -100, 1).unwrap();
+        assert_eq!(clamped, vec!["a", "b"]);
+        let empty: Vec<String> = client.lrange("l", 3, 1).unwrap();
+        assert!(empty.is_empty());
+        let missing: Vec<String> = client.lrange("nope", 0, -1).unwrap();
+        assert!(missing.is_empty());
+
+        let c: String = client.lindex("l", 2).unwrap();
+        assert_eq!(c, "c");
+        let last: String = client.lindex("l", -1).unwrap();
+        assert_eq!(last, "a");
+        let out: Option<String> = client.lindex("l", 5).unwrap();
+        assert_eq!(out, None);
+        let out: Option<String> = client.lindex("l", -6).unwrap();
+        assert_eq!(out, None);
+    }
+
+    #[test]
+    fn test_lset() {
+        let mut client = letters();
+        let _: () = client.lset("l", -1, "z").unwrap();
+        let _: () = client.lset("l", 0, "y").unwrap();
+        let all: Vec<String> = client.lrange("l", 0, -1).unwrap();
+        assert_eq!(all, vec!["y", "b", "c", "b", "z"]);
+
+        assert!(matches!(
+            client.lset::<_, _, i64>("l", 5, "x"),
+            Err(RedisError::InvalidArgument(msg)) if msg == "index out of range"
+        ));
+        assert!(matches!(
+            client.lset::<_, _, ()>("nope", 0, "x"),
+            Err(RedisError::NoSuchKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_linsert() {
+        let mut client = letters();
+        assert_eq!(
+            client
+                .linsert_before::<_, _, _, i64>("l", "b", "x")
+                .unwrap(),
+            6
+        );
+        assert_eq!(
+            client.linsert_after::<_, _, _, i64>("l", "c", "y").unwrap(),
+            7
+        );
+        assert_eq!(
+            client.linsert_after::<_, _, _, i64>("l", "q", "y").unwrap(),
+            -1
+        );
+        assert_eq!(
+            client
+                .linsert_before::<_, _, _, i64>("nope", "a", "y")
+                .unwrap(),
+            0
+        );
+        let all: Vec<String> = client.lrange("l", 0, -1).unwrap();
+        assert_eq!(all, vec!["a", "x", "b", "c", "y", "b", "a"]);
+
+        let result: Result<i64, _> = cmd("LINSERT")
+            .arg("l")
+            .arg("AROUND")
+            .arg("a")
+            .arg("b")
+            .query(&mut client);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_lrem_directions() {
+        let mut client = blocking::Client::new();
+        let _: () = client.rpush("l", ["a", "x", "a", "x", "a"]).unwrap();
+        assert_eq!(client.lrem::<_, _, i64>("l", 1, "a").unwrap(), 1);
+        let all: Vec<String> = client.lrange("l", 0, -1).unwrap();
+        assert_eq!(all, vec!["x", "a", "x", "a"]);
+
+        assert_eq!(client.lrem::<_, _, i64>("l", -1, "x").unwrap(), 1);
+        let all: Vec<String> = client.lrange("l", 0, -1).unwrap();
+        assert_eq!(all, vec!["x", "a", "a"]);
+
+        assert_eq!(client.lrem::<_, _, i64>("l", 0, "a").unwrap(), 2);
+        assert_eq!(client.lrem::<_, _, i64>("l", 0, "x").unwrap(), 1);
+        assert_eq!(client.exists::<_, i64>("l").unwrap(), 0);
+        assert_eq!(client.lrem::<_, _, i64>("l", 0, "x").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_ltrim() {
+        let mut client = letters();
+        let _: () = client.ltrim("l", 1, -2).unwrap();
+        let all: Vec<String> = client.lrange("l", 0, -1).unwrap();
+        assert_eq!(all, vec!["b", "c", "b"]);
+
+        let _: () = client.ltrim("l", -1, 100).unwrap();
+        let all: Vec<String> = client.lrange("l", 0, -1).unwrap();
+        assert_eq!(all, vec!["b"]);
+
+        let _: () = client.ltrim("l", 5, 10).unwrap();
+        assert_eq!(client.exists::<_, i64>("l").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_lpos_options() {
+        let mut client = letters();
+        let first: i64 = client.lpos("l", "b", LposOptions::default()).unwrap();
+        assert_eq!(first, 1);
+        let second: i64 = client
+            .lpos("l", "b", LposOptions::default().rank(2))
+            .unwrap();
+        assert_eq!(second, 3);
+        let last: i64 = client
+            .lpos("l", "a", LposOptions::default().rank(-1))
+            .unwrap();
+        assert_eq!(last, 4);
+        let none: Option<i64> = client
+            .lpos("l", "b", LposOptions::default().rank(3))
+            .unwrap();
+        assert_eq!(none, None);
+
+        let all: Vec<i64> = client
+            .lpos("l", "b", LposOptions::default().count(0))
+            .unwrap();
+        assert_eq!(all, vec![1, 3]);
+        let from_tail: Vec<i64> = client
+            .lpos("l", "a", LposOptions::default().rank(-1).count(2))
+            .unwrap();
+        assert_eq!(from_tail, vec![4, 0]);
+        let bounded: Vec<i64> = client
+            .lpos("l", "b", LposOptions::default().count(0).maxlen(3))
+            .unwrap();
+        assert_eq!(bounded, vec![1]);
+        let bounded: Vec<i64> = client
+            .lpos("l", "b", LposOptions::default().rank(-1).count(0).maxlen(2))
+            .unwrap();
+        assert_eq!(bounded, vec![3]);
+
+        let missing: Vec<i64> = client
+            .lpos("nope", "b", LposOptions::default().count(1))
+            .unwrap();
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn test_lpos_rejects_bad_options() {
+        let mut client = letters();
+        let zero: Result<i64, _> = client.lpos("l", "a", LposOptions::default().rank(0));
+        assert!(matches!(
+            zero,
+            Err(RedisError::InvalidArgument(msg)) if msg.starts_with("RANK can't be zero")
+        ));
+        for (opt, msg) in [
+            ("COUNT", "COUNT can't be negative"),
+            ("MAXLEN", "MAXLEN can't be negative"),
+        ] {
+            let result: Result<i64, _> = cmd("LPOS")
+                .arg("l")
+                .arg("a")
+                .arg(opt)
+                .arg(-1)
+                .query(&mut client);
+            assert!(matches!(result, Err(RedisError::InvalidArgument(m)) if m == msg));
+        }
+        let result: Result<i64, _> = cmd("LPOS").arg("l").arg("a").arg("RANK").query(&mut client);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_list_commands_by_name() {
+        let mut client = blocking::Client::new();
+        let len: i64 = cmd("RPUSH")
+            .arg("l")
+            .arg("a")
+            .arg("b")
+            .arg("c")
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(len, 3);
+        let popped: Vec<String> = cmd("LPOP").arg("l").arg(2).query(&mut client).unwrap();
+        assert_eq!(popped, vec!["a", "b"]);
+        let result: Result<Vec<String>, _> = cmd("LPOP").arg("l").arg(-1).query(&mut client);
+        assert!(result.is_err());
+        let pos: i64 = cmd("LPOS")
+            .arg("l")
+            .arg("c")
+            .arg("maxlen")
+            .arg(0)
+            .arg("rank")
+            .arg(1)
+            .query(&mut client)
+            .unwrap();
+        assert_eq!(pos, 0);
+    }
+
+    #[test]
+    fn test_list_commands_on_wrong_type() {
+        let mut client = blocking::Client::new();
+        let _: () = client.set("s", "v").unwrap();
+        assert!(matches!(
+            client.rpush::<_, _, i64>("s", "a"),
+            Err(RedisError::WrongType)
+        ));
+        assert!(matches!(
+            client.lpush_exists::<_, _, ()>("s", "a"),
+            Err(RedisError::WrongType)
+        ));
+        let result: Result<Vec<String>, _> = client.lrange("s", 0, -1);
+        assert!(matches!(result, Err(RedisError::WrongType)));
+        let result: Result<i64, _> = client.lpos("s", "v", LposOptions::default());
+        assert!(matches!(result, Err(RedisError::WrongType)));
+        assert!(matches!(
+            client.ltrim::<_, ()>("s", 0, 1),
+            Err(RedisError::WrongType)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_async_list_commands() {
+        let mut client = Client::new();
+        let _: () = client.rpush("l", vec!["a", "b", "c"]).await.unwrap();
+        let _: () = client.lset("l", 1, "x").await.unwrap();
+        let all: Vec<String> = client.lrange("l", 0, -1).await.unwrap();
+        assert_eq!(all, vec!["a", "x", "c"]);
+        let popped: Vec<String> = client.rpop_count("l", 2).await.unwrap();
+        assert_eq!(popped, vec!["c", "x"]);
+    }
+}